@@ -31,6 +31,8 @@ typedef enum {
     OCKAM_VAULT_SECRET_TYPE_AES256_KEY,
     OCKAM_VAULT_SECRET_TYPE_CURVE25519_PRIVATEKEY,
     OCKAM_VAULT_SECRET_TYPE_P256_PRIVATEKEY,
+    OCKAM_VAULT_SECRET_TYPE_ED25519_PRIVATEKEY,
+    OCKAM_VAULT_SECRET_TYPE_CHACHA20_KEY,
 } ockam_vault_secret_type_t;
 
 /**
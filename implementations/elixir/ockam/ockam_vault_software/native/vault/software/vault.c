@@ -85,6 +85,8 @@ static int parse_secret_attributes(ErlNifEnv *env, ERL_NIF_TERM arg, ockam_vault
     }
 
     attributes->length = length;
+    attributes->usage = OCKAM_VAULT_SECRET_USAGE_ANY;
+    attributes->export_policy = OCKAM_VAULT_SECRET_EXPORTABLE;
 
     return 0;
 }
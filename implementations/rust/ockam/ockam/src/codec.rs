@@ -0,0 +1,24 @@
+use ockam_core::{erased_serde, Codec, CodecError, DecodeFn, Encoded, Result};
+
+/// A BARE (Binary Application Record Encoding) codec
+///
+/// This is the encoding used by the TCP transport, and by the
+/// Elixir and C implementations of Ockam.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bare;
+
+impl Codec for Bare {
+    fn name(&self) -> &'static str {
+        "bare"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Encoded> {
+        serde_bare::to_vec(&value).map_err(|_| CodecError::EncodeFailed.into())
+    }
+
+    fn decode(&self, data: &[u8], f: &mut DecodeFn) -> Result<()> {
+        let mut de = serde_bare::Deserializer::new(data);
+        f(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|_| CodecError::DecodeFailed.into())
+    }
+}
@@ -16,6 +16,10 @@ pub enum OckamError {
     InvalidEventId,
     AttestationRequesterDoesntMatch,
     AttestationNonceDoesntMatch,
+    InvalidKeyType,
+    RecoveredKeyDoesntMatch,
+    FailedLoadProfile,
+    UnsupportedVaultBackend,
 }
 
 impl OckamError {
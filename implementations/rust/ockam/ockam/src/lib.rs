@@ -34,8 +34,29 @@ mod lease;
 pub use credential::*;
 pub use lease::*;
 
+#[cfg(all(feature = "std", feature = "ockam_node"))]
+mod setup;
+#[cfg(all(feature = "std", feature = "ockam_node"))]
+pub use setup::*;
+
+#[cfg(feature = "std")]
+mod codec;
+#[cfg(feature = "std")]
+pub use codec::*;
+#[cfg(feature = "cbor")]
+pub use ockam_core::Cbor;
+#[cfg(feature = "json")]
+pub use ockam_core::Json;
+
 pub use ockam_core::async_trait::async_trait as async_worker;
+
+// Used by the code generated by #[ockam::worker]
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub extern crate serde;
 pub use ockam_core::{
-    Address, Encoded, Error, Message, Result, Route, Routed, RouterMessage, TransportMessage,
-    Worker,
+    AccessControl, Address, AllOf, AllowAll, AllowedProfiles, AnyOf, Bincode, Codec, Encoded,
+    EnvelopeHeader, Error, HopLimitExceeded, LocalInfo, LocalOnly, Message, PayloadKind,
+    RequireAttributes, Result, Route, Routed, RouterMessage, RoutingError, SecureChannelOnly,
+    TransportMessage, Typed, TypedMessage, Worker,
 };
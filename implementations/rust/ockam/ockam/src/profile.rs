@@ -1,9 +1,15 @@
 use crate::OckamError;
-use ockam_vault_core::{Hasher, KeyIdVault, PublicKey, Secret, SecretVault, Signer, Verifier};
+use ockam_vault_core::{
+    Buffer, Hasher, KeyIdVault, PublicKey, Secret, SecretAttributes, SecretDerivationVault,
+    SecretPersistence, SecretSharingVault, SecretType, SecretUsage, SecretVault, Signer, Verifier,
+    CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH, P256_SECRET_LENGTH,
+};
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
 
 mod authentication;
+mod channel;
+pub use channel::*;
 mod contact;
 pub use contact::*;
 mod identifiers;
@@ -15,10 +21,32 @@ use authentication::Authentication;
 pub use change::*;
 use history::ProfileChangeHistory;
 use ockam_core::lib::HashMap;
+use ockam_core::Address;
+
+/// Vault of a [`Profile`], which may be shared with the workers of a node
+pub trait ProfileVault:
+    SecretVault
+    + KeyIdVault
+    + Hasher
+    + Signer
+    + Verifier
+    + SecretSharingVault
+    + SecretDerivationVault
+    + Send
+{
+}
 
-pub trait ProfileVault: SecretVault + KeyIdVault + Hasher + Signer + Verifier {}
-
-impl<D> ProfileVault for D where D: SecretVault + KeyIdVault + Hasher + Signer + Verifier {}
+impl<D> ProfileVault for D where
+    D: SecretVault
+        + KeyIdVault
+        + Hasher
+        + Signer
+        + Verifier
+        + SecretSharingVault
+        + SecretDerivationVault
+        + Send
+{
+}
 
 pub type ProfileEventAttributes = HashMap<String, String>;
 /// Contacts Database
@@ -152,6 +180,7 @@ pub struct Profile {
     change_history: ProfileChangeHistory,
     contacts: ContactsDb,
     vault: Arc<Mutex<dyn ProfileVault>>,
+    seed: Option<Secret>,
 }
 
 impl Profile {
@@ -162,7 +191,10 @@ impl Profile {
     /// Label for key used to issue credentials
     pub const CREDENTIALS_ISSUE: &'static str = "OCKAM_CIK";
     /// Current version of change structure
-    pub const CURRENT_CHANGE_VERSION: u8 = 1;
+    pub const CURRENT_CHANGE_VERSION: u8 = 2;
+    /// Version of change structure which doesn't record the type of keys,
+    /// all of them being Curve25519
+    pub const UNTYPED_KEYS_CHANGE_VERSION: u8 = 1;
 }
 
 impl Profile {
@@ -192,6 +224,7 @@ impl Profile {
             change_history: ProfileChangeHistory::new(change_events),
             contacts,
             vault,
+            seed: None,
         };
 
         profile
@@ -203,6 +236,61 @@ impl Profile {
     pub fn create(
         attributes: Option<ProfileEventAttributes>,
         vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        Self::create_with_key_type(attributes, SecretType::Curve25519, vault)
+    }
+
+    /// Create new [`Profile`] with keys of the given type
+    ///
+    /// Supported types are [`SecretType::Curve25519`], [`SecretType::P256`] and
+    /// [`SecretType::Ed25519`]. Use Ed25519 for change signatures that third party
+    /// tools can verify.
+    /// Keys created later on by this [`Profile`] have the same type as its update key.
+    pub fn create_with_key_type(
+        attributes: Option<ProfileEventAttributes>,
+        key_type: SecretType,
+        vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        let secret_attributes = Self::key_secret_attributes(key_type)?;
+        Self::create_with_optional_seed(attributes, secret_attributes, None, vault)
+    }
+
+    /// Create new [`Profile`] with keys of the given type, whose root key
+    /// can be split with [`Profile::split_root_key`]
+    ///
+    /// The root key of such a [`Profile`] can be exported from the vault,
+    /// as well as the root keys it is rotated into. Other keys can't.
+    pub fn create_recoverable(
+        attributes: Option<ProfileEventAttributes>,
+        key_type: SecretType,
+        vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        let secret_attributes = Self::key_secret_attributes(key_type)?.with_exportable(true);
+        Self::create_with_optional_seed(attributes, secret_attributes, None, vault)
+    }
+
+    /// Create new [`Profile`] with keys derived from a seed
+    ///
+    /// The seed is a [`SecretType::Buffer`] secret of 16 to 64 bytes,
+    /// and keys are of type [`SecretType::Curve25519`] or [`SecretType::Ed25519`].
+    /// Every key created or rotated later on by this [`Profile`] is derived
+    /// from the seed as well, so that [`Profile::regenerate`] restores them
+    /// from the seed and the [`Contact`] of this [`Profile`].
+    pub fn create_from_seed(
+        attributes: Option<ProfileEventAttributes>,
+        key_type: SecretType,
+        seed: &Secret,
+        vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        let secret_attributes = Self::key_secret_attributes(key_type)?;
+        Self::create_with_optional_seed(attributes, secret_attributes, Some(seed), vault)
+    }
+
+    fn create_with_optional_seed(
+        attributes: Option<ProfileEventAttributes>,
+        secret_attributes: SecretAttributes,
+        seed: Option<&Secret>,
+        vault: Arc<Mutex<dyn ProfileVault>>,
     ) -> ockam_core::Result<Self> {
         let mut v = vault.lock().unwrap();
         let prev_id = v.sha256(Profile::NO_EVENT)?;
@@ -213,6 +301,8 @@ impl Profile {
             prev_id,
             key_attributes.clone(),
             attributes,
+            secret_attributes,
+            seed,
             None,
             v.deref_mut(),
         )?;
@@ -224,17 +314,21 @@ impl Profile {
         let public_kid = v.compute_key_id_for_public_key(&public_key)?;
         let public_kid = ProfileIdentifier::from_key_id(public_kid);
 
-        let profile = Profile::new(
+        let mut profile = Profile::new(
             public_kid,
             vec![change_event],
             Default::default(),
             vault.clone(),
         );
+        profile.seed = seed.cloned();
 
         Ok(profile)
     }
 
     /// Create new key. Key is uniquely identified by label in [`KeyAttributes`]
+    ///
+    /// The key is derived from the seed of the [`Profile`] if it was created
+    /// with [`Profile::create_from_seed`], and generated otherwise.
     pub fn create_key(
         &mut self,
         key_attributes: KeyAttributes,
@@ -315,6 +409,60 @@ impl Profile {
 }
 
 impl Profile {
+    /// Attributes of the secrets of [`Profile`] keys of the given type
+    pub(crate) fn key_secret_attributes(
+        key_type: SecretType,
+    ) -> ockam_core::Result<SecretAttributes> {
+        let length = match key_type {
+            SecretType::Curve25519 => CURVE25519_SECRET_LENGTH,
+            SecretType::P256 => P256_SECRET_LENGTH,
+            SecretType::Ed25519 => ED25519_SECRET_LENGTH,
+            SecretType::Buffer | SecretType::Aes | SecretType::ChaCha20 => {
+                return Err(OckamError::InvalidKeyType.into())
+            }
+        };
+        // Profile keys only sign, and never leave the vault
+        Ok(
+            SecretAttributes::new(key_type, SecretPersistence::Persistent, length)
+                .with_usage(SecretUsage::Sign)
+                .with_exportable(false),
+        )
+    }
+
+    /// Derivation path of a key from the seed of a [`Profile`]. Keys are
+    /// numbered from 0 for each label, as they are rotated.
+    fn key_derivation_path(
+        key_attributes: &KeyAttributes,
+        key_number: u32,
+        vault: &dyn ProfileVault,
+    ) -> ockam_core::Result<String> {
+        // 62 bits of the hash of the label make collisions unlikely
+        let hash = vault.sha256(key_attributes.label().as_bytes())?;
+        let index = |i: usize| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&hash[i..i + 4]);
+            u32::from_be_bytes(bytes) & 0x7fff_ffff
+        };
+        Ok(format!("m/{}'/{}'/{}'", index(0), index(4), key_number))
+    }
+
+    /// Generate the secret of a new key, or derive it when there is a seed
+    pub(crate) fn new_key_secret(
+        key_attributes: &KeyAttributes,
+        key_number: u32,
+        secret_attributes: SecretAttributes,
+        seed: Option<&Secret>,
+        vault: &mut dyn ProfileVault,
+    ) -> ockam_core::Result<Secret> {
+        match seed {
+            Some(seed) => {
+                let path = Self::key_derivation_path(key_attributes, key_number, vault)?;
+                vault.secret_derive(seed, &path, secret_attributes)
+            }
+            None => vault.secret_generate(secret_attributes),
+        }
+    }
+
     pub(crate) fn get_root_secret(&self, vault: &dyn ProfileVault) -> ockam_core::Result<Secret> {
         let public_key =
             ProfileChangeHistory::get_current_profile_update_public_key(self.change_events())?;
@@ -336,6 +484,125 @@ impl Profile {
     }
 }
 
+// Recovery
+impl Profile {
+    /// Split the current root key of this [`Profile`] into shares
+    /// encrypted to the Curve25519 public keys of `guardians`, any
+    /// `threshold` of which recover it.
+    ///
+    /// Shares are only valid until the root key is rotated, and only the
+    /// root key of a [`Profile`] created with [`Profile::create_recoverable`]
+    /// can be split, since guardians together learn it.
+    pub fn split_root_key(
+        &self,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> ockam_core::Result<Vec<Buffer<u8>>> {
+        let mut vault = self.vault.lock().unwrap();
+        let root_secret = self.get_root_secret(vault.deref())?;
+        vault.secret_split(&root_secret, threshold, guardians)
+    }
+
+    /// Recover a [`Profile`] from its [`Contact`] and shares of its
+    /// root key, which guardians re-encrypted to the `recipient` secret
+    /// of `vault` with [`SecretSharingVault::secret_share_reencrypt`].
+    ///
+    /// Only the root key is recovered, other keys should be rotated.
+    pub fn recover(
+        contact: &Contact,
+        recipient: &Secret,
+        shares: &[Buffer<u8>],
+        vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        {
+            let mut v = vault.lock().unwrap();
+            contact.verify(v.deref_mut())?;
+            let public_key = ProfileChangeHistory::get_current_profile_update_public_key(
+                contact.change_events(),
+            )?;
+
+            let root_secret = v.secret_recover(recipient, shares)?;
+            if v.secret_public_key_get(&root_secret).ok() != Some(public_key) {
+                v.secret_destroy(root_secret)?;
+                return Err(OckamError::RecoveredKeyDoesntMatch.into());
+            }
+        }
+
+        Ok(Profile::new(
+            contact.identifier().clone(),
+            contact.change_events().to_vec(),
+            Default::default(),
+            vault,
+        ))
+    }
+}
+
+// Seed
+impl Profile {
+    /// Regenerate a [`Profile`] created with [`Profile::create_from_seed`]
+    /// from its [`Contact`] and seed. The current keys of the [`Profile`] are
+    /// derived again into `vault`.
+    pub fn regenerate(
+        contact: &Contact,
+        key_type: SecretType,
+        seed: &Secret,
+        vault: Arc<Mutex<dyn ProfileVault>>,
+    ) -> ockam_core::Result<Self> {
+        {
+            let mut v = vault.lock().unwrap();
+            contact.verify(v.deref_mut())?;
+
+            // The number and public key of the last key with each label
+            let mut keys: Vec<(&KeyAttributes, u32, PublicKey)> = Vec::new();
+            for change in contact
+                .change_events()
+                .iter()
+                .flat_map(|e| e.changes().data())
+            {
+                let key_attributes = ProfileChangeHistory::get_change_key_attributes(change);
+                let public_key = ProfileChangeHistory::get_change_public_key(change)?;
+                match keys.iter_mut().find(|(a, _, _)| *a == key_attributes) {
+                    Some(key) => {
+                        key.1 += 1;
+                        key.2 = public_key;
+                    }
+                    None => keys.push((key_attributes, 0, public_key)),
+                }
+            }
+
+            let secret_attributes = Self::key_secret_attributes(key_type)?;
+            let mut secrets = Vec::with_capacity(keys.len());
+            for (key_attributes, key_number, public_key) in keys {
+                let secret = Self::new_key_secret(
+                    key_attributes,
+                    key_number,
+                    secret_attributes,
+                    Some(seed),
+                    v.deref_mut(),
+                )?;
+                let matches = v.secret_public_key_get(&secret).ok() == Some(public_key);
+                secrets.push(secret);
+                if !matches {
+                    for secret in secrets {
+                        v.secret_destroy(secret)?;
+                    }
+                    return Err(OckamError::RecoveredKeyDoesntMatch.into());
+                }
+            }
+        }
+
+        let mut profile = Profile::new(
+            contact.identifier().clone(),
+            contact.change_events().to_vec(),
+            Default::default(),
+            vault,
+        );
+        profile.seed = Some(seed.clone());
+
+        Ok(profile)
+    }
+}
+
 // Contacts
 impl Profile {
     /// Convert [`Profile`] to [`Contact`]
@@ -451,16 +718,34 @@ impl Profile {
         Authentication::verify_proof(
             channel_state,
             &contact.get_profile_update_public_key()?,
+            contact.get_profile_update_key_type()?,
             proof,
             vault.deref_mut(),
         )
     }
+
+    /// Verify Proof of possession of the [`Profile`] at the other end of
+    /// the secure channel with the given address, which then marks the
+    /// messages it receives with the [`ProfileIdentifier`].
+    pub fn authenticate_channel(
+        &self,
+        channel: Address,
+        channel_state: &[u8],
+        responder_contact_id: &ProfileIdentifier,
+        proof: &[u8],
+    ) -> ockam_core::Result<AuthenticatedChannel> {
+        self.verify_authentication_proof(channel_state, responder_contact_id, proof)?;
+        Ok(AuthenticatedChannel::new(
+            channel,
+            responder_contact_id.clone(),
+        ))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use ockam_vault::SoftwareVault;
+    use ockam_vault::{SoftwareVault, VaultError};
 
     #[test]
     fn test_new() {
@@ -504,6 +789,61 @@ mod test {
         let _alice_root_public_key = profile.get_public_key(&root_key_attributes).unwrap();
     }
 
+    #[test]
+    fn test_p256_keys() {
+        let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+        let mut alice =
+            Profile::create_with_key_type(None, SecretType::P256, vault.clone()).unwrap();
+        let mut bob = Profile::create(None, vault).unwrap();
+
+        let truck_key_attributes = KeyAttributes::new("Truck management".to_string());
+        alice
+            .create_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        alice
+            .rotate_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        alice
+            .rotate_key(Profile::PROFILE_UPDATE.into(), None)
+            .unwrap();
+        alice.verify().unwrap();
+
+        let truck_public_key = alice.get_public_key(&truck_key_attributes).unwrap();
+        assert_eq!(
+            truck_public_key.as_ref().len(),
+            ockam_vault_core::P256_PUBLIC_LENGTH
+        );
+
+        bob.verify_and_add_contact(alice.to_contact()).unwrap();
+    }
+
+    #[test]
+    fn test_ed25519_keys() {
+        let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+        let mut alice =
+            Profile::create_with_key_type(None, SecretType::Ed25519, vault.clone()).unwrap();
+        let mut bob = Profile::create(None, vault).unwrap();
+
+        // Change signatures are standard Ed25519 signatures
+        let public_key = alice
+            .get_public_key(&Profile::PROFILE_UPDATE.into())
+            .unwrap();
+        let public_key = ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()).unwrap();
+        let event = alice.change_events().first().unwrap();
+        let signature = match event.proof() {
+            ProfileChangeProof::Signature(s) => ed25519_dalek::Signature::from(*s.data()),
+        };
+        public_key
+            .verify_strict(event.identifier().as_ref(), &signature)
+            .unwrap();
+
+        alice
+            .rotate_key(Profile::PROFILE_UPDATE.into(), None)
+            .unwrap();
+        alice.verify().unwrap();
+        bob.verify_and_add_contact(alice.to_contact()).unwrap();
+    }
+
     #[test]
     fn test_update() {
         let vault = Arc::new(Mutex::new(SoftwareVault::default()));
@@ -531,4 +871,204 @@ mod test {
         bob.verify_and_update_contact(&alice_id, change_events)
             .unwrap();
     }
+
+    #[test]
+    fn test_untyped_keys_contact() {
+        // Contact with a root key rotation, serialized before changes
+        // recorded the type of keys
+        let contact_hex = [
+            "40000000000000006336363839326635626130343034366336366132313731643634363331656339",
+            "30646136356464633966333639613061663264376565636432376130656230620200000000000000",
+            "18f0ccda0a1a0a7fe3a702585ca38a72b566f566d19e2bf9616beda769e5a727b9154549a381303a",
+            "93dd12a27e173d7f366f1597ca282dace84737fcfb3fa28201000000000000000100000000000000",
+            "000000000009000000000000004f434b414d5f50554b2000000000000000ca628d040d4c0285ed4a",
+            "ec0d9b89c1aaeeba1c120a8ca384d4381fb52fb55026b0ae7eacd6861ffec67249778680ef72769f",
+            "d30aabbaccb48000c8c76ad1901eb8a5da226f741b47d3e0eeb7a477134d65ff56ea1f92bbd90b25",
+            "24b34de36a080000000000000000b7601b8e3a389feb33c5a05a9ce23281f18dd3ca05e26ee40b7d",
+            "0e7ad1c905a32548d71e419be491990962f5259f8c74991da0d3645041036f5bafade346d008eb1f",
+            "46e45229145504f36e7b451dd4b10844954250dfdc8cdbfb11cf2091dba618f0ccda0a1a0a7fe3a7",
+            "02585ca38a72b566f566d19e2bf9616beda769e5a727010000000000000001000000000000000001",
+            "00000009000000000000004f434b414d5f50554b2000000000000000a4fd0daf139f3ecd891543bc",
+            "c3ec2a77ea7dfe56bb84a44ab688b2475ba1d87e8c345d9dfee73b3680acacb89ef1149e560c0c18",
+            "b905e6c5a84c73952d5546db8677c2a0a541efb7e73f3c6d1bf98d360d6f05cd0e846c7853cee6a3",
+            "307a5703c8f9616b44ddd8f225b78d15f77080fc3adeba153a1f77c65561a9c770b4659d23ce54f6",
+            "a0ec6ae0d0523bdfbcf2dd6782e141b8f5fc79f2af521925710bd00800000000000000003ac9f1f0",
+            "d32a6eddf6dfe1c72ec9347d3890f565893c854d8738ef3dbda041c889ce6620897b3e3102e07b1e",
+            "ce457707fd04e9bded9f0696a90657686b25b000",
+        ]
+        .concat();
+        let contact = ockam_core::hex::decode(&contact_hex).unwrap();
+
+        let contact = Profile::deserialize_contact(&contact).unwrap();
+        let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+        let mut bob = Profile::create(None, vault).unwrap();
+        bob.verify_contact(&contact).unwrap();
+        for change in contact
+            .change_events()
+            .iter()
+            .flat_map(|e| e.changes().data())
+        {
+            assert_eq!(change.version(), Profile::UNTYPED_KEYS_CHANGE_VERSION);
+            assert_eq!(
+                ProfileChangeHistory::get_change_key_type(change),
+                SecretType::Curve25519
+            );
+        }
+        // Event identifiers still match the changes
+        let serialized = Profile::serialize_contact(&contact).unwrap();
+        assert_eq!(ockam_core::hex::encode(&serialized), contact_hex);
+        bob.verify_and_add_contact(contact).unwrap();
+    }
+
+    #[test]
+    fn test_recover() {
+        let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+        let mut alice =
+            Profile::create_recoverable(None, SecretType::Curve25519, vault.clone()).unwrap();
+        let bob = Profile::create_recoverable(None, SecretType::Curve25519, vault.clone()).unwrap();
+        let carol = Profile::create(None, vault).unwrap();
+        alice
+            .rotate_key(Profile::PROFILE_UPDATE.into(), None)
+            .unwrap();
+
+        let recovery_key = |vault: &mut SoftwareVault| {
+            let attributes = SecretAttributes::new(
+                SecretType::Curve25519,
+                SecretPersistence::Persistent,
+                CURVE25519_SECRET_LENGTH,
+            );
+            let secret = vault.secret_generate(attributes).unwrap();
+            let public_key = vault.secret_public_key_get(&secret).unwrap();
+            (secret, public_key)
+        };
+        let mut guardians: Vec<_> = (0..3).map(|_| SoftwareVault::default()).collect();
+        let keys: Vec<_> = guardians.iter_mut().map(recovery_key).collect();
+        let guardian_keys: Vec<_> = keys.iter().map(|(_, public)| public.clone()).collect();
+        let alice_shares = alice.split_root_key(2, &guardian_keys).unwrap();
+        let bob_shares = bob.split_root_key(2, &guardian_keys).unwrap();
+
+        // Root keys which can't be exported can't be split either
+        let err = carol.split_root_key(1, &guardian_keys[..1]).err().unwrap();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::SecretNotExportable as u32
+        );
+
+        // Alice lost her vault, two guardians send her their shares
+        let mut new_vault = SoftwareVault::default();
+        let (recipient, recipient_public) = recovery_key(&mut new_vault);
+        let reencrypt = |i: usize, shares: &[Vec<u8>], guardians: &mut Vec<SoftwareVault>| {
+            guardians[i]
+                .secret_share_reencrypt(&keys[i].0, &shares[i], &recipient_public)
+                .unwrap()
+        };
+        let shares = vec![
+            reencrypt(0, &alice_shares, &mut guardians),
+            reencrypt(2, &alice_shares, &mut guardians),
+        ];
+        let wrong_shares = vec![
+            reencrypt(0, &bob_shares, &mut guardians),
+            reencrypt(1, &bob_shares, &mut guardians),
+        ];
+        let new_vault = Arc::new(Mutex::new(new_vault));
+
+        let err = Profile::recover(
+            &alice.to_contact(),
+            &recipient,
+            &wrong_shares,
+            new_vault.clone(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.code(),
+            OckamError::DOMAIN_CODE + OckamError::RecoveredKeyDoesntMatch as u32
+        );
+
+        let mut recovered =
+            Profile::recover(&alice.to_contact(), &recipient, &shares, new_vault).unwrap();
+        assert_eq!(recovered.identifier(), alice.identifier());
+        recovered
+            .rotate_key(Profile::PROFILE_UPDATE.into(), None)
+            .unwrap();
+        recovered.verify().unwrap();
+        // Rotated root keys can be split again
+        recovered.split_root_key(2, &guardian_keys).unwrap();
+    }
+
+    #[test]
+    fn test_regenerate() {
+        let seed = |vault: &mut SoftwareVault| {
+            let attributes =
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 32);
+            vault.secret_import(&[7u8; 32], attributes).unwrap()
+        };
+        let mut vault = SoftwareVault::default();
+        let alice_seed = seed(&mut vault);
+        let vault = Arc::new(Mutex::new(vault));
+        let mut alice =
+            Profile::create_from_seed(None, SecretType::Ed25519, &alice_seed, vault).unwrap();
+        let truck_key_attributes = KeyAttributes::new("Truck management".to_string());
+        alice
+            .create_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        alice
+            .rotate_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        alice
+            .rotate_key(Profile::PROFILE_UPDATE.into(), None)
+            .unwrap();
+
+        // The same seed gives the same root key
+        let mut vault = SoftwareVault::default();
+        let bob_seed = seed(&mut vault);
+        let bob = Profile::create_from_seed(
+            None,
+            SecretType::Ed25519,
+            &bob_seed,
+            Arc::new(Mutex::new(vault)),
+        )
+        .unwrap();
+        assert_eq!(bob.identifier(), alice.identifier());
+
+        // Alice lost her vault, she still has her seed
+        let mut new_vault = SoftwareVault::default();
+        let new_seed = seed(&mut new_vault);
+        let new_vault = Arc::new(Mutex::new(new_vault));
+        let err = Profile::regenerate(
+            &alice.to_contact(),
+            SecretType::Curve25519,
+            &new_seed,
+            new_vault.clone(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.code(),
+            OckamError::DOMAIN_CODE + OckamError::RecoveredKeyDoesntMatch as u32
+        );
+
+        let mut regenerated = Profile::regenerate(
+            &alice.to_contact(),
+            SecretType::Ed25519,
+            &new_seed,
+            new_vault,
+        )
+        .unwrap();
+        assert_eq!(regenerated.identifier(), alice.identifier());
+        regenerated.get_secret_key(&truck_key_attributes).unwrap();
+        regenerated
+            .rotate_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        regenerated.verify().unwrap();
+
+        // Both keep deriving the same keys
+        alice
+            .rotate_key(truck_key_attributes.clone(), None)
+            .unwrap();
+        assert_eq!(
+            regenerated.get_public_key(&truck_key_attributes).unwrap(),
+            alice.get_public_key(&truck_key_attributes).unwrap()
+        );
+    }
 }
@@ -1,5 +1,5 @@
 use crate::{OckamError, ProfileVault};
-use ockam_vault_core::{PublicKey, Secret};
+use ockam_vault_core::{PublicKey, Secret, SecretType};
 use serde::{Deserialize, Serialize};
 use serde_big_array::big_array;
 
@@ -41,6 +41,7 @@ impl Authentication {
     pub(crate) fn verify_proof(
         channel_state: &[u8],
         responder_public_key: &PublicKey,
+        responder_key_type: SecretType,
         proof: &[u8],
         vault: &mut dyn ProfileVault,
     ) -> ockam_core::Result<()> {
@@ -50,6 +51,7 @@ impl Authentication {
         vault.verify(
             &proof.signature(),
             responder_public_key.as_ref(),
+            responder_key_type,
             channel_state,
         )
     }
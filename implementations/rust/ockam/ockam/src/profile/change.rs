@@ -1,5 +1,8 @@
-use crate::ProfileEventAttributes;
-use serde::{Deserialize, Serialize};
+use crate::{OckamError, Profile, ProfileEventAttributes};
+use serde::de::{Error as _, SeqAccess, Visitor};
+use serde::ser::{Error as _, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
 mod proof;
 pub use proof::*;
@@ -22,7 +25,10 @@ impl ProfileEventAttributeKey {
 }
 
 /// Individual change applied to profile. [`ProfileChangeEvent`] consists of one or more such changes
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///
+/// Changes of version [`Profile::UNTYPED_KEYS_CHANGE_VERSION`] keep the
+/// layout they were signed with, which doesn't record the type of keys.
+#[derive(Debug, Clone)]
 pub struct ProfileChange {
     version: u8,
     // TODO: Check attributes serialization
@@ -46,6 +52,23 @@ impl ProfileChange {
 }
 
 impl ProfileChange {
+    /// Binary form of the data of the change, which keys sign
+    pub(crate) fn data_binary(&self) -> ockam_core::Result<Vec<u8>> {
+        let (key_attributes, public_key) = match &self.change_type {
+            ProfileChangeType::CreateKey(c) => (c.data().key_attributes(), c.data().public_key()),
+            ProfileChangeType::RotateKey(c) => (c.data().key_attributes(), c.data().public_key()),
+        };
+        let binary = if self.version == Profile::UNTYPED_KEYS_CHANGE_VERSION {
+            serde_bare::to_vec(&UntypedKeyChangeData::new(key_attributes, public_key))
+        } else {
+            match &self.change_type {
+                ProfileChangeType::CreateKey(c) => serde_bare::to_vec(c.data()),
+                ProfileChangeType::RotateKey(c) => serde_bare::to_vec(c.data()),
+            }
+        };
+        binary.map_err(|_| OckamError::BareError.into())
+    }
+
     pub(crate) fn new(
         version: u8,
         attributes: ProfileEventAttributes,
@@ -58,3 +81,56 @@ impl ProfileChange {
         }
     }
 }
+
+impl Serialize for ProfileChange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ProfileChange", 3)?;
+        state.serialize_field("version", &self.version)?;
+        state.serialize_field("attributes", &self.attributes)?;
+        if self.version == Profile::UNTYPED_KEYS_CHANGE_VERSION {
+            let change_type = UntypedChangeType::from_change_type(&self.change_type)
+                .ok_or_else(|| S::Error::custom("untyped change of a key other than Curve25519"))?;
+            state.serialize_field("change_type", &change_type)?;
+        } else {
+            state.serialize_field("change_type", &self.change_type)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ProfileChange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ProfileChangeVisitor;
+
+        impl<'de> Visitor<'de> for ProfileChangeVisitor {
+            type Value = ProfileChange;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("struct ProfileChange")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let version: u8 = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                let attributes = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+                let change_type = if version == Profile::UNTYPED_KEYS_CHANGE_VERSION {
+                    seq.next_element::<UntypedChangeType>()?
+                        .map(UntypedChangeType::into_change_type)
+                } else {
+                    seq.next_element()?
+                };
+                let change_type = change_type.ok_or_else(|| A::Error::invalid_length(2, &self))?;
+                Ok(ProfileChange::new(version, attributes, change_type))
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "ProfileChange",
+            &["version", "attributes", "change_type"],
+            ProfileChangeVisitor,
+        )
+    }
+}
@@ -4,6 +4,8 @@ mod create_key;
 pub use create_key::*;
 mod rotate_key;
 pub use rotate_key::*;
+mod untyped;
+pub(crate) use untyped::*;
 
 /// Possible types of [`crate::Profile`] changes
 #[derive(Serialize, Deserialize, Debug, Clone)]
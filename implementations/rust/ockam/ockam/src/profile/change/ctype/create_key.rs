@@ -4,9 +4,7 @@ use crate::{
     ProfileChangeEvent, ProfileChangeProof, ProfileChangeType, ProfileEventAttributes,
     ProfileVault, Signature, SignatureType,
 };
-use ockam_vault_core::{
-    Secret, SecretAttributes, SecretPersistence, SecretType, CURVE25519_SECRET_LENGTH,
-};
+use ockam_vault_core::{Secret, SecretAttributes, SecretType};
 use serde::{Deserialize, Serialize};
 use serde_big_array::big_array;
 
@@ -16,6 +14,7 @@ big_array! { BigArray; }
 pub struct CreateKeyChangeData {
     key_attributes: KeyAttributes,
     public_key: Vec<u8>,
+    key_type: SecretType,
 }
 
 impl CreateKeyChangeData {
@@ -25,13 +24,18 @@ impl CreateKeyChangeData {
     pub fn public_key(&self) -> &[u8] {
         &self.public_key
     }
+    /// Type of the key, which selects the scheme of its signatures
+    pub fn key_type(&self) -> SecretType {
+        self.key_type
+    }
 }
 
 impl CreateKeyChangeData {
-    pub fn new(key_attributes: KeyAttributes, public_key: Vec<u8>) -> Self {
+    pub fn new(key_attributes: KeyAttributes, public_key: Vec<u8>, key_type: SecretType) -> Self {
         CreateKeyChangeData {
             key_attributes,
             public_key,
+            key_type,
         }
     }
 }
@@ -66,22 +70,22 @@ impl Profile {
         prev_id: EventIdentifier,
         key_attributes: KeyAttributes,
         attributes: Option<ProfileEventAttributes>,
+        secret_attributes: SecretAttributes,
+        seed: Option<&Secret>,
         root_key: Option<&Secret>,
         vault: &mut dyn ProfileVault,
     ) -> ockam_core::Result<ProfileChangeEvent> {
         let attributes = attributes.unwrap_or_default();
 
-        // TODO: Should be customisable
-        let secret_attributes = SecretAttributes::new(
-            SecretType::Curve25519,
-            SecretPersistence::Persistent,
-            CURVE25519_SECRET_LENGTH,
-        );
-
-        let secret_key = vault.secret_generate(secret_attributes)?;
+        // This is the first key with these attributes
+        let secret_key = Self::new_key_secret(&key_attributes, 0, secret_attributes, seed, vault)?;
         let public_key = vault.secret_public_key_get(&secret_key)?;
 
-        let data = CreateKeyChangeData::new(key_attributes, public_key.as_ref().to_vec());
+        let data = CreateKeyChangeData::new(
+            key_attributes,
+            public_key.as_ref().to_vec(),
+            secret_attributes.stype(),
+        );
         let data_binary = serde_bare::to_vec(&data).map_err(|_| OckamError::BareError)?;
         let data_hash = vault.sha256(data_binary.as_slice())?;
         let self_signature = vault.sign(&secret_key, &data_hash)?;
@@ -131,6 +135,22 @@ impl Profile {
 
         let prev_id = self.change_history.get_last_event_id()?;
 
-        Self::create_key_event_static(prev_id, key_attributes, attributes, root_key, vault)
+        // New keys have the same type as the root key
+        let secret_attributes = match root_key {
+            Some(root_key) => {
+                Self::key_secret_attributes(vault.secret_attributes_get(root_key)?.stype())?
+            }
+            None => Self::key_secret_attributes(SecretType::Curve25519)?,
+        };
+
+        Self::create_key_event_static(
+            prev_id,
+            key_attributes,
+            attributes,
+            secret_attributes,
+            self.seed.as_ref(),
+            root_key,
+            vault,
+        )
     }
 }
@@ -4,9 +4,7 @@ use crate::{
     ProfileChangeEvent, ProfileChangeProof, ProfileChangeType, ProfileEventAttributes,
     ProfileVault, Signature, SignatureType,
 };
-use ockam_vault_core::{
-    Secret, SecretAttributes, SecretPersistence, SecretType, CURVE25519_SECRET_LENGTH,
-};
+use ockam_vault_core::{Secret, SecretType};
 use serde::{Deserialize, Serialize};
 use serde_big_array::big_array;
 use std::ops::Deref;
@@ -17,6 +15,7 @@ big_array! { BigArray; }
 pub struct RotateKeyChangeData {
     key_attributes: KeyAttributes,
     public_key: Vec<u8>,
+    key_type: SecretType,
 }
 
 impl RotateKeyChangeData {
@@ -26,13 +25,18 @@ impl RotateKeyChangeData {
     pub fn public_key(&self) -> &[u8] {
         self.public_key.as_slice()
     }
+    /// Type of the key, which selects the scheme of its signatures
+    pub fn key_type(&self) -> SecretType {
+        self.key_type
+    }
 }
 
 impl RotateKeyChangeData {
-    pub fn new(key_attributes: KeyAttributes, public_key: Vec<u8>) -> Self {
+    pub fn new(key_attributes: KeyAttributes, public_key: Vec<u8>, key_type: SecretType) -> Self {
         RotateKeyChangeData {
             key_attributes,
             public_key,
+            key_type,
         }
     }
 }
@@ -90,17 +94,23 @@ impl Profile {
         let last_key_in_chain =
             Self::get_secret_key_from_event(&key_attributes, last_event_in_chain, vault.deref())?;
 
-        // TODO: Should be customisable
-        let secret_attributes = SecretAttributes::new(
-            SecretType::Curve25519,
-            SecretPersistence::Persistent,
-            CURVE25519_SECRET_LENGTH,
-        );
-
-        let secret_key = vault.secret_generate(secret_attributes)?;
+        // The new key has the same type as the rotated key, and can be
+        // exported if it could, so that recoverable root keys stay so
+        let last_attributes = vault.secret_attributes_get(&last_key_in_chain)?;
+        let secret_attributes = Self::key_secret_attributes(last_attributes.stype())?
+            .with_exportable(last_attributes.exportable());
+
+        let key_number = ProfileChangeHistory::count_keys(self.change_events(), &key_attributes);
+        let secret_key = Self::new_key_secret(
+            &key_attributes,
+            key_number,
+            secret_attributes,
+            self.seed.as_ref(),
+            vault,
+        )?;
         let public_key = vault.secret_public_key_get(&secret_key)?.as_ref().to_vec();
 
-        let data = RotateKeyChangeData::new(key_attributes, public_key);
+        let data = RotateKeyChangeData::new(key_attributes, public_key, secret_attributes.stype());
         let data_binary = serde_bare::to_vec(&data).map_err(|_| OckamError::BareError)?;
         let data_hash = vault.sha256(data_binary.as_slice())?;
         let self_signature = vault.sign(&secret_key, &data_hash)?;
@@ -0,0 +1,105 @@
+use crate::{
+    CreateKeyChange, CreateKeyChangeData, KeyAttributes, ProfileChangeType, RotateKeyChange,
+    RotateKeyChangeData,
+};
+use ockam_vault_core::SecretType;
+use serde::{Deserialize, Serialize};
+use serde_big_array::big_array;
+
+big_array! { BigArray; }
+
+/// Data of a key change of version [`crate::Profile::UNTYPED_KEYS_CHANGE_VERSION`],
+/// which doesn't record the type of the key, since it is Curve25519
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UntypedKeyChangeData {
+    key_attributes: KeyAttributes,
+    public_key: Vec<u8>,
+}
+
+impl UntypedKeyChangeData {
+    pub(crate) fn new(key_attributes: &KeyAttributes, public_key: &[u8]) -> Self {
+        Self {
+            key_attributes: key_attributes.clone(),
+            public_key: public_key.to_vec(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UntypedCreateKeyChange {
+    data: UntypedKeyChangeData,
+    #[serde(with = "BigArray")]
+    self_signature: [u8; 64],
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct UntypedRotateKeyChange {
+    data: UntypedKeyChangeData,
+    #[serde(with = "BigArray")]
+    self_signature: [u8; 64],
+    #[serde(with = "BigArray")]
+    prev_signature: [u8; 64],
+}
+
+/// Layout of [`ProfileChangeType`] in changes of version
+/// [`crate::Profile::UNTYPED_KEYS_CHANGE_VERSION`]
+#[derive(Serialize, Deserialize)]
+pub(crate) enum UntypedChangeType {
+    CreateKey(UntypedCreateKeyChange),
+    RotateKey(UntypedRotateKeyChange),
+}
+
+impl UntypedChangeType {
+    /// Layout of a change of Curve25519 keys, `None` for other key types
+    pub(crate) fn from_change_type(change_type: &ProfileChangeType) -> Option<Self> {
+        Some(match change_type {
+            ProfileChangeType::CreateKey(c) => {
+                if c.data().key_type() != SecretType::Curve25519 {
+                    return None;
+                }
+                Self::CreateKey(UntypedCreateKeyChange {
+                    data: UntypedKeyChangeData::new(
+                        c.data().key_attributes(),
+                        c.data().public_key(),
+                    ),
+                    self_signature: *c.self_signature(),
+                })
+            }
+            ProfileChangeType::RotateKey(c) => {
+                if c.data().key_type() != SecretType::Curve25519 {
+                    return None;
+                }
+                Self::RotateKey(UntypedRotateKeyChange {
+                    data: UntypedKeyChangeData::new(
+                        c.data().key_attributes(),
+                        c.data().public_key(),
+                    ),
+                    self_signature: *c.self_signature(),
+                    prev_signature: *c.prev_signature(),
+                })
+            }
+        })
+    }
+
+    pub(crate) fn into_change_type(self) -> ProfileChangeType {
+        match self {
+            Self::CreateKey(c) => ProfileChangeType::CreateKey(CreateKeyChange::new(
+                CreateKeyChangeData::new(
+                    c.data.key_attributes,
+                    c.data.public_key,
+                    SecretType::Curve25519,
+                ),
+                c.self_signature,
+            )),
+            Self::RotateKey(c) => ProfileChangeType::RotateKey(RotateKeyChange::new(
+                RotateKeyChangeData::new(
+                    c.data.key_attributes,
+                    c.data.public_key,
+                    SecretType::Curve25519,
+                ),
+                c.self_signature,
+                c.prev_signature,
+            )),
+        }
+    }
+}
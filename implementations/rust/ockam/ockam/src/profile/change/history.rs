@@ -3,7 +3,7 @@ use crate::{
     EventIdentifier, KeyAttributes, OckamError, Profile, ProfileChange, ProfileChangeEvent,
     ProfileChangeProof, ProfileChangeType, ProfileVault, SignatureType,
 };
-use ockam_vault_core::PublicKey;
+use ockam_vault_core::{PublicKey, SecretType};
 use serde::{Deserialize, Serialize};
 
 /// Full history of [`Profile`] changes. History and corresponding secret keys are enough to recreate [`Profile`]
@@ -78,6 +78,26 @@ impl ProfileChangeHistory {
         Self::get_public_key_from_event(&key_attributes, &last_key_event)
     }
 
+    pub(crate) fn get_change_key_attributes(change: &ProfileChange) -> &KeyAttributes {
+        match change.change_type() {
+            CreateKey(change) => change.data().key_attributes(),
+            RotateKey(change) => change.data().key_attributes(),
+        }
+    }
+
+    /// Number of keys with the given attributes that were created or
+    /// rotated in, which is also the number of the next one
+    pub(crate) fn count_keys(
+        existing_events: &[ProfileChangeEvent],
+        key_attributes: &KeyAttributes,
+    ) -> u32 {
+        existing_events
+            .iter()
+            .flat_map(|e| e.changes().data())
+            .filter(|c| Self::get_change_key_attributes(c) == key_attributes)
+            .count() as u32
+    }
+
     pub(crate) fn get_change_public_key(change: &ProfileChange) -> ockam_core::Result<PublicKey> {
         let data = match change.change_type() {
             CreateKey(change) => change.data().public_key(),
@@ -91,6 +111,13 @@ impl ProfileChangeHistory {
         }
     }
 
+    pub(crate) fn get_change_key_type(change: &ProfileChange) -> SecretType {
+        match change.change_type() {
+            CreateKey(change) => change.data().key_type(),
+            RotateKey(change) => change.data().key_type(),
+        }
+    }
+
     pub(crate) fn get_public_key_from_event(
         key_attributes: &KeyAttributes,
         event: &ProfileChangeEvent,
@@ -110,6 +137,17 @@ impl ProfileChangeHistory {
         Self::find_last_key_event_public_key(existing_events, &key_attributes)
     }
 
+    pub(crate) fn get_current_profile_update_key_type(
+        existing_events: &[ProfileChangeEvent],
+    ) -> ockam_core::Result<SecretType> {
+        let key_attributes = KeyAttributes::new(Profile::PROFILE_UPDATE.to_string());
+        let event = Self::find_last_key_event(existing_events, &key_attributes)?;
+        let change = Self::find_key_change_in_event(event, &key_attributes)
+            .ok_or(OckamError::InvalidInternalState)?;
+
+        Ok(Self::get_change_key_type(change))
+    }
+
     pub(crate) fn get_first_root_public_key(&self) -> ockam_core::Result<PublicKey> {
         // TODO: Support root key rotation
         let root_event;
@@ -187,7 +225,13 @@ impl ProfileChangeHistory {
                     };
                     let root_public_key =
                         Self::get_current_profile_update_public_key(events_to_look)?;
-                    vault.verify(s.data(), root_public_key.as_ref(), event_id.as_ref())?;
+                    let root_key_type = Self::get_current_profile_update_key_type(events_to_look)?;
+                    vault.verify(
+                        s.data(),
+                        root_public_key.as_ref(),
+                        root_key_type,
+                        event_id.as_ref(),
+                    )?;
                 }
             },
         }
@@ -196,22 +240,30 @@ impl ProfileChangeHistory {
             if !match change.change_type() {
                 CreateKey(c) => {
                     // Should have 1 self signature
-                    let data_binary =
-                        serde_bare::to_vec(c.data()).map_err(|_| OckamError::BareError)?;
+                    let data_binary = change.data_binary()?;
                     let data_hash = vault.sha256(data_binary.as_slice())?;
 
                     vault
-                        .verify(c.self_signature(), c.data().public_key(), &data_hash)
+                        .verify(
+                            c.self_signature(),
+                            c.data().public_key(),
+                            c.data().key_type(),
+                            &data_hash,
+                        )
                         .is_ok()
                 }
                 RotateKey(c) => {
                     // Should have 1 self signature and 1 prev signature
-                    let data_binary =
-                        serde_bare::to_vec(c.data()).map_err(|_| OckamError::BareError)?;
+                    let data_binary = change.data_binary()?;
                     let data_hash = vault.sha256(data_binary.as_slice())?;
 
                     if vault
-                        .verify(c.self_signature(), c.data().public_key(), &data_hash)
+                        .verify(
+                            c.self_signature(),
+                            c.data().public_key(),
+                            c.data().key_type(),
+                            &data_hash,
+                        )
                         .is_err()
                     {
                         false
@@ -225,9 +277,15 @@ impl ProfileChangeHistory {
                         .ok_or(OckamError::InvalidInternalState)?;
                         let public_key =
                             ProfileChangeHistory::get_change_public_key(prev_key_change)?;
+                        let key_type = ProfileChangeHistory::get_change_key_type(prev_key_change);
 
                         vault
-                            .verify(c.prev_signature(), public_key.as_ref(), &data_hash)
+                            .verify(
+                                c.prev_signature(),
+                                public_key.as_ref(),
+                                key_type,
+                                &data_hash,
+                            )
                             .is_ok()
                     }
                 }
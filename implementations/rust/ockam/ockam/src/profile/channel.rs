@@ -0,0 +1,88 @@
+use crate::ProfileIdentifier;
+#[cfg(feature = "std")]
+use crate::{
+    CredentialAttribute, CredentialError, CredentialPresentation, CredentialVerifier,
+    PresentationManifest,
+};
+use ockam_core::lib::BTreeMap;
+use ockam_core::{Address, TransportMessage};
+
+/// A secure channel whose peer was authenticated with its [`crate::Profile`]
+///
+/// Secure channel workers mark the messages they decrypt before
+/// forwarding them, so that workers can read the channel, the
+/// authenticated profile and the attributes of the credentials it
+/// presented from [`ockam_core::Routed::local`].
+#[derive(Clone, Debug)]
+pub struct AuthenticatedChannel {
+    channel: Address,
+    profile_id: ProfileIdentifier,
+    credential_attributes: BTreeMap<String, String>,
+}
+
+impl AuthenticatedChannel {
+    pub(crate) fn new(channel: Address, profile_id: ProfileIdentifier) -> Self {
+        Self {
+            channel,
+            profile_id,
+            credential_attributes: BTreeMap::new(),
+        }
+    }
+    /// Address of the secure channel
+    pub fn channel(&self) -> &Address {
+        &self.channel
+    }
+    /// Identifier of the authenticated peer profile
+    pub fn profile_id(&self) -> &ProfileIdentifier {
+        &self.profile_id
+    }
+    /// Revealed attributes of the credentials presented by the peer, by label
+    pub fn credential_attributes(&self) -> &BTreeMap<String, String> {
+        &self.credential_attributes
+    }
+    /// Verify credential presentations made by the peer over the
+    /// channel, and record the attributes they reveal
+    #[cfg(feature = "std")]
+    pub fn verify_credential_presentations(
+        &mut self,
+        presentations: &[CredentialPresentation],
+        presentation_manifests: &[PresentationManifest],
+        proof_request_id: [u8; 32],
+    ) -> Result<(), CredentialError> {
+        CredentialVerifier::verify_credential_presentations(
+            presentations,
+            presentation_manifests,
+            proof_request_id,
+        )?;
+
+        for (presentation, manifest) in presentations.iter().zip(presentation_manifests) {
+            let revealed = manifest
+                .revealed
+                .iter()
+                .zip(presentation.revealed_attributes.iter());
+            for (&index, attribute) in revealed {
+                let schema = manifest
+                    .credential_schema
+                    .attributes
+                    .get(index)
+                    .ok_or(CredentialError::MismatchedAttributesAndClaims)?;
+                let value = match attribute {
+                    CredentialAttribute::NotSpecified => continue,
+                    CredentialAttribute::Empty => String::new(),
+                    CredentialAttribute::String(s) => s.clone(),
+                    CredentialAttribute::Numeric(n) => n.to_string(),
+                    CredentialAttribute::Blob(b) => ockam_core::hex::encode(b),
+                };
+                self.credential_attributes
+                    .insert(schema.label.clone(), value);
+            }
+        }
+        Ok(())
+    }
+    /// Mark a message received through the channel
+    pub fn mark(&self, msg: &mut TransportMessage) {
+        msg.local.secure_channel = Some(self.channel.clone());
+        msg.local.profile_id = Some(self.profile_id.clone());
+        msg.local.credential_attributes = self.credential_attributes.clone();
+    }
+}
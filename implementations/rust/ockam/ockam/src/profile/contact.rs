@@ -2,7 +2,7 @@ use crate::profile::history::ProfileChangeHistory;
 use crate::{
     EventIdentifier, KeyAttributes, OckamError, ProfileChangeEvent, ProfileIdentifier, ProfileVault,
 };
-use ockam_vault_core::PublicKey;
+use ockam_vault_core::{PublicKey, SecretType};
 use serde::{Deserialize, Serialize};
 
 /// Contact is an abstraction responsible for storing user's public data (mainly - public keys).
@@ -125,6 +125,10 @@ impl Contact {
     pub fn get_profile_update_public_key(&self) -> ockam_core::Result<PublicKey> {
         ProfileChangeHistory::get_current_profile_update_public_key(self.change_events())
     }
+    /// Get the [`SecretType`] of the [`crate::Profile`] Update key
+    pub(crate) fn get_profile_update_key_type(&self) -> ockam_core::Result<SecretType> {
+        ProfileChangeHistory::get_current_profile_update_key_type(self.change_events())
+    }
     /// Get [`PublicKey`]. Key is uniquely identified by label in [`KeyAttributes`]
     pub fn get_public_key(&self, key_attributes: &KeyAttributes) -> ockam_core::Result<PublicKey> {
         self.change_history.get_public_key(key_attributes)
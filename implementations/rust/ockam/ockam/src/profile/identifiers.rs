@@ -1,20 +1,7 @@
 use ockam_core::hex::encode;
-use ockam_vault_core::KeyId;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
-pub struct ProfileIdentifier(KeyId);
-
-/// Unique [`crate::Profile`] identifier, computed as SHA256 of root public key
-impl ProfileIdentifier {
-    pub fn from_key_id(key_id: KeyId) -> Self {
-        Self { 0: key_id }
-    }
-    /// Human-readable form of the id
-    pub fn to_string_representation(&self) -> String {
-        format!("P_ID.{}", &self.0)
-    }
-}
+pub use ockam_core::ProfileIdentifier;
 
 /// Unique [`crate::ProfileChangeEvent`] identifier, computed as SHA256 of the event data
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
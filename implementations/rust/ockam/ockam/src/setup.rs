@@ -0,0 +1,81 @@
+//! Apply the `vault` and `profile` sections of node configuration files
+
+use crate::{ContactsDb, OckamError, Profile, ProfileVault};
+use ockam_core::Result;
+use ockam_node::{Context, NodeConfig, ProfileConfig, VaultConfig};
+use ockam_vault::SoftwareVault;
+use std::fs;
+use std::ops::DerefMut;
+use std::sync::{Arc, Mutex};
+
+/// The vault of a node, shared with its workers
+///
+/// Get it via [`Context::resource`] on nodes built with [`setup_vault_and_profile`].
+pub struct NodeVault(pub Arc<Mutex<dyn ProfileVault>>);
+
+/// The profile of a node, shared with its workers
+///
+/// Get it via [`Context::resource`] on nodes built with [`setup_vault_and_profile`].
+pub struct NodeProfile(pub Mutex<Profile>);
+
+/// Open the vault and the profile configured for a node, and share
+/// them with its workers as [`NodeVault`] and [`NodeProfile`]
+///
+/// Nodes without a `vault` section use a [`SoftwareVault`].  The
+/// profile is only opened if the `profile` section is present.  The
+/// software vault keeps secrets in memory, so a stored profile can
+/// only sign again with a vault keeping its keys, like a PKCS#11
+/// token (with the `ockam_vault_pkcs11` feature).
+///
+/// ```ignore
+/// let (ctx, mut executor) = NodeBuilder::new()
+///     .config_file("node.toml")
+///     .setup(ockam::setup_vault_and_profile)
+///     .build()?;
+/// ```
+pub fn setup_vault_and_profile(ctx: &Context, config: &NodeConfig) -> Result<()> {
+    let vault = open_vault(config.vault.as_ref().unwrap_or(&VaultConfig::Software))?;
+
+    if let Some(profile) = &config.profile {
+        let profile = open_profile(profile, vault.clone())?;
+        ctx.set_resource(NodeProfile(Mutex::new(profile)));
+    }
+
+    ctx.set_resource(NodeVault(vault));
+    Ok(())
+}
+
+fn open_vault(config: &VaultConfig) -> Result<Arc<Mutex<dyn ProfileVault>>> {
+    match config {
+        VaultConfig::Software => Ok(Arc::new(Mutex::new(SoftwareVault::default()))),
+        #[cfg(feature = "ockam_vault_pkcs11")]
+        VaultConfig::Pkcs11 { module, token, pin } => {
+            let vault = ockam_vault_pkcs11::Pkcs11Vault::open(module, token, pin)?;
+            Ok(Arc::new(Mutex::new(vault)))
+        }
+        #[cfg(not(feature = "ockam_vault_pkcs11"))]
+        VaultConfig::Pkcs11 { .. } => Err(OckamError::UnsupportedVaultBackend.into()),
+    }
+}
+
+/// Load the profile stored at the configured path, or create and
+/// store a new one
+fn open_profile(config: &ProfileConfig, vault: Arc<Mutex<dyn ProfileVault>>) -> Result<Profile> {
+    if !config.path.exists() {
+        let profile = Profile::create(None, vault)?;
+        fs::write(&config.path, profile.serialize_to_contact()?)
+            .map_err(|_| OckamError::FailedLoadProfile)?;
+        return Ok(profile);
+    }
+
+    let data = fs::read(&config.path).map_err(|_| OckamError::FailedLoadProfile)?;
+    let contact = Profile::deserialize_contact(&data)?;
+    contact.verify(vault.lock().unwrap().deref_mut())?;
+
+    Ok(Profile::new(
+        contact.identifier().clone(),
+        contact.change_events().to_vec(),
+        ContactsDb::default(),
+        vault,
+    ))
+}
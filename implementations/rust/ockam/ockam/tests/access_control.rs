@@ -0,0 +1,196 @@
+// These tests check that worker relays enforce access control
+// policies and report denied messages as dead letters
+
+use ockam::{
+    async_worker, AccessControl, Address, AllowedProfiles, Context, DeadLetter, LocalOnly,
+    ProfileIdentifier, RequireAttributes, Result, Routed, RouterMessage, SecureChannelOnly,
+    TransportMessage, Typed, Worker,
+};
+use std::time::Duration;
+
+/// Accepts local messages only, and replies to them
+struct LocalEcho;
+
+#[async_worker]
+impl Worker for LocalEcho {
+    type Message = String;
+    type Context = Context;
+
+    fn access_control(&self) -> Box<dyn AccessControl> {
+        Box::new(LocalOnly)
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.take()).await
+    }
+}
+
+/// A router for addresses of type 9 accepting local messages only
+struct LocalRouter;
+
+#[async_worker]
+impl Worker for LocalRouter {
+    type Message = RouterMessage;
+    type Context = Context;
+
+    fn access_control(&self) -> Box<dyn AccessControl> {
+        Box::new(LocalOnly)
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<RouterMessage>,
+    ) -> Result<()> {
+        if let RouterMessage::Route(mut msg) = msg.take() {
+            msg.onward.step();
+            ctx.forward_message(msg).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Replies to the messages accepted by its policy
+struct Guarded<P>(P);
+
+#[async_worker]
+impl<P: AccessControl + Clone> Worker for Guarded<P> {
+    type Message = String;
+    type Context = Context;
+
+    fn access_control(&self) -> Box<dyn AccessControl> {
+        Box::new(self.0.clone())
+    }
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.take()).await
+    }
+}
+
+/// A message to `onward`, as forwarded by a transport connection
+fn remote(ctx: &Context, onward: ockam::Route) -> Result<TransportMessage> {
+    let payload = ctx.codec::<String>().encode_message(&String::from("hi"))?;
+    let mut msg = TransportMessage::v2(onward, payload);
+    msg.return_ = ctx.address().into();
+    msg.local.transport = Some("tcp_conn".into());
+    Ok(msg)
+}
+
+async fn deny_remote_messages(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("local_echo", LocalEcho).await?;
+    ctx.start_worker("local_router", LocalRouter).await?;
+    ctx.register(9, "local_router").await?;
+
+    // Local messages are accepted
+    ctx.send_message("local_echo", String::from("hi")).await?;
+    assert_eq!(*ctx.receive::<String>().await?, "hi");
+
+    // Remote messages are denied by the worker
+    let msg = remote(ctx, "local_echo".into())?;
+    let payload = msg.payload.clone();
+    ctx.forward_message(msg).await?;
+    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+    assert_eq!(letter.address, "local_echo".into());
+    assert_eq!(letter.return_route, ctx.address().into());
+    assert_eq!(letter.payload, payload);
+
+    // Remote messages are denied by the router, before being routed
+    let onward = ockam::Route::new()
+        .append(Address::from_string("9#peer"))
+        .append("local_echo");
+    ctx.forward_message(remote(ctx, onward.into())?).await?;
+    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+    assert_eq!(letter.address, "local_router".into());
+    Ok(())
+}
+
+/// Check that the worker at `addr` denies a remote message, and
+/// accepts it once it was received through a secure channel which
+/// authenticated Alice and her credential
+async fn deny_unless_authenticated(ctx: &mut Context, addr: &str) -> Result<()> {
+    let msg = remote(ctx, addr.into())?;
+    ctx.forward_message(msg).await?;
+    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+    assert_eq!(letter.address, addr.into());
+
+    let mut msg = remote(ctx, addr.into())?;
+    msg.local.secure_channel = Some("channel".into());
+    msg.local.profile_id = Some(ProfileIdentifier::from_key_id("alice".into()));
+    msg.local
+        .credential_attributes
+        .insert("role".into(), "admin".into());
+    ctx.forward_message(msg).await?;
+    assert_eq!(*ctx.receive::<String>().await?, "hi");
+    Ok(())
+}
+
+async fn deny_unauthenticated_messages(ctx: &mut Context) -> Result<()> {
+    let alice = ProfileIdentifier::from_key_id("alice".into());
+    ctx.start_worker("channel_only", Guarded(SecureChannelOnly))
+        .await?;
+    ctx.start_worker("alice_only", Guarded(AllowedProfiles::new(vec![alice])))
+        .await?;
+    let admins = RequireAttributes::new().attribute("role", "admin");
+    ctx.start_worker("admins_only", Guarded(admins)).await?;
+
+    deny_unless_authenticated(ctx, "channel_only").await?;
+    deny_unless_authenticated(ctx, "alice_only").await?;
+    deny_unless_authenticated(ctx, "admins_only").await?;
+
+    // Messages authenticated for another profile or role are denied
+    let mut msg = remote(ctx, "alice_only".into())?;
+    msg.local.secure_channel = Some("channel".into());
+    msg.local.profile_id = Some(ProfileIdentifier::from_key_id("bob".into()));
+    msg.local
+        .credential_attributes
+        .insert("role".into(), "guest".into());
+    ctx.forward_message(msg.clone()).await?;
+    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+    assert_eq!(letter.address, "alice_only".into());
+
+    msg.onward = "admins_only".into();
+    ctx.forward_message(msg).await?;
+    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+    assert_eq!(letter.address, "admins_only".into());
+    Ok(())
+}
+
+#[test]
+fn relays_enforce_access_control() {
+    let (mut ctx, mut executor) = ockam::NodeBuilder::new()
+        .dead_letter_address("app")
+        .tracing(false)
+        .build()
+        .unwrap();
+
+    executor
+        .execute_test(
+            async move {
+                let res = deny_remote_messages(&mut ctx).await;
+                ctx.stop().await?;
+                res
+            },
+            Duration::from_secs(10),
+        )
+        .unwrap();
+}
+
+#[test]
+fn relays_enforce_authentication_policies() {
+    let (mut ctx, mut executor) = ockam::NodeBuilder::new()
+        .dead_letter_address("app")
+        .tracing(false)
+        .build()
+        .unwrap();
+
+    executor
+        .execute_test(
+            async move {
+                let res = deny_unauthenticated_messages(&mut ctx).await;
+                ctx.stop().await?;
+                res
+            },
+            Duration::from_secs(10),
+        )
+        .unwrap();
+}
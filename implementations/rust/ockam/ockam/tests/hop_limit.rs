@@ -0,0 +1,51 @@
+// This test checks that expired messages are reported to their
+// sender, whatever codec the node uses for payloads
+
+use ockam::{Bare, Context, HopLimitExceeded, PayloadKind, Result, TransportMessage, Typed};
+use std::time::Duration;
+
+/// An expired message from the test context to `nowhere`
+fn expired(ctx: &Context, kind: PayloadKind) -> TransportMessage {
+    let mut msg = TransportMessage::v2("nowhere".into(), vec![]);
+    msg.return_ = ctx.address().into();
+    msg.hop_limit = 0;
+    msg.kind = kind;
+    msg
+}
+
+async fn report_expired_messages(ctx: &mut Context) -> Result<()> {
+    assert_eq!(ctx.codec::<Typed<HopLimitExceeded>>().name(), "bare");
+
+    let msg = expired(ctx, PayloadKind::Data);
+    assert!(ctx.forward_message(msg).await.is_err());
+    let report = ctx.receive::<Typed<HopLimitExceeded>>().await?;
+    assert_eq!(report.onward, "nowhere".into());
+
+    // Expired reports are dropped without a new report, so the next
+    // message is the one sent afterwards
+    let msg = expired(ctx, PayloadKind::Report);
+    assert!(ctx.forward_message(msg).await.is_err());
+    ctx.send_message(ctx.address(), String::from("done")).await?;
+    assert_eq!(*ctx.receive::<String>().await?, "done");
+    Ok(())
+}
+
+#[test]
+fn expired_messages_are_reported_with_any_codec() {
+    let (mut ctx, mut executor) = ockam::NodeBuilder::new()
+        .codec(Bare)
+        .tracing(false)
+        .build()
+        .unwrap();
+
+    executor
+        .execute_test(
+            async move {
+                let res = report_expired_messages(&mut ctx).await;
+                ctx.stop().await?;
+                res
+            },
+            Duration::from_secs(10),
+        )
+        .unwrap();
+}
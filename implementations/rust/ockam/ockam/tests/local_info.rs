@@ -0,0 +1,121 @@
+// These tests check that local information about how a message
+// arrived is kept while it is routed through the node
+
+use ockam::{
+    async_worker, Address, AuthenticatedChannel, Context, Profile, ProfileIdentifier, Result,
+    Routed, RouterMessage, TransportMessage, Worker,
+};
+use ockam_vault::SoftwareVault;
+use std::sync::{Arc, Mutex};
+
+/// The transport, secure channel and profile a message arrived with
+type Arrival = (Option<Address>, Option<Address>, Option<ProfileIdentifier>);
+
+/// Replies with how each message arrived
+struct Inspector;
+
+#[async_worker]
+impl Worker for Inspector {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        let local = msg.local();
+        let arrival: Arrival = (
+            local.transport.clone(),
+            local.secure_channel.clone(),
+            local.profile_id.clone(),
+        );
+        ctx.send_message(msg.reply(), arrival).await
+    }
+}
+
+/// A router for addresses of type 9, forwarding messages to the rest
+/// of their route
+struct Forwarder;
+
+#[async_worker]
+impl Worker for Forwarder {
+    type Message = RouterMessage;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<RouterMessage>,
+    ) -> Result<()> {
+        let local = msg.local().clone();
+        if let RouterMessage::Route(mut msg) = msg.take() {
+            assert_eq!(msg.local.transport, local.transport);
+            msg.onward.step();
+            ctx.forward_message(msg).await?;
+        }
+        Ok(())
+    }
+}
+
+#[ockam::test]
+async fn routers_keep_local_info(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("inspector", Inspector).await?;
+    ctx.start_worker("forwarder", Forwarder).await?;
+    ctx.register(9, "forwarder").await?;
+
+    // Forward a message the way a transport connection worker does
+    let payload = ctx.codec::<String>().encode_message(&String::from("hi"))?;
+    let onward = ockam::Route::new()
+        .append(Address::from_string("9#peer"))
+        .append("inspector");
+    let mut msg = TransportMessage::v2(onward.into(), payload);
+    msg.return_ = ctx.address().into();
+    msg.local.transport = Some("tcp_conn".into());
+    ctx.forward_message(msg).await?;
+
+    let (transport, ..) = ctx.receive::<Arrival>().await?.take();
+    assert_eq!(transport, Some("tcp_conn".into()));
+    Ok(())
+}
+
+/// Bob authenticates Alice at the end of the key exchange of a
+/// secure channel
+fn authenticate_alice() -> Result<AuthenticatedChannel> {
+    let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+    let alice = Profile::create(None, vault.clone())?;
+    let mut bob = Profile::create(None, vault)?;
+    let alice_id = alice.identifier().clone();
+    bob.verify_and_add_contact(alice.to_contact())?;
+
+    let key_agreement_hash = [7u8; 32];
+    let proof = alice.generate_authentication_proof(&key_agreement_hash)?;
+
+    // A proof for another channel state doesn't authenticate Alice
+    assert!(bob
+        .authenticate_channel("other".into(), &[8u8; 32], &alice_id, &proof)
+        .is_err());
+
+    bob.authenticate_channel("channel".into(), &key_agreement_hash, &alice_id, &proof)
+}
+
+#[ockam::test]
+async fn secure_channels_mark_messages(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("inspector", Inspector).await?;
+    let channel = authenticate_alice()?;
+
+    // The channel marks the messages it decrypts before forwarding them
+    let payload = ctx.codec::<String>().encode_message(&String::from("hi"))?;
+    let mut msg = TransportMessage::v2("inspector".into(), payload);
+    msg.return_ = ctx.address().into();
+    msg.local.transport = Some("tcp_conn".into());
+    channel.mark(&mut msg);
+    ctx.forward_message(msg).await?;
+
+    let arrival = ctx.receive::<Arrival>().await?.take();
+    assert_eq!(
+        arrival,
+        (
+            Some("tcp_conn".into()),
+            Some("channel".into()),
+            Some(channel.profile_id().clone())
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,7 @@
+// This test checks that #[ockam::node] accepts node arguments
+
+#[ockam::node(worker_threads = 2, address = "root", tracing = false)]
+async fn main(context: ockam::Context) {
+    assert_eq!(context.address(), "root".into());
+    context.stop().await.unwrap();
+}
@@ -0,0 +1,7 @@
+// This test checks that #[ockam::node] causes a compile time error
+// if a node argument is given a value of the wrong type.
+
+#[ockam::node(worker_threads = "2")]
+async fn main(context: ockam::Context) {
+    context.stop().await.unwrap();
+}
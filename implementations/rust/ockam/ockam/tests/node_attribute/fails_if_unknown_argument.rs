@@ -0,0 +1,7 @@
+// This test checks that #[ockam::node] causes a compile time error
+// if it is given an unknown argument.
+
+#[ockam::node(threads = 2)]
+async fn main(context: ockam::Context) {
+    context.stop().await.unwrap();
+}
@@ -0,0 +1,68 @@
+// These tests check that nodes open the vault and profile of their
+// configuration file, and share them with their workers
+
+use ockam::{Context, NodeBuilder, NodeProfile, NodeVault, ProfileIdentifier, Result};
+use std::{path::PathBuf, time::Duration};
+
+/// Build a node from a configuration file storing its profile at
+/// `profile`, and return the identifier of the profile
+fn run_node(name: &str, profile: &PathBuf) -> ProfileIdentifier {
+    let path = std::env::temp_dir().join(format!("ockam_{}.toml", name));
+    let config = format!(
+        "tracing = false\n\n[vault]\nbackend = \"software\"\n\n[profile]\npath = {:?}\n",
+        profile
+    );
+    std::fs::write(&path, config).unwrap();
+
+    let (ctx, mut executor) = NodeBuilder::new()
+        .config_file(&path)
+        .setup(ockam::setup_vault_and_profile)
+        .build()
+        .unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    executor
+        .execute_test(
+            async move {
+                let res = shared_profile(&ctx).await;
+                ctx.stop().await?;
+                tx.send(res?).unwrap();
+                Ok(())
+            },
+            Duration::from_secs(10),
+        )
+        .unwrap();
+    rx.recv().unwrap()
+}
+
+async fn shared_profile(ctx: &Context) -> Result<ProfileIdentifier> {
+    // Workers get the vault and profile opened by the node
+    let worker = ctx.new_context("worker").await?;
+    assert!(worker.resource::<NodeVault>().is_some());
+    let profile = worker.resource::<NodeProfile>().unwrap();
+    let profile = profile.0.lock().unwrap();
+    profile.verify()?;
+    Ok(profile.identifier().clone())
+}
+
+#[test]
+fn nodes_open_configured_profile() {
+    let profile = std::env::temp_dir().join("ockam_node_setup.profile");
+    let _ = std::fs::remove_file(&profile);
+
+    // The first node creates and stores the profile, the next one loads it
+    let created = run_node("nodes_open_configured_profile", &profile);
+    assert!(profile.exists());
+    let loaded = run_node("nodes_open_configured_profile", &profile);
+    assert_eq!(created, loaded);
+
+    // Profiles which can't be verified are refused
+    std::fs::write(&profile, b"not a profile").unwrap();
+    let path = std::env::temp_dir().join("ockam_nodes_open_configured_profile.toml");
+    assert!(NodeBuilder::new()
+        .config_file(&path)
+        .setup(ockam::setup_vault_and_profile)
+        .build()
+        .is_err());
+    std::fs::remove_file(&profile).unwrap();
+}
@@ -0,0 +1,48 @@
+// These tests check that services published to a registry service
+// can be discovered
+
+use ockam::{
+    async_worker, Context, RemoteRegistry, Result, ServiceDiscovery, ServiceInfo, Worker,
+    REGISTRY_SERVICE_ADDRESS,
+};
+use std::time::Duration;
+
+/// Keeps its service published while it runs
+struct Publisher(ServiceInfo);
+
+#[async_worker]
+impl Worker for Publisher {
+    type Message = ();
+    type Context = Context;
+
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        RemoteRegistry::new(REGISTRY_SERVICE_ADDRESS).keep_published(ctx, self.0.clone())
+    }
+}
+
+#[ockam::test(registry_service = true)]
+async fn discover_services(ctx: &mut Context) -> Result<()> {
+    let registry = RemoteRegistry::new(REGISTRY_SERVICE_ADDRESS);
+    let service = ServiceInfo::new("echo", "echoer", Duration::from_secs(10)).metadata("v", "1");
+    registry.publish(ctx, service).await?;
+
+    let found = registry.query(ctx, "echo").await?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].route, "echoer".into());
+    assert_eq!(found[0].metadata["v"], "1");
+
+    registry.unpublish(ctx, "echo", &"echoer".into()).await?;
+    assert!(registry.query(ctx, "echo").await?.is_empty());
+    Ok(())
+}
+
+#[ockam::test(registry_service = true)]
+async fn discover_services_kept_published(ctx: &mut Context) -> Result<()> {
+    let service = ServiceInfo::new("echo", "publisher", Duration::from_secs(10));
+    ctx.start_worker("publisher", Publisher(service)).await?;
+
+    // The service is published from a task of the worker
+    let registry = RemoteRegistry::new(REGISTRY_SERVICE_ADDRESS);
+    while registry.query(ctx, "echo").await?.is_empty() {}
+    Ok(())
+}
@@ -0,0 +1,64 @@
+// These tests trace routes to the echo service of a node
+
+use ockam::{async_worker, Context, Result, Routed, RouterMessage, Worker, ECHO_SERVICE_ADDRESS};
+
+/// A router for addresses of type 9, forwarding messages to the rest
+/// of their route
+struct HopRouter;
+
+#[async_worker]
+impl Worker for HopRouter {
+    type Message = RouterMessage;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<RouterMessage>,
+    ) -> Result<()> {
+        if let RouterMessage::Route(mut msg) = msg.take() {
+            msg.onward.step();
+            ctx.forward_message(msg).await?;
+        }
+        Ok(())
+    }
+}
+
+#[ockam::test(echo_service = true)]
+async fn trace_to_echo_service(ctx: &mut Context) -> Result<()> {
+    let trace = ctx.trace_route(ECHO_SERVICE_ADDRESS).await?;
+
+    let hops: Vec<_> = trace.hops.iter().map(|hop| hop.address.clone()).collect();
+    assert_eq!(
+        hops,
+        vec!["app".into(), ECHO_SERVICE_ADDRESS.into(), "app".into()]
+    );
+    assert_eq!(trace.latencies().len(), 2);
+
+    ctx.ping(ECHO_SERVICE_ADDRESS).await?;
+    Ok(())
+}
+
+#[ockam::test(echo_service = true)]
+async fn trace_records_router_hops(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("hop_router", HopRouter).await?;
+    ctx.register(9, "hop_router").await?;
+
+    let route = ockam::Route::new()
+        .append(ockam::Address::from_string("9#peer"))
+        .append(ECHO_SERVICE_ADDRESS);
+    let trace = ctx.trace_route(route).await?;
+
+    // The echo service replies directly, without the router
+    let hops: Vec<_> = trace.hops.iter().map(|hop| hop.address.clone()).collect();
+    assert_eq!(
+        hops,
+        vec![
+            "app".into(),
+            "hop_router".into(),
+            ECHO_SERVICE_ADDRESS.into(),
+            "app".into()
+        ]
+    );
+    Ok(())
+}
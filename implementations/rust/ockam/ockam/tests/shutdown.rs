@@ -0,0 +1,123 @@
+// These tests check that a node stops its workers gracefully, and
+// aborts the workers which don't stop in time
+
+use ockam::{async_worker, Context, Result, Routed, TransportMessage, Worker};
+use std::{
+    sync::mpsc::{channel, Sender},
+    time::Duration,
+};
+
+/// Replies to messages, and tells the app when it stops
+struct Reporter(&'static str);
+
+#[async_worker]
+impl Worker for Reporter {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.take()).await
+    }
+
+    async fn stop(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.send_message("app", format!("{} stopped", self.0)).await
+    }
+}
+
+/// Waits for a message to its gate context before stopping
+struct Slow(Context);
+
+#[async_worker]
+impl Worker for Slow {
+    type Message = ();
+    type Context = Context;
+
+    async fn stop(&mut self, _ctx: &mut Context) -> Result<()> {
+        self.0.receive::<String>().await?;
+        Ok(())
+    }
+}
+
+#[ockam::test]
+async fn stops_workers_in_reverse_order(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("first", Reporter("first")).await?;
+    ctx.start_worker("second", Reporter("second")).await?;
+
+    // Queued messages are handled before the worker stops
+    ctx.send_message("first", String::from("hi")).await?;
+    let report = ctx.stop_with_timeout(Duration::from_secs(1)).await?;
+    assert!(report.is_clean());
+    assert_eq!(report.stopped, vec!["second".into(), "first".into()]);
+
+    let mut received = vec![];
+    for _ in 0..3 {
+        received.push(ctx.receive::<String>().await?.take());
+    }
+    let stopped: Vec<_> = received.iter().filter(|m| *m != "hi").collect();
+    assert_eq!(stopped, vec!["second stopped", "first stopped"]);
+    assert_eq!(received.last().unwrap(), "first stopped");
+    Ok(())
+}
+
+#[ockam::test]
+async fn refuses_transport_messages_while_stopping(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("target", Reporter("target")).await?;
+    let gate = ctx.new_context("gate").await?;
+    ctx.start_worker("slow", Slow(gate)).await?;
+    ctx.stop().await?;
+
+    // Messages arriving from a transport are refused...
+    let payload = ctx.codec::<String>().encode_message(&String::from("hi"))?;
+    let mut msg = TransportMessage::v2("target".into(), payload);
+    msg.return_ = ctx.address().into();
+    msg.local.transport = Some("tcp_conn".into());
+    assert!(ctx.forward_message(msg).await.is_err());
+
+    // ...while local workers can still talk to each other
+    ctx.send_message("target", String::from("hi")).await?;
+    assert_eq!(*ctx.receive::<String>().await?, "hi");
+
+    // Let the node finish stopping
+    ctx.send_message("gate", String::from("go")).await?;
+    Ok(())
+}
+
+/// Never stops, and tells the test when it is dropped
+struct Stuck(Sender<()>);
+
+impl Drop for Stuck {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+#[async_worker]
+impl Worker for Stuck {
+    type Message = ();
+    type Context = Context;
+
+    async fn stop(&mut self, _ctx: &mut Context) -> Result<()> {
+        std::future::pending().await
+    }
+}
+
+#[test]
+fn aborts_workers_missing_the_deadline() {
+    let (ctx, mut executor) = ockam::NodeBuilder::new().tracing(false).build().unwrap();
+    let (dropped_tx, dropped_rx) = channel();
+
+    executor
+        .execute_test(
+            async move {
+                ctx.start_worker("stuck", Stuck(dropped_tx)).await?;
+                let report = ctx.stop_with_timeout(Duration::from_millis(100)).await?;
+                assert_eq!(report.failed, vec!["stuck".into()]);
+                Ok(())
+            },
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+    // The worker is dropped once its task is aborted
+    dropped_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+}
@@ -0,0 +1,16 @@
+// This test checks that an attribute macro #[ockam::test] exists
+// and can be used with an async test function
+
+#[ockam::test]
+async fn my_test(ctx: &mut ockam::Context) -> ockam::Result<()> {
+    ctx.list_workers().await?;
+    Ok(())
+}
+
+#[ockam::test(timeout = 1000)]
+async fn my_test_with_timeout(ctx: &mut ockam::Context) -> ockam::Result<()> {
+    ctx.list_workers().await?;
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+// This test checks that #[ockam::test] causes a compile time error
+// if the item it is defined on is not an async function.
+
+#[ockam::test]
+fn my_test(_ctx: &mut ockam::Context) -> ockam::Result<()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+// This test checks that #[ockam::test] causes a compile time error
+// if the function does not return a result.
+
+#[ockam::test]
+async fn my_test(ctx: &mut ockam::Context) {
+    ctx.stop().await.unwrap();
+}
+
+fn main() {}
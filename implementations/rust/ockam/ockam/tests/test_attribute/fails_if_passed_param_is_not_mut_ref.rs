@@ -0,0 +1,9 @@
+// This test checks that #[ockam::test] causes a compile time error
+// if the function is passed a `Context` by value.
+
+#[ockam::test]
+async fn my_test(ctx: ockam::Context) -> ockam::Result<()> {
+    ctx.stop().await
+}
+
+fn main() {}
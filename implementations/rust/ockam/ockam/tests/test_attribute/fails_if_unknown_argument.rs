@@ -0,0 +1,9 @@
+// This test checks that #[ockam::test] causes a compile time error
+// if it is given an argument other than `timeout`.
+
+#[ockam::test(retries = 3)]
+async fn my_test(ctx: &mut ockam::Context) -> ockam::Result<()> {
+    ctx.stop().await
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+// These tests run async test functions with the #[ockam::test]
+// attribute, which the trybuild tests only compile
+
+use ockam::{async_worker, Context, Error, Result, Routed, Worker};
+
+struct Echo;
+
+#[async_worker]
+impl Worker for Echo {
+    type Message = String;
+    type Context = Context;
+
+    async fn handle_message(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.take()).await
+    }
+}
+
+#[ockam::test]
+async fn runs_in_a_node(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("echo", Echo).await?;
+    ctx.send_message("echo", String::from("Hello Ockam!")).await?;
+    assert_eq!(*ctx.receive::<String>().await?, "Hello Ockam!");
+    Ok(())
+}
+
+// Every test starts its own node, so addresses don't collide
+#[ockam::test]
+async fn runs_in_a_fresh_node(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("echo", Echo).await?;
+    Ok(())
+}
+
+// The test only returns (and fails) once its node has stopped
+#[ockam::test(timeout = 200)]
+#[should_panic]
+async fn fails_on_timeout(ctx: &mut Context) -> Result<()> {
+    // Nothing is ever sent to the test context
+    ctx.receive::<String>().await?;
+    Ok(())
+}
+
+#[ockam::test]
+#[should_panic]
+async fn fails_on_error(_ctx: &mut Context) -> Result<()> {
+    Err(Error::new(1, "test"))
+}
+
+#[ockam::test]
+#[should_panic(expected = "test panicked")]
+async fn resumes_panics(_ctx: &mut Context) -> Result<()> {
+    panic!("test panicked")
+}
@@ -4,6 +4,7 @@ fn trybuild() {
     t.pass("tests/node_attribute/can_be_used_on_main.rs");
     t.pass("tests/node_attribute/can_be_used_on_any_fn.rs");
     t.pass("tests/node_attribute/can_be_used_on_any_fn_ockam_use_as_o.rs");
+    t.pass("tests/node_attribute/can_be_used_with_node_arguments.rs");
     t.compile_fail("tests/node_attribute/fails_if_item_is_not_a_function.rs");
     t.compile_fail("tests/node_attribute/fails_if_function_is_not_async.rs");
     t.compile_fail("tests/node_attribute/fails_if_passed_param_is_self.rs");
@@ -14,4 +15,18 @@ fn trybuild() {
     t.compile_fail("tests/node_attribute/fails_if_unused_context.rs");
     t.compile_fail("tests/node_attribute/fails_if_unused_context_empty_fnbody.rs");
     t.compile_fail("tests/node_attribute/fails_if_more_than_one_arg.rs");
+    t.compile_fail("tests/node_attribute/fails_if_unknown_argument.rs");
+    t.compile_fail("tests/node_attribute/fails_if_argument_has_wrong_type.rs");
+    t.pass("tests/test_attribute/can_be_used_on_async_test_fn.rs");
+    t.compile_fail("tests/test_attribute/fails_if_function_is_not_async.rs");
+    t.compile_fail("tests/test_attribute/fails_if_passed_param_is_not_mut_ref.rs");
+    t.compile_fail("tests/test_attribute/fails_if_no_result_returned.rs");
+    t.compile_fail("tests/test_attribute/fails_if_unknown_argument.rs");
+    t.pass("tests/worker_attribute/can_be_used_on_impl_block.rs");
+    t.pass("tests/worker_attribute/can_use_async_shutdown.rs");
+    t.compile_fail("tests/worker_attribute/fails_if_handler_is_not_async.rs");
+    t.compile_fail("tests/worker_attribute/fails_if_message_is_not_routed.rs");
+    t.compile_fail("tests/worker_attribute/fails_if_no_handlers.rs");
+    t.compile_fail("tests/worker_attribute/fails_if_duplicate_handler.rs");
+    t.compile_fail("tests/worker_attribute/fails_if_used_on_trait_impl.rs");
 }
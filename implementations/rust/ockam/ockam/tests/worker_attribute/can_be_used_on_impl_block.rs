@@ -0,0 +1,43 @@
+// This test checks that #[ockam::worker] generates a worker that
+// dispatches several message types to their handlers
+
+use ockam::{Context, Result, Routed};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Ping(u8);
+
+#[derive(Serialize, Deserialize)]
+pub struct Add(u8, u8);
+
+pub struct Calculator;
+
+#[ockam::worker]
+impl Calculator {
+    #[handler]
+    async fn on_ping(&mut self, ctx: &mut Context, msg: Routed<Ping>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.0).await
+    }
+
+    #[handler]
+    async fn on_add(&mut self, ctx: &mut Context, msg: Routed<Add>) -> Result<()> {
+        ctx.send_message(msg.reply(), msg.0 + msg.1).await
+    }
+}
+
+#[ockam::node]
+async fn main(mut ctx: Context) {
+    ctx.start_worker("calculator", Calculator).await.unwrap();
+
+    ctx.send_message("calculator", CalculatorMessage::from(Ping(7)))
+        .await
+        .unwrap();
+    assert_eq!(*ctx.receive::<u8>().await.unwrap(), 7);
+
+    ctx.send_message("calculator", CalculatorMessage::from(Add(2, 3)))
+        .await
+        .unwrap();
+    assert_eq!(*ctx.receive::<u8>().await.unwrap(), 5);
+
+    ctx.stop().await.unwrap();
+}
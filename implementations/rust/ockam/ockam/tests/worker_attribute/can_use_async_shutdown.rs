@@ -0,0 +1,35 @@
+// This test checks that an async #[shutdown] method can send messages
+// while the worker is stopping
+
+use ockam::{Context, Result, Routed};
+use std::time::Duration;
+
+pub struct Greeter;
+
+#[ockam::worker]
+impl Greeter {
+    #[handler]
+    async fn on_name(&mut self, ctx: &mut Context, msg: Routed<String>) -> Result<()> {
+        ctx.send_message(msg.reply(), format!("Hello {}", msg.take()))
+            .await
+    }
+
+    #[shutdown]
+    async fn goodbye(&mut self, ctx: &mut Context) -> Result<()> {
+        ctx.send_message("app", String::from("Goodbye")).await
+    }
+}
+
+#[ockam::node]
+async fn main(mut ctx: Context) {
+    ctx.start_worker("greeter", Greeter).await.unwrap();
+
+    ctx.send_message("greeter", GreeterMessage::from(String::from("Alice")))
+        .await
+        .unwrap();
+    assert_eq!(*ctx.receive::<String>().await.unwrap(), "Hello Alice");
+
+    let report = ctx.stop_with_timeout(Duration::from_secs(1)).await.unwrap();
+    assert!(report.is_clean());
+    assert_eq!(*ctx.receive::<String>().await.unwrap(), "Goodbye");
+}
@@ -0,0 +1,27 @@
+// This test checks that #[ockam::worker] causes a compile time error
+// if two handlers accept the same message type.
+
+pub struct Echo;
+
+#[ockam::worker]
+impl Echo {
+    #[handler]
+    async fn on_string(
+        &mut self,
+        _ctx: &mut ockam::Context,
+        _msg: ockam::Routed<String>,
+    ) -> ockam::Result<()> {
+        Ok(())
+    }
+
+    #[handler]
+    async fn on_other_string(
+        &mut self,
+        _ctx: &mut ockam::Context,
+        _msg: ockam::Routed<String>,
+    ) -> ockam::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// This test checks that #[ockam::worker] causes a compile time error
+// if a handler is not an async function.
+
+pub struct Echo;
+
+#[ockam::worker]
+impl Echo {
+    #[handler]
+    fn on_string(
+        &mut self,
+        _ctx: &mut ockam::Context,
+        _msg: ockam::Routed<String>,
+    ) -> ockam::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {}
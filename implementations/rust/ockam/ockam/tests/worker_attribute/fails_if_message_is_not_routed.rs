@@ -0,0 +1,14 @@
+// This test checks that #[ockam::worker] causes a compile time error
+// if a handler does not take a `ockam::Routed<M>` message.
+
+pub struct Echo;
+
+#[ockam::worker]
+impl Echo {
+    #[handler]
+    async fn on_string(&mut self, _ctx: &mut ockam::Context, _msg: String) -> ockam::Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+// This test checks that #[ockam::worker] causes a compile time error
+// if the impl block has no handlers.
+
+pub struct Echo;
+
+#[ockam::worker]
+impl Echo {
+    fn helper(&self) {}
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// This test checks that #[ockam::worker] causes a compile time error
+// if it is used on a trait impl block.
+
+pub struct Echo;
+
+#[ockam::worker]
+impl Default for Echo {
+    fn default() -> Self {
+        Echo
+    }
+}
+
+fn main() {}
@@ -0,0 +1,43 @@
+// These tests run workers generated by #[ockam::worker], which the
+// trybuild tests only compile
+
+use ockam::{Address, Context, Result, Routed, TransportMessage};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Ping;
+
+/// Replies with the transport which delivered each message
+pub struct Inspector;
+
+#[ockam::worker]
+impl Inspector {
+    #[handler]
+    async fn on_ping(&mut self, ctx: &mut Context, msg: Routed<Ping>) -> Result<()> {
+        let transport = msg.local().transport.clone();
+        ctx.send_message(msg.reply(), transport).await
+    }
+}
+
+#[ockam::test]
+async fn handlers_see_local_info(ctx: &mut Context) -> Result<()> {
+    ctx.start_worker("inspector", Inspector).await?;
+
+    // Forward a message the way a transport connection worker does
+    let payload = ctx
+        .codec::<InspectorMessage>()
+        .encode_message(&InspectorMessage::from(Ping))?;
+    let mut msg = TransportMessage::v2("inspector".into(), payload);
+    msg.return_ = ctx.address().into();
+    msg.local.transport = Some("tcp_conn".into());
+    ctx.forward_message(msg).await?;
+
+    let transport = ctx.receive::<Option<Address>>().await?.take();
+    assert_eq!(transport, Some("tcp_conn".into()));
+
+    // Messages sent by local workers have no transport
+    ctx.send_message("inspector", InspectorMessage::from(Ping))
+        .await?;
+    assert_eq!(ctx.receive::<Option<Address>>().await?.take(), None);
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! Worker access control policies
+//!
+//! A worker declares its policy via [`Worker::access_control`].  The
+//! node checks every incoming message against it before calling
+//! [`Worker::handle_message`], and drops messages which are not
+//! authorized.
+//!
+//! [`Worker::access_control`]: crate::Worker::access_control
+//! [`Worker::handle_message`]: crate::Worker::handle_message
+
+use crate::{
+    lib::{BTreeMap, BTreeSet, Box, String, Vec},
+    LocalInfo, ProfileIdentifier, Route,
+};
+
+/// A policy deciding which messages a worker accepts
+pub trait AccessControl: Send + Sync + 'static {
+    /// Check if a message with the given return route and local
+    /// information may be delivered to the worker
+    fn is_authorized(&self, return_route: &Route, local: &LocalInfo) -> bool;
+}
+
+/// Accept all messages
+///
+/// This is the default policy of workers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AllowAll;
+
+impl AccessControl for AllowAll {
+    fn is_authorized(&self, _: &Route, _: &LocalInfo) -> bool {
+        true
+    }
+}
+
+/// Only accept messages sent by workers on the same node
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalOnly;
+
+impl AccessControl for LocalOnly {
+    fn is_authorized(&self, _: &Route, local: &LocalInfo) -> bool {
+        local.transport.is_none()
+    }
+}
+
+/// Only accept messages received through a secure channel
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SecureChannelOnly;
+
+impl AccessControl for SecureChannelOnly {
+    fn is_authorized(&self, _: &Route, local: &LocalInfo) -> bool {
+        local.secure_channel.is_some()
+    }
+}
+
+/// Only accept messages from a set of profiles, authenticated by
+/// the secure channel they were received through
+#[derive(Clone, Debug, Default)]
+pub struct AllowedProfiles {
+    profiles: BTreeSet<ProfileIdentifier>,
+}
+
+impl AllowedProfiles {
+    /// Create a policy accepting the given profiles
+    pub fn new<I: IntoIterator<Item = ProfileIdentifier>>(profiles: I) -> Self {
+        Self {
+            profiles: profiles.into_iter().collect(),
+        }
+    }
+}
+
+impl AccessControl for AllowedProfiles {
+    fn is_authorized(&self, _: &Route, local: &LocalInfo) -> bool {
+        local
+            .profile_id
+            .as_ref()
+            .map(|id| self.profiles.contains(id))
+            .unwrap_or(false)
+    }
+}
+
+/// Only accept messages from holders of a credential with the
+/// given attribute values
+#[derive(Clone, Debug, Default)]
+pub struct RequireAttributes {
+    attributes: BTreeMap<String, String>,
+}
+
+impl RequireAttributes {
+    /// Create a policy requiring no attributes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require a credential attribute to have a specific value
+    pub fn attribute<L: Into<String>, V: Into<String>>(mut self, label: L, value: V) -> Self {
+        self.attributes.insert(label.into(), value.into());
+        self
+    }
+}
+
+impl AccessControl for RequireAttributes {
+    fn is_authorized(&self, _: &Route, local: &LocalInfo) -> bool {
+        self.attributes
+            .iter()
+            .all(|(label, value)| local.credential_attributes.get(label) == Some(value))
+    }
+}
+
+/// Accept messages which are accepted by all of the given policies
+#[derive(Default)]
+pub struct AllOf(pub Vec<Box<dyn AccessControl>>);
+
+impl AccessControl for AllOf {
+    fn is_authorized(&self, return_route: &Route, local: &LocalInfo) -> bool {
+        self.0
+            .iter()
+            .all(|policy| policy.is_authorized(return_route, local))
+    }
+}
+
+/// Accept messages which are accepted by any of the given policies
+#[derive(Default)]
+pub struct AnyOf(pub Vec<Box<dyn AccessControl>>);
+
+impl AccessControl for AnyOf {
+    fn is_authorized(&self, return_route: &Route, local: &LocalInfo) -> bool {
+        self.0
+            .iter()
+            .any(|policy| policy.is_authorized(return_route, local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::ToString;
+
+    fn remote() -> LocalInfo {
+        LocalInfo {
+            transport: Some("tcp".into()),
+            ..LocalInfo::default()
+        }
+    }
+
+    #[test]
+    fn local_policy() {
+        let route = Route::from("app");
+        assert!(LocalOnly.is_authorized(&route, &LocalInfo::default()));
+        assert!(!LocalOnly.is_authorized(&route, &remote()));
+    }
+
+    #[test]
+    fn secure_channel_policy() {
+        let route = Route::from("app");
+        let mut local = remote();
+        assert!(!SecureChannelOnly.is_authorized(&route, &local));
+        local.secure_channel = Some("channel".into());
+        assert!(SecureChannelOnly.is_authorized(&route, &local));
+    }
+
+    #[test]
+    fn profile_and_attribute_policies() {
+        let route = Route::from("app");
+        let alice = ProfileIdentifier::from_key_id("alice".to_string());
+        let bob = ProfileIdentifier::from_key_id("bob".to_string());
+        let mut local = remote();
+        assert!(!AllowedProfiles::new(vec![alice.clone()]).is_authorized(&route, &local));
+
+        local.profile_id = Some(alice.clone());
+        local
+            .credential_attributes
+            .insert("role".to_string(), "admin".to_string());
+        assert!(AllowedProfiles::new(vec![alice]).is_authorized(&route, &local));
+        assert!(!AllowedProfiles::new(vec![bob]).is_authorized(&route, &local));
+
+        let admin = RequireAttributes::new().attribute("role", "admin");
+        let guest = RequireAttributes::new().attribute("role", "guest");
+        assert!(admin.is_authorized(&route, &local));
+        assert!(!guest.is_authorized(&route, &local));
+        assert!(!admin.is_authorized(&route, &remote()));
+    }
+
+    #[test]
+    fn combined_policies() {
+        let route = Route::from("app");
+        let local = LocalInfo::default();
+
+        let any = AnyOf(vec![Box::new(LocalOnly), Box::new(AllOf::default())]);
+        let all = AllOf(vec![Box::new(AllowAll), Box::new(LocalOnly)]);
+        assert!(any.is_authorized(&route, &remote()));
+        assert!(all.is_authorized(&route, &local));
+        assert!(!all.is_authorized(&route, &remote()));
+        assert!(!AnyOf::default().is_authorized(&route, &local));
+    }
+}
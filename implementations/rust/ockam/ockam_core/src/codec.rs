@@ -0,0 +1,196 @@
+//! Message payload codecs
+//!
+//! By default message payloads are encoded with bincode (see
+//! [`Message::encode`]).  A [`Codec`] allows a node to use a
+//! different wire format for all, or only some message types, for
+//! example to interoperate with other Ockam implementations.
+
+use crate::{Encoded, Message, Result};
+use bincode::Options;
+
+/// Errors from encoding or decoding payloads with a [`Codec`]
+#[derive(Clone, Copy, Debug)]
+pub enum CodecError {
+    /// The message could not be encoded
+    EncodeFailed,
+    /// The payload could not be decoded
+    DecodeFailed,
+}
+
+impl CodecError {
+    /// Integer code associated with the error domain.
+    pub const DOMAIN_CODE: u32 = 17_000;
+    /// Descriptive name for the error domain.
+    pub const DOMAIN_NAME: &'static str = "OCKAM_CODEC";
+}
+
+impl From<CodecError> for crate::Error {
+    fn from(e: CodecError) -> crate::Error {
+        crate::Error::new(
+            CodecError::DOMAIN_CODE + (e as u32),
+            CodecError::DOMAIN_NAME,
+        )
+    }
+}
+
+/// A callback receiving a type-erased deserializer
+pub type DecodeFn<'f> = dyn for<'de> FnMut(
+        &mut dyn erased_serde::Deserializer<'de>,
+    ) -> crate::lib::Result<(), erased_serde::Error>
+    + 'f;
+
+/// A wire format for message payloads
+///
+/// This trait is object safe so that codecs can be selected at
+/// runtime.  Use [`encode_message`] and [`decode_message`] to
+/// convert typed messages.
+///
+/// [`encode_message`]: #method.encode_message
+/// [`decode_message`]: #method.decode_message
+pub trait Codec: Send + Sync + 'static {
+    /// A short name of this codec, e.g. `"bincode"`
+    fn name(&self) -> &'static str;
+
+    /// Encode a serializable value
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Encoded>;
+
+    /// Pass a deserializer over `data` to the given callback
+    fn decode(&self, data: &[u8], f: &mut DecodeFn) -> Result<()>;
+}
+
+impl dyn Codec {
+    /// Encode a message with this codec
+    pub fn encode_message<M: Message>(&self, msg: &M) -> Result<Encoded> {
+        self.encode(msg)
+    }
+
+    /// Decode a message with this codec
+    pub fn decode_message<M: Message>(&self, data: &[u8]) -> Result<M> {
+        let mut msg = None;
+        self.decode(data, &mut |de| {
+            msg = Some(erased_serde::deserialize(de)?);
+            Ok(())
+        })?;
+        msg.ok_or_else(|| CodecError::DecodeFailed.into())
+    }
+}
+
+/// The default codec, compatible with [`Message::encode`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bincode;
+
+impl Codec for Bincode {
+    fn name(&self) -> &'static str {
+        "bincode"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Encoded> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn decode(&self, data: &[u8], f: &mut DecodeFn) -> Result<()> {
+        // Same options as `bincode::deserialize`
+        let opts = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes();
+        let mut de = bincode::Deserializer::from_slice(data, opts);
+        f(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|_| CodecError::DecodeFailed.into())
+    }
+}
+
+/// A JSON codec, mostly useful for debugging
+#[cfg(feature = "json")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Codec for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Encoded> {
+        serde_json::to_vec(value).map_err(|_| CodecError::EncodeFailed.into())
+    }
+
+    fn decode(&self, data: &[u8], f: &mut DecodeFn) -> Result<()> {
+        let mut de = serde_json::Deserializer::from_slice(data);
+        f(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|_| CodecError::DecodeFailed)?;
+        de.end().map_err(|_| CodecError::DecodeFailed.into())
+    }
+}
+
+/// A CBOR (RFC 7049) codec
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    fn name(&self) -> &'static str {
+        "cbor"
+    }
+
+    fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<Encoded> {
+        serde_cbor::to_vec(&value).map_err(|_| CodecError::EncodeFailed.into())
+    }
+
+    fn decode(&self, data: &[u8], f: &mut DecodeFn) -> Result<()> {
+        let mut de = serde_cbor::Deserializer::from_slice(data);
+        f(&mut <dyn erased_serde::Deserializer>::erase(&mut de))
+            .map_err(|_| CodecError::DecodeFailed)?;
+        de.end().map_err(|_| CodecError::DecodeFailed.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::{String, ToString};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ping {
+        seq: u64,
+        name: String,
+    }
+
+    fn ping() -> Ping {
+        Ping {
+            seq: 42,
+            name: "ping".to_string(),
+        }
+    }
+
+    fn roundtrip(codec: &dyn Codec) {
+        let data = codec.encode_message(&ping()).unwrap();
+        assert_eq!(codec.decode_message::<Ping>(&data).unwrap(), ping());
+        assert!(codec.decode_message::<Ping>(&data[..1]).is_err());
+    }
+
+    #[test]
+    fn bincode_roundtrip() {
+        roundtrip(&Bincode);
+    }
+
+    #[test]
+    fn bincode_matches_message_encoding() {
+        let codec: &dyn Codec = &Bincode;
+        let data = codec.encode_message(&ping()).unwrap();
+        assert_eq!(data, ping().encode().unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        roundtrip(&Json);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_roundtrip() {
+        roundtrip(&Cbor);
+    }
+}
@@ -0,0 +1,287 @@
+//! Typed message envelopes
+//!
+//! Plain messages are encoded without any information about their
+//! type, so a payload decoded into the wrong type can only be
+//! reported as a generic decoding failure.  A [`Typed`] message
+//! carries an [`EnvelopeHeader`] with a stable type identifier and a
+//! schema version, which lets receivers reject unexpected types and
+//! migrate older schema versions explicitly.
+
+use crate::{
+    lib::{
+        fmt::{self, Display, Formatter},
+        Deref, DerefMut, String, ToString, Vec,
+    },
+    Encoded, Message, Result,
+};
+use bincode::Options;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Errors from decoding a typed message envelope
+#[derive(Clone, Copy, Debug)]
+pub enum EnvelopeError {
+    /// The envelope does not contain the expected message type
+    TypeMismatch,
+    /// The schema version of the envelope is not supported
+    UnsupportedVersion,
+}
+
+impl EnvelopeError {
+    /// Integer code associated with the error domain.
+    pub const DOMAIN_CODE: u32 = 16_000;
+    /// Descriptive name for the error domain.
+    pub const DOMAIN_NAME: &'static str = "OCKAM_ENVELOPE";
+}
+
+impl From<EnvelopeError> for crate::Error {
+    fn from(e: EnvelopeError) -> crate::Error {
+        crate::Error::new(
+            EnvelopeError::DOMAIN_CODE + (e as u32),
+            EnvelopeError::DOMAIN_NAME,
+        )
+    }
+}
+
+/// A message type with a stable identifier and schema version
+///
+/// Wrap a value in [`Typed`] to send it in a tagged envelope.
+pub trait TypedMessage: Message {
+    /// A stable identifier for this message type, e.g. `"ockam.ping"`
+    const TYPE_ID: &'static str;
+
+    /// The current schema version of this message type
+    const VERSION: u16;
+
+    /// Decode a payload encoded with an older schema version
+    ///
+    /// The default implementation rejects all older versions.
+    #[allow(clippy::ptr_arg)]
+    fn migrate(_version: u16, _payload: &Encoded) -> Result<Self> {
+        Err(EnvelopeError::UnsupportedVersion.into())
+    }
+}
+
+/// The header of a typed message envelope
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Eq, PartialEq)]
+pub struct EnvelopeHeader {
+    /// The stable identifier of the message type
+    pub type_id: String,
+    /// The schema version the payload was encoded with
+    pub version: u16,
+}
+
+impl EnvelopeHeader {
+    /// Read the envelope header of an encoded message, if it has one
+    ///
+    /// This is meant for diagnostics, e.g. to report which message
+    /// type was received when decoding a payload failed.  Only
+    /// payloads encoded with [`Bincode`](crate::Bincode) can be read.
+    #[allow(clippy::ptr_arg)]
+    pub fn peek(data: &Encoded) -> Option<Self> {
+        bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(data.len() as u64)
+            .deserialize::<RawEnvelope>(data)
+            .ok()
+            .map(|raw| raw.header)
+    }
+}
+
+impl Display for EnvelopeHeader {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "`{}` v{}", self.type_id, self.version)
+    }
+}
+
+/// The wire representation of a typed envelope
+#[derive(Serialize, Deserialize)]
+struct RawEnvelope {
+    header: EnvelopeHeader,
+    payload: Vec<u8>,
+}
+
+/// A message wrapped in a typed envelope
+///
+/// Decoding a `Typed<M>` fails if the envelope holds a different
+/// message type, or a schema version newer than `M::VERSION`.  Older
+/// versions are passed to [`TypedMessage::migrate`].
+pub struct Typed<M: TypedMessage>(M);
+
+impl<M: TypedMessage> Typed<M> {
+    /// Wrap a message in a typed envelope
+    pub fn new(inner: M) -> Self {
+        Self(inner)
+    }
+
+    /// Consume the envelope
+    pub fn take(self) -> M {
+        self.0
+    }
+
+    /// Decode a typed envelope, reporting envelope errors explicitly
+    ///
+    /// Unlike [`Message::decode`], this returns an
+    /// [`EnvelopeError`] if the envelope holds a different message
+    /// type or an unsupported schema version.
+    #[allow(clippy::ptr_arg)]
+    pub fn open(data: &Encoded) -> Result<M> {
+        let RawEnvelope { header, payload } = bincode::deserialize(data)?;
+        Self::unpack(&header, &payload)
+    }
+
+    #[allow(clippy::ptr_arg)]
+    fn unpack(header: &EnvelopeHeader, payload: &Encoded) -> Result<M> {
+        if header.type_id != M::TYPE_ID {
+            Err(EnvelopeError::TypeMismatch.into())
+        } else if header.version == M::VERSION {
+            M::decode(payload)
+        } else if header.version < M::VERSION {
+            M::migrate(header.version, payload)
+        } else {
+            Err(EnvelopeError::UnsupportedVersion.into())
+        }
+    }
+}
+
+impl<M: TypedMessage> From<M> for Typed<M> {
+    fn from(inner: M) -> Self {
+        Self(inner)
+    }
+}
+
+impl<M: TypedMessage> Deref for Typed<M> {
+    type Target = M;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<M: TypedMessage> DerefMut for Typed<M> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<M: TypedMessage> Serialize for Typed<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> crate::lib::Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let payload = self
+            .0
+            .encode()
+            .map_err(|e| S::Error::custom(format!("failed to encode payload: {}", e)))?;
+        RawEnvelope {
+            header: EnvelopeHeader {
+                type_id: M::TYPE_ID.to_string(),
+                version: M::VERSION,
+            },
+            payload,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, M: TypedMessage> Deserialize<'de> for Typed<M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> crate::lib::Result<Self, D::Error> {
+        use de::Error;
+
+        let RawEnvelope { header, payload } = RawEnvelope::deserialize(deserializer)?;
+        Self::unpack(&header, &payload).map(Self).map_err(|e| {
+            D::Error::custom(format!(
+                "failed to decode {} as `{}` v{}: {}",
+                header,
+                M::TYPE_ID,
+                M::VERSION,
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct PingV1 {
+        seq: u8,
+    }
+
+    impl TypedMessage for PingV1 {
+        const TYPE_ID: &'static str = "test.ping";
+        const VERSION: u16 = 1;
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct PingV2 {
+        seq: u32,
+    }
+
+    impl TypedMessage for PingV2 {
+        const TYPE_ID: &'static str = "test.ping";
+        const VERSION: u16 = 2;
+
+        fn migrate(version: u16, payload: &Encoded) -> Result<Self> {
+            match version {
+                1 => PingV1::decode(payload).map(|v1| PingV2 { seq: v1.seq as u32 }),
+                _ => Err(EnvelopeError::UnsupportedVersion.into()),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Pong;
+
+    impl TypedMessage for Pong {
+        const TYPE_ID: &'static str = "test.pong";
+        const VERSION: u16 = 1;
+    }
+
+    #[test]
+    fn typed_roundtrip() {
+        let data = Typed::new(PingV1 { seq: 7 }).encode().unwrap();
+        let msg = Typed::<PingV1>::decode(&data).unwrap();
+        assert_eq!(msg.take(), PingV1 { seq: 7 });
+    }
+
+    #[test]
+    fn rejects_other_type() {
+        let data = Typed::new(Pong).encode().unwrap();
+        assert!(Typed::<PingV1>::decode(&data).is_err());
+
+        let err = Typed::<PingV1>::open(&data).unwrap_err();
+        assert_eq!(err.code(), EnvelopeError::DOMAIN_CODE);
+    }
+
+    #[test]
+    fn migrates_older_version() {
+        let data = Typed::new(PingV1 { seq: 7 }).encode().unwrap();
+        let msg = Typed::<PingV2>::decode(&data).unwrap();
+        assert_eq!(msg.take(), PingV2 { seq: 7 });
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let data = Typed::new(PingV2 { seq: 7 }).encode().unwrap();
+        assert!(Typed::<PingV1>::decode(&data).is_err());
+
+        let err = Typed::<PingV1>::open(&data).unwrap_err();
+        assert_eq!(
+            err.code(),
+            EnvelopeError::DOMAIN_CODE + EnvelopeError::UnsupportedVersion as u32
+        );
+    }
+
+    #[test]
+    fn peek_header() {
+        let data = Typed::new(PingV2 { seq: 7 }).encode().unwrap();
+        let header = EnvelopeHeader::peek(&data).unwrap();
+        assert_eq!(header.type_id, "test.ping");
+        assert_eq!(header.version, 2);
+        assert_eq!(format!("{}", header), "`test.ping` v2");
+
+        assert!(EnvelopeHeader::peek(&PingV1 { seq: 7 }.encode().unwrap()).is_none());
+    }
+}
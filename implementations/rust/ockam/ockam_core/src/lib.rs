@@ -35,13 +35,24 @@ pub extern crate hex;
 #[macro_use]
 pub extern crate async_trait;
 
+#[cfg(feature = "std")]
+pub extern crate erased_serde;
+
+mod access_control;
+mod codec;
+mod envelope;
 mod error;
 mod message;
+mod profile;
 mod routing;
 mod worker;
 
+pub use access_control::*;
+pub use codec::*;
+pub use envelope::*;
 pub use error::*;
 pub use message::*;
+pub use profile::*;
 pub use routing::*;
 pub use worker::*;
 
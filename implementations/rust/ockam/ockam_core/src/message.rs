@@ -3,7 +3,7 @@ use crate::{
         fmt::{self, Debug, Display, Formatter},
         Deref, DerefMut, Vec,
     },
-    Address, Result, Route,
+    Address, LocalInfo, Result, Route,
 };
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -44,12 +44,29 @@ impl From<bincode::Error> for crate::Error {
 pub struct Routed<M: Message> {
     inner: M,
     route: Route,
+    local: LocalInfo,
 }
 
 impl<M: Message> Routed<M> {
     /// Create a new Routed message wrapper
     pub fn new(inner: M, route: Route) -> Self {
-        Self { inner, route }
+        Self {
+            inner,
+            route,
+            local: LocalInfo::default(),
+        }
+    }
+
+    /// Attach local information about how the message arrived
+    pub fn with_local(mut self, local: LocalInfo) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Get the local information about how the message arrived
+    #[inline]
+    pub fn local(&self) -> &LocalInfo {
+        &self.local
     }
 
     /// Return a copy of the full return route of the wrapped message
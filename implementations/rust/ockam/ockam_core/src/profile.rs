@@ -0,0 +1,24 @@
+use crate::lib::String;
+use serde::{Deserialize, Serialize};
+
+/// Unique profile identifier, computed as SHA256 of the root public key
+///
+/// Defined here so that the profile a message was authenticated with
+/// can be part of its [`LocalInfo`](crate::LocalInfo).
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct ProfileIdentifier(String);
+
+impl ProfileIdentifier {
+    /// Create an identifier from the key id of the root public key
+    pub fn from_key_id(key_id: String) -> Self {
+        Self(key_id)
+    }
+    /// Key id this identifier is computed from
+    pub fn key_id(&self) -> &String {
+        &self.0
+    }
+    /// Human-readable form of the id
+    pub fn to_string_representation(&self) -> String {
+        format!("P_ID.{}", &self.0)
+    }
+}
@@ -20,6 +20,25 @@ impl AddressSet {
     pub fn first(&self) -> Address {
         self.0.first().cloned().unwrap()
     }
+
+    /// Check if the set contains an address.
+    pub fn contains(&self, a: &Address) -> bool {
+        self.0.contains(a)
+    }
+
+    /// Add an address to the end of the set.
+    pub fn push(&mut self, a: Address) {
+        if !self.contains(&a) {
+            self.0.push(a);
+        }
+    }
+
+    /// Remove an address from the set, returning whether it was found.
+    pub fn remove(&mut self, a: &Address) -> bool {
+        let len = self.0.len();
+        self.0.retain(|x| x != a);
+        self.0.len() != len
+    }
 }
 
 impl<T: Into<Address>> From<Vec<T>> for AddressSet {
@@ -95,6 +114,19 @@ impl Address {
 
         Self { tt, inner }
     }
+
+    /// Generate a random address of the given type
+    ///
+    /// The address is 16 random bytes, hex-encoded, which makes
+    /// collisions with other generated addresses very unlikely.
+    #[cfg(feature = "std")]
+    pub fn random(tt: u8) -> Self {
+        let bytes: [u8; 16] = rand::random();
+        Self {
+            tt,
+            inner: hex::encode(bytes).into_bytes(),
+        }
+    }
 }
 
 impl Display for Address {
@@ -183,6 +215,29 @@ fn parse_addr_with_type() {
     );
 }
 
+#[test]
+#[cfg(feature = "std")]
+fn random_addr_is_unique() {
+    let (a, b) = (Address::random(0), Address::random(0));
+    assert_ne!(a, b);
+    assert_eq!(a.len(), 32);
+    assert_eq!(
+        Address::from_string(a.to_string().split(':').nth(1).unwrap()),
+        a
+    );
+}
+
+#[test]
+fn address_set_add_remove() {
+    let mut set = AddressSet::from("primary");
+    set.push("alias".into());
+    set.push("alias".into());
+    assert_eq!(set.iter().count(), 2);
+    assert!(set.remove(&"alias".into()));
+    assert!(!set.remove(&"alias".into()));
+    assert_eq!(set.first(), "primary".into());
+}
+
 #[test]
 #[should_panic]
 fn parse_addr_invalid() {
@@ -1,5 +1,58 @@
-use crate::{lib::Vec, Route};
-use serde::{Deserialize, Serialize};
+use crate::{
+    lib::{BTreeMap, String, Vec},
+    Address, ProfileIdentifier, Result, Route, RoutingError,
+};
+use core::fmt;
+use serde::{
+    de::{self, SeqAccess, Unexpected, Visitor},
+    ser::SerializeTuple,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The default hop limit of new transport messages
+pub const DEFAULT_HOP_LIMIT: u8 = 32;
+
+/// The maximum route length accepted from remote peers
+pub const MAX_ROUTE_LENGTH: usize = 64;
+
+/// Local information about how a message arrived at this node
+///
+/// This information is never serialized.  It is set by the
+/// transports and secure channels handling a message, and can be
+/// read by workers via [`Routed::local`](crate::Routed::local), for
+/// example to make authorization decisions.
+#[derive(Debug, Clone, Default, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct LocalInfo {
+    /// The transport connection worker which received the message
+    pub transport: Option<Address>,
+    /// The secure channel the message was received through
+    pub secure_channel: Option<Address>,
+    /// The peer profile authenticated by the secure channel
+    pub profile_id: Option<ProfileIdentifier>,
+    /// Credential attributes verified by the secure channel, by label
+    pub credential_attributes: BTreeMap<String, String>,
+    /// The arrival time of the message, in microseconds since the UNIX epoch
+    pub arrival: Option<u64>,
+}
+
+/// The kind of payload a transport message carries
+///
+/// Messages generated by nodes are marked, so that routers can
+/// handle them without decoding payloads, whatever codec they were
+/// encoded with.
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, Hash, Ord, PartialOrd, Eq, PartialEq,
+)]
+pub enum PayloadKind {
+    /// A message sent by a worker
+    #[default]
+    Data,
+    /// A report about another message, which is never reported on
+    /// itself, e.g. a [`HopLimitExceeded`](crate::HopLimitExceeded)
+    Report,
+    /// A route tracing probe, recording the hops it takes
+    Probe,
+}
 
 /// A generic transport message
 ///
@@ -7,10 +60,26 @@ use serde::{Deserialize, Serialize};
 /// crate) in order to provide a mechanism for third-party developers
 /// to create custom transport channel routers.  Casual users of ockam
 /// should never have to interact with this type directly.
-#[derive(Serialize, Deserialize, Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+///
+/// Version 1 messages are encoded as `version`, `onward`, `return_`
+/// and `payload`.  Version 2 adds the `hop_limit` and `kind` after
+/// the `version`.  Both versions are decoded, and messages are encoded
+/// in the layout of their own version.
+#[derive(Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub struct TransportMessage {
     /// The transport protocol version
     pub version: u8,
+    /// The number of hops this message may still take
+    ///
+    /// Nodes decrement this field every time they forward a message,
+    /// and drop messages when it reaches zero, to break routing loops.
+    /// Version 1 messages don't carry a hop limit, and start with
+    /// [`DEFAULT_HOP_LIMIT`] on every node that decodes them.
+    pub hop_limit: u8,
+    /// The kind of the payload
+    ///
+    /// Version 1 messages always carry [`PayloadKind::Data`].
+    pub kind: PayloadKind,
     /// Onward message route
     pub onward: Route,
     /// Return message route
@@ -20,6 +89,10 @@ pub struct TransportMessage {
     pub return_: Route,
     /// The message payload
     pub payload: Vec<u8>,
+    /// Local information about how this message arrived
+    ///
+    /// This field is never encoded.
+    pub local: LocalInfo,
 }
 
 impl TransportMessage {
@@ -27,9 +100,210 @@ impl TransportMessage {
     pub fn v1(onward: Route, payload: Vec<u8>) -> Self {
         Self {
             version: 1,
+            hop_limit: DEFAULT_HOP_LIMIT,
+            kind: PayloadKind::Data,
             onward,
             return_: Route::new().into(),
             payload,
+            local: LocalInfo::default(),
+        }
+    }
+
+    /// Create a new v2 transport message with empty return route
+    pub fn v2(onward: Route, payload: Vec<u8>) -> Self {
+        Self {
+            version: 2,
+            ..Self::v1(onward, payload)
         }
     }
+
+    /// Take a hop, decrementing the hop limit
+    ///
+    /// Returns an error if the hop limit has already expired.
+    pub fn hop(&mut self) -> Result<()> {
+        match self.hop_limit.checked_sub(1) {
+            Some(limit) => {
+                self.hop_limit = limit;
+                Ok(())
+            }
+            None => Err(RoutingError::HopLimitExceeded.into()),
+        }
+    }
+
+    /// Check that the routes of a message received from a remote
+    /// peer do not exceed [`MAX_ROUTE_LENGTH`]
+    pub fn validate(&self) -> Result<()> {
+        if self.onward.len() > MAX_ROUTE_LENGTH || self.return_.len() > MAX_ROUTE_LENGTH {
+            Err(RoutingError::RouteTooLong.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Serialize for TransportMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        let v1 = self.version == 1;
+        let mut tuple = serializer.serialize_tuple(if v1 { 4 } else { 6 })?;
+        tuple.serialize_element(&self.version)?;
+        if !v1 {
+            tuple.serialize_element(&self.hop_limit)?;
+            tuple.serialize_element(&self.kind)?;
+        }
+        tuple.serialize_element(&self.onward)?;
+        tuple.serialize_element(&self.return_)?;
+        tuple.serialize_element(&self.payload)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TransportMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_tuple(6, TransportMessageVisitor)
+    }
+}
+
+struct TransportMessageVisitor;
+
+impl TransportMessageVisitor {
+    fn next<'de, T: Deserialize<'de>, A: SeqAccess<'de>>(
+        &self,
+        seq: &mut A,
+        index: usize,
+    ) -> core::result::Result<T, A::Error> {
+        seq.next_element()?
+            .ok_or_else(|| de::Error::invalid_length(index, self))
+    }
+}
+
+impl<'de> Visitor<'de> for TransportMessageVisitor {
+    type Value = TransportMessage;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a version 1 or 2 transport message")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> core::result::Result<Self::Value, A::Error> {
+        let version: u8 = self.next(&mut seq, 0)?;
+        let (hop_limit, kind) = match version {
+            1 => (DEFAULT_HOP_LIMIT, PayloadKind::Data),
+            2 => (self.next(&mut seq, 1)?, self.next(&mut seq, 2)?),
+            _ => {
+                let version = Unexpected::Unsigned(version.into());
+                return Err(de::Error::invalid_value(version, &self));
+            }
+        };
+        let fields = if version == 1 { 1 } else { 3 };
+
+        Ok(TransportMessage {
+            version,
+            hop_limit,
+            kind,
+            onward: self.next(&mut seq, fields)?,
+            return_: self.next(&mut seq, fields + 1)?,
+            payload: self.next(&mut seq, fields + 2)?,
+            local: LocalInfo::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::ToString;
+
+    #[test]
+    fn hop_limit_expires() {
+        let mut msg = TransportMessage::v1("app".into(), vec![]);
+        msg.hop_limit = 2;
+        assert!(msg.hop().is_ok());
+        assert!(msg.hop().is_ok());
+        assert_eq!(msg.hop_limit, 0);
+        assert!(msg.hop().is_err());
+    }
+
+    #[test]
+    fn local_info_is_not_serialized() {
+        let mut msg = TransportMessage::v1("app".into(), vec![1, 2, 3]);
+        msg.local.transport = Some("tcp".into());
+        msg.local.secure_channel = Some("channel".into());
+        msg.local.profile_id = Some(ProfileIdentifier::from_key_id("alice".to_string()));
+        msg.local
+            .credential_attributes
+            .insert("role".to_string(), "admin".to_string());
+        msg.local.arrival = Some(42);
+
+        let data = bincode::serialize(&msg).unwrap();
+        let decoded: TransportMessage = bincode::deserialize(&data).unwrap();
+        assert_eq!(decoded.local, LocalInfo::default());
+        assert_eq!(decoded.payload, msg.payload);
+    }
+
+    /// The layout of version 1 messages, before the hop limit
+    #[derive(Serialize, Deserialize)]
+    struct TransportMessageV1 {
+        version: u8,
+        onward: Route,
+        return_: Route,
+        payload: Vec<u8>,
+    }
+
+    #[test]
+    fn decode_v1() {
+        let old = TransportMessageV1 {
+            version: 1,
+            onward: "app".into(),
+            return_: "sender".into(),
+            payload: vec![1, 2, 3],
+        };
+        let data = bincode::serialize(&old).unwrap();
+
+        let msg: TransportMessage = bincode::deserialize(&data).unwrap();
+        assert_eq!(msg.version, 1);
+        assert_eq!(msg.hop_limit, DEFAULT_HOP_LIMIT);
+        assert_eq!(msg.kind, PayloadKind::Data);
+        assert_eq!(msg.onward, old.onward);
+        assert_eq!(msg.return_, old.return_);
+        assert_eq!(msg.payload, old.payload);
+
+        // Version 1 messages keep their layout when forwarded
+        assert_eq!(bincode::serialize(&msg).unwrap(), data);
+    }
+
+    #[test]
+    fn encode_v2() {
+        let mut msg = TransportMessage::v2("app".into(), vec![1, 2, 3]);
+        msg.hop_limit = 7;
+        msg.kind = PayloadKind::Probe;
+
+        let data = bincode::serialize(&msg).unwrap();
+        assert_eq!(data[..2], [2, 7]);
+        let decoded: TransportMessage = bincode::deserialize(&data).unwrap();
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let mut msg = TransportMessage::v2("app".into(), vec![]);
+        msg.version = 3;
+        let data = bincode::serialize(&msg).unwrap();
+        assert!(bincode::deserialize::<TransportMessage>(&data).is_err());
+    }
+
+    #[test]
+    fn route_length_is_limited() {
+        let mut msg = TransportMessage::v1("app".into(), vec![]);
+        assert!(msg.validate().is_ok());
+
+        for i in 0..MAX_ROUTE_LENGTH {
+            msg.return_.modify().append(i.to_string());
+        }
+        assert!(msg.validate().is_ok());
+
+        msg.return_.modify().append("one.too.many");
+        assert!(msg.validate().is_err());
+    }
 }
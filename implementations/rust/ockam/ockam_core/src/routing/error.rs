@@ -0,0 +1,24 @@
+/// Errors from routing transport messages
+#[derive(Clone, Copy, Debug)]
+pub enum RoutingError {
+    /// The hop limit of a message expired before reaching its destination
+    HopLimitExceeded,
+    /// A route exceeds the maximum route length
+    RouteTooLong,
+}
+
+impl RoutingError {
+    /// Integer code associated with the error domain.
+    pub const DOMAIN_CODE: u32 = 18_000;
+    /// Descriptive name for the error domain.
+    pub const DOMAIN_NAME: &'static str = "OCKAM_ROUTING";
+}
+
+impl From<RoutingError> for crate::Error {
+    fn from(e: RoutingError) -> crate::Error {
+        crate::Error::new(
+            RoutingError::DOMAIN_CODE + (e as u32),
+            RoutingError::DOMAIN_NAME,
+        )
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Address, TransportMessage};
+use crate::{Address, Route, TransportMessage, TypedMessage};
 use serde::{Deserialize, Serialize};
 
 /// A command message for router implementations
@@ -17,3 +17,18 @@ pub enum RouterMessage {
         self_addr: Address,
     },
 }
+
+/// Sent to the return route of a message whose hop limit expired
+///
+/// Receive it as a [`Typed`](crate::Typed) message to diagnose
+/// routing loops.
+#[derive(Serialize, Deserialize, Debug, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+pub struct HopLimitExceeded {
+    /// The remaining onward route of the dropped message
+    pub onward: Route,
+}
+
+impl TypedMessage for HopLimitExceeded {
+    const TYPE_ID: &'static str = "ockam.routing.hop_limit_exceeded";
+    const VERSION: u16 = 1;
+}
@@ -6,6 +6,9 @@ pub use address::*;
 mod data;
 pub use data::*;
 
+mod error;
+pub use error::*;
+
 mod route;
 pub use route::*;
 
@@ -39,6 +39,16 @@ impl Route {
         self.inner.front()
     }
 
+    /// Get the number of addresses in this route
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check if this route is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     /// Get the final recipient address
     pub fn recipient(&self) -> Address {
         self.inner
@@ -105,6 +115,12 @@ impl RouteBuilder<'_> {
         self
     }
 
+    /// Remove the last item of the route
+    pub fn pop_back(mut self) -> Self {
+        self.inner.pop_back();
+        self
+    }
+
     /// Replace the next item in the route with a new address
     ///
     /// Similar to [`Self::prepend(...)`](RouteBuilder::prepend), but
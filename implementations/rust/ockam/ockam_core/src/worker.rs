@@ -1,4 +1,4 @@
-use crate::{lib::Box, Message, Result, Routed};
+use crate::{lib::Box, AccessControl, AllowAll, Message, Result, Routed};
 use async_trait::async_trait;
 
 /// Base ockam worker trait.
@@ -16,10 +16,33 @@ pub trait Worker: Send + 'static {
     }
 
     /// Override shutdown behaviour
+    ///
+    /// Workers which need to send messages or wait for I/O while
+    /// shutting down should override [`Self::stop`] instead.
     fn shutdown(&mut self, _context: &mut Self::Context) -> Result<()> {
         Ok(())
     }
 
+    /// Override asynchronous shutdown behaviour
+    ///
+    /// This is called once the worker was asked to stop and has
+    /// handled the messages already in its mailbox.  The context can
+    /// still be used to send messages.  By default this calls
+    /// [`Self::shutdown`].
+    async fn stop(&mut self, context: &mut Self::Context) -> Result<()> {
+        self.shutdown(context)
+    }
+
+    /// Override the access control policy of this worker
+    ///
+    /// This is called once when the worker is started.  Messages
+    /// which are not authorized by the policy are dropped before
+    /// reaching [`Self::handle_message`].  By default all messages
+    /// are accepted.
+    fn access_control(&self) -> Box<dyn AccessControl> {
+        Box::new(AllowAll)
+    }
+
     /// Try to open and handle a typed message
     async fn handle_message(
         &mut self,
@@ -30,6 +30,8 @@ typedef enum {
     OCKAM_VAULT_SECRET_TYPE_AES_KEY,
     OCKAM_VAULT_SECRET_TYPE_CURVE25519_PRIVATEKEY,
     OCKAM_VAULT_SECRET_TYPE_P256_PRIVATEKEY,
+    OCKAM_VAULT_SECRET_TYPE_ED25519_PRIVATEKEY,
+    OCKAM_VAULT_SECRET_TYPE_CHACHA20_KEY,
 } ockam_vault_secret_type_t;
 
 /**
@@ -41,6 +43,25 @@ typedef enum {
     OCKAM_VAULT_SECRET_PERSISTENT = 1,
 } ockam_vault_secret_persistence_t;
 
+/**
+ * @enum    ockam_vault_secret_usage_t
+ * @brief   Operations a secret may be used for.
+ */
+typedef enum {
+    OCKAM_VAULT_SECRET_USAGE_ANY = 0,
+    OCKAM_VAULT_SECRET_USAGE_SIGN,
+    OCKAM_VAULT_SECRET_USAGE_DERIVE,
+} ockam_vault_secret_usage_t;
+
+/**
+ * @enum    ockam_vault_secret_export_policy_t
+ * @brief   Whether a secret may be exported.
+ */
+typedef enum {
+    OCKAM_VAULT_SECRET_EXPORTABLE = 0,
+    OCKAM_VAULT_SECRET_NOT_EXPORTABLE = 1,
+} ockam_vault_secret_export_policy_t;
+
 /**
  * @struct  ockam_vault_secret_attributes_t
  * @brief   Attributes for a specific ockam vault secret.
@@ -49,6 +70,8 @@ typedef struct {
     uint8_t  type;
     uint8_t  persistence;
     uint32_t length;
+    uint8_t  usage;
+    uint8_t  export_policy;
 } ockam_vault_secret_attributes_t;
 
 /**
@@ -101,6 +124,7 @@ ockam_vault_extern_error_t ockam_vault_secret_import(ockam_vault_t
 
 /**
  * @brief   Export data from an ockam vault secret into the supplied output buffer.
+ *          Fails for secrets with the OCKAM_VAULT_SECRET_NOT_EXPORTABLE policy.
  * @param   vault[in]                 Vault object to use for exporting secret data.
  * @param   secret[in]                Ockam vault secret to export data from.
  * @param   output_buffer[out]        Buffer to place the exported secret data in.
@@ -68,6 +68,9 @@ pub enum FfiError {
 
     /// Ownership error.
     OwnershipError,
+
+    /// The secret's export policy forbids exporting it.
+    SecretNotExportable,
 }
 
 impl FfiError {
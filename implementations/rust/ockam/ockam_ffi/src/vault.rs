@@ -126,6 +126,7 @@ pub extern "C" fn ockam_vault_secret_import(
 }
 
 /// Export a secret key with the specific handle to the `output_buffer`.
+/// Fails for secrets whose attributes don't allow exporting them.
 #[no_mangle]
 pub extern "C" fn ockam_vault_secret_export(
     context: FfiVaultFatPointer,
@@ -137,6 +138,11 @@ pub extern "C" fn ockam_vault_secret_export(
     *output_buffer_length = 0;
     match call(context, |v| -> Result<(), FfiOckamError> {
         let ctx = Secret::new(secret as usize);
+        // Checked here as well, so that no vault behind the FFI leaks
+        // non-exportable secrets
+        if !v.secret_attributes_get(&ctx)?.exportable() {
+            return Err(FfiError::SecretNotExportable.into());
+        }
         let key = v.secret_export(&ctx)?;
         if output_buffer_size < key.as_ref().len() as u32 {
             return Err(FfiError::BufferTooSmall.into());
@@ -2,7 +2,7 @@
 
 use crate::FfiError;
 use ockam_core::lib::convert::TryFrom;
-use ockam_vault_core::{SecretAttributes, SecretPersistence, SecretType};
+use ockam_vault_core::{SecretAttributes, SecretPersistence, SecretType, SecretUsage};
 
 /// Represents a handle id for the secret key
 pub type SecretKeyHandle = u64;
@@ -41,6 +41,8 @@ pub struct FfiSecretAttributes {
     stype: u8,
     persistence: u8,
     length: u32,
+    usage: u8,
+    export_policy: u8,
 }
 
 impl FfiSecretAttributes {
@@ -53,14 +55,24 @@ impl FfiSecretAttributes {
     pub fn length(&self) -> u32 {
         self.length
     }
+    pub fn usage(&self) -> u8 {
+        self.usage
+    }
+    pub fn export_policy(&self) -> u8 {
+        self.export_policy
+    }
 }
 
 impl FfiSecretAttributes {
+    /// Attributes of a secret usable for any operation and exportable,
+    /// which is what zero values of `usage` and `export_policy` mean
     pub fn new(stype: u8, persistence: u8, length: u32) -> Self {
         Self {
             stype,
             persistence,
             length,
+            usage: 0,
+            export_policy: 0,
         }
     }
 }
@@ -72,6 +84,8 @@ impl From<SecretAttributes> for FfiSecretAttributes {
             SecretType::Aes => 1,
             SecretType::Curve25519 => 2,
             SecretType::P256 => 3,
+            SecretType::Ed25519 => 4,
+            SecretType::ChaCha20 => 5,
         };
 
         let persistence = match attrs.persistence() {
@@ -79,7 +93,19 @@ impl From<SecretAttributes> for FfiSecretAttributes {
             SecretPersistence::Persistent => 1,
         };
 
-        Self::new(stype, persistence, attrs.length() as u32)
+        let usage = match attrs.usage() {
+            SecretUsage::Any => 0,
+            SecretUsage::Sign => 1,
+            SecretUsage::Derive => 2,
+        };
+
+        let export_policy = if attrs.exportable() { 0 } else { 1 };
+
+        Self {
+            usage,
+            export_policy,
+            ..Self::new(stype, persistence, attrs.length() as u32)
+        }
     }
 }
 
@@ -92,6 +118,8 @@ impl TryFrom<FfiSecretAttributes> for SecretAttributes {
             1 => Ok(SecretType::Aes),
             2 => Ok(SecretType::Curve25519),
             3 => Ok(SecretType::P256),
+            4 => Ok(SecretType::Ed25519),
+            5 => Ok(SecretType::ChaCha20),
             _ => Err(FfiError::InvalidParam),
         }?;
 
@@ -101,6 +129,21 @@ impl TryFrom<FfiSecretAttributes> for SecretAttributes {
             _ => Err(FfiError::InvalidParam),
         }?;
 
-        Ok(Self::new(stype, persistence, attrs.length() as usize))
+        let usage = match attrs.usage() {
+            0 => Ok(SecretUsage::Any),
+            1 => Ok(SecretUsage::Sign),
+            2 => Ok(SecretUsage::Derive),
+            _ => Err(FfiError::InvalidParam),
+        }?;
+
+        let exportable = match attrs.export_policy() {
+            0 => Ok(true),
+            1 => Ok(false),
+            _ => Err(FfiError::InvalidParam),
+        }?;
+
+        Ok(Self::new(stype, persistence, attrs.length() as usize)
+            .with_usage(usage)
+            .with_exportable(exportable))
     }
 }
@@ -93,6 +93,7 @@ impl KeyExchanger for Initiator {
                 vault.verify(
                     prekey_bundle.signature_prekey.as_ref(),
                     prekey_bundle.identity_key.as_ref(),
+                    SecretType::Curve25519,
                     prekey_bundle.signed_prekey.as_ref(),
                 )?;
                 let atts = SecretAttributes::new(
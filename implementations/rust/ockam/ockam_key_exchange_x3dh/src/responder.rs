@@ -195,7 +195,12 @@ impl KeyExchanger for Responder {
                 )?;
                 let ikb = PublicKey::new(array_ref![plaintext, 0, 32].to_vec());
                 let signature = array_ref![plaintext, 32, 64];
-                vault.verify(signature, eik.as_ref(), &plaintext[..32])?;
+                vault.verify(
+                    signature,
+                    eik.as_ref(),
+                    SecretType::Curve25519,
+                    &plaintext[..32],
+                )?;
 
                 self.completed_key_exchange = Some(CompletedKeyExchange::new(
                     state_hash,
@@ -0,0 +1,79 @@
+use crate::XXVault;
+use ockam_vault_core::{
+    Buffer, Secret, SecretAttributes, SecretPersistence, SecretType, AES256_SECRET_LENGTH,
+    CHACHA20_SECRET_LENGTH,
+};
+
+/// The cipher function of an XX handshake
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XXCipher {
+    /// AES-256-GCM, the default
+    #[default]
+    AesGcm,
+    /// ChaCha20-Poly1305, for targets without AES acceleration
+    ChaChaPoly,
+}
+
+impl XXCipher {
+    /// The name of the function in Noise protocol names
+    pub fn noise_name(&self) -> &'static str {
+        match self {
+            XXCipher::AesGcm => "AESGCM",
+            XXCipher::ChaChaPoly => "ChaChaPoly",
+        }
+    }
+
+    /// The attributes of the keys derived during the handshake
+    pub(crate) fn secret_attributes(&self) -> SecretAttributes {
+        let (stype, length) = match self {
+            XXCipher::AesGcm => (SecretType::Aes, AES256_SECRET_LENGTH),
+            XXCipher::ChaChaPoly => (SecretType::ChaCha20, CHACHA20_SECRET_LENGTH),
+        };
+        SecretAttributes::new(stype, SecretPersistence::Ephemeral, length)
+    }
+
+    /// The 96 bit nonce for the message counter `n`
+    ///
+    /// Noise encodes the counter big-endian for AES-GCM and
+    /// little-endian for ChaCha20-Poly1305, after 32 zero bits.
+    pub(crate) fn nonce(&self, n: u16) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        match self {
+            XXCipher::AesGcm => nonce[10..].copy_from_slice(&n.to_be_bytes()),
+            XXCipher::ChaChaPoly => nonce[4..6].copy_from_slice(&n.to_le_bytes()),
+        }
+        nonce
+    }
+
+    pub(crate) fn encrypt(
+        &self,
+        vault: &mut dyn XXVault,
+        key: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        match self {
+            XXCipher::AesGcm => vault.aead_aes_gcm_encrypt(key, plaintext, nonce, aad),
+            XXCipher::ChaChaPoly => {
+                vault.aead_chacha20_poly1305_encrypt(key, plaintext, nonce, aad)
+            }
+        }
+    }
+
+    pub(crate) fn decrypt(
+        &self,
+        vault: &mut dyn XXVault,
+        key: &Secret,
+        ciphertext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        match self {
+            XXCipher::AesGcm => vault.aead_aes_gcm_decrypt(key, ciphertext, nonce, aad),
+            XXCipher::ChaChaPoly => {
+                vault.aead_chacha20_poly1305_decrypt(key, ciphertext, nonce, aad)
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use ockam_vault_core::{
+    SecretAttributes, SecretPersistence, SecretType, CURVE25519_PUBLIC_LENGTH,
+    CURVE25519_SECRET_LENGTH, P256_PUBLIC_LENGTH, P256_SECRET_LENGTH,
+};
+
+/// The Diffie-Hellman function of an XX handshake
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XXCurve {
+    /// X25519, the default
+    #[default]
+    Curve25519,
+    /// NIST P-256, with uncompressed public keys
+    P256,
+}
+
+impl XXCurve {
+    /// The name of the function in Noise protocol names
+    pub fn noise_name(&self) -> &'static str {
+        match self {
+            XXCurve::Curve25519 => "25519",
+            XXCurve::P256 => "P256",
+        }
+    }
+
+    /// The length of public keys sent during the handshake
+    pub fn public_key_length(&self) -> usize {
+        match self {
+            XXCurve::Curve25519 => CURVE25519_PUBLIC_LENGTH,
+            XXCurve::P256 => P256_PUBLIC_LENGTH,
+        }
+    }
+
+    /// The attributes of ephemeral key pairs on this curve
+    pub(crate) fn secret_attributes(&self) -> SecretAttributes {
+        let (stype, length) = match self {
+            XXCurve::Curve25519 => (SecretType::Curve25519, CURVE25519_SECRET_LENGTH),
+            XXCurve::P256 => (SecretType::P256, P256_SECRET_LENGTH),
+        };
+        SecretAttributes::new(stype, SecretPersistence::Ephemeral, length)
+    }
+}
@@ -1,5 +1,9 @@
 use ockam_vault_core::{AsymmetricVault, Hasher, SecretVault, SymmetricVault};
 
+mod cipher;
+pub use cipher::*;
+mod curve;
+pub use curve::*;
 mod error;
 pub use error::*;
 
@@ -31,10 +35,28 @@ mod tests {
     #[allow(non_snake_case)]
     #[test]
     fn full_flow__correct_credentials__keys_should_match() {
+        full_flow(XXCurve::Curve25519, XXCipher::AesGcm);
+    }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn full_flow_p256__correct_credentials__keys_should_match() {
+        full_flow(XXCurve::P256, XXCipher::AesGcm);
+    }
+
+    #[allow(non_snake_case)]
+    #[test]
+    fn full_flow_chachapoly__correct_credentials__keys_should_match() {
+        full_flow(XXCurve::Curve25519, XXCipher::ChaChaPoly);
+    }
+
+    fn full_flow(curve: XXCurve, cipher: XXCipher) {
         let vault_initiator = Arc::new(Mutex::new(SoftwareVault::default()));
         let vault_responder = Arc::new(Mutex::new(SoftwareVault::default()));
         let key_exchanger =
-            XXNewKeyExchanger::new(vault_initiator.clone(), vault_responder.clone());
+            XXNewKeyExchanger::new(vault_initiator.clone(), vault_responder.clone())
+                .with_curve(curve)
+                .with_cipher(cipher);
 
         let mut initiator = key_exchanger.initiator();
         let mut responder = key_exchanger.responder();
@@ -1,5 +1,5 @@
 use crate::state::State;
-use crate::{Initiator, Responder, XXVault};
+use crate::{Initiator, Responder, XXCipher, XXCurve, XXVault};
 use ockam_key_exchange_core::NewKeyExchanger;
 use std::sync::{Arc, Mutex};
 
@@ -7,6 +7,8 @@ use std::sync::{Arc, Mutex};
 pub struct XXNewKeyExchanger {
     vault_initiator: Arc<Mutex<dyn XXVault>>,
     vault_responder: Arc<Mutex<dyn XXVault>>,
+    curve: XXCurve,
+    cipher: XXCipher,
 }
 
 impl XXNewKeyExchanger {
@@ -18,20 +20,38 @@ impl XXNewKeyExchanger {
         Self {
             vault_initiator,
             vault_responder,
+            curve: XXCurve::default(),
+            cipher: XXCipher::default(),
         }
     }
+
+    /// Use another Diffie-Hellman function than X25519
+    ///
+    /// Both parties of a handshake must use the same curve.
+    pub fn with_curve(mut self, curve: XXCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Use another cipher function than AES-GCM
+    ///
+    /// Both parties of a handshake must use the same cipher.
+    pub fn with_cipher(mut self, cipher: XXCipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
 }
 
 impl NewKeyExchanger<Initiator, Responder> for XXNewKeyExchanger {
     /// Create a new initiator using the provided backing vault
     fn initiator(&self) -> Initiator {
-        let ss = State::new(self.vault_initiator.clone());
+        let ss = State::new(self.vault_initiator.clone(), self.curve, self.cipher);
         Initiator::new(ss)
     }
 
     /// Create a new responder using the provided backing vault
     fn responder(&self) -> Responder {
-        let ss = State::new(self.vault_responder.clone());
+        let ss = State::new(self.vault_responder.clone(), self.curve, self.cipher);
         Responder::new(ss)
     }
 }
@@ -1,9 +1,6 @@
-use crate::{XXError, XXVault, AES_GCM_TAGSIZE, SHA256_SIZE};
+use crate::{XXCipher, XXCurve, XXError, XXVault, AES_GCM_TAGSIZE, SHA256_SIZE};
 use ockam_key_exchange_core::CompletedKeyExchange;
-use ockam_vault_core::{
-    PublicKey, Secret, SecretAttributes, SecretPersistence, SecretType, AES256_SECRET_LENGTH,
-    CURVE25519_PUBLIC_LENGTH, CURVE25519_SECRET_LENGTH,
-};
+use ockam_vault_core::{PublicKey, Secret, SecretAttributes, SecretPersistence, SecretType};
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
 use zeroize::Zeroize;
@@ -11,17 +8,23 @@ use zeroize::Zeroize;
 struct DhState {
     key: Option<Secret>,
     ck: Option<Secret>,
+    cipher: XXCipher,
 }
 
 impl DhState {
-    fn empty() -> Self {
+    fn empty(cipher: XXCipher) -> Self {
         Self {
             key: None,
             ck: None,
+            cipher,
         }
     }
 
-    fn new(protocol_name: &[u8; 32], vault: &mut dyn XXVault) -> ockam_core::Result<Self> {
+    fn new(
+        protocol_name: &[u8; 32],
+        cipher: XXCipher,
+        vault: &mut dyn XXVault,
+    ) -> ockam_core::Result<Self> {
         let attributes = SecretAttributes::new(
             SecretType::Buffer,
             SecretPersistence::Ephemeral,
@@ -33,6 +36,7 @@ impl DhState {
         Ok(Self {
             key: None,
             ck: Some(ck),
+            cipher,
         })
     }
 }
@@ -47,9 +51,6 @@ impl DhState {
 }
 
 impl DhState {
-    fn get_symmetric_key_type_and_length(&self) -> (SecretType, usize) {
-        (SecretType::Aes, AES256_SECRET_LENGTH)
-    }
     /// Perform the diffie-hellman computation
     fn dh(
         &mut self,
@@ -65,13 +66,7 @@ impl DhState {
             SHA256_SIZE,
         );
 
-        let symmetric_secret_info = self.get_symmetric_key_type_and_length();
-
-        let attributes_k = SecretAttributes::new(
-            symmetric_secret_info.0,
-            SecretPersistence::Ephemeral,
-            symmetric_secret_info.1,
-        );
+        let attributes_k = self.cipher.secret_attributes();
 
         let ecdh = vault.ec_diffie_hellman(secret_handle, public_key)?;
 
@@ -110,6 +105,8 @@ pub(crate) struct State {
     dh_state: DhState,
     nonce: u16,
     h: Option<[u8; SHA256_SIZE]>,
+    curve: XXCurve,
+    cipher: XXCipher,
     vault: Arc<Mutex<dyn XXVault>>,
 }
 
@@ -134,7 +131,7 @@ impl std::fmt::Debug for State {
 }
 
 impl State {
-    pub(crate) fn new(vault: Arc<Mutex<dyn XXVault>>) -> Self {
+    pub(crate) fn new(vault: Arc<Mutex<dyn XXVault>>, curve: XXCurve, cipher: XXCipher) -> Self {
         Self {
             run_prologue: true,
             identity_key: None,
@@ -143,30 +140,32 @@ impl State {
             ephemeral_public: None,
             remote_static_public_key: None,
             remote_ephemeral_public_key: None,
-            dh_state: DhState::empty(),
+            dh_state: DhState::empty(cipher),
             nonce: 0,
             h: None,
+            curve,
+            cipher,
             vault,
         }
     }
 }
 
 impl State {
-    fn get_symmetric_key_type_and_length(&self) -> (SecretType, usize) {
-        (SecretType::Aes, AES256_SECRET_LENGTH)
-    }
-
-    fn get_protocol_name(&self) -> &'static [u8] {
-        b"Noise_XX_25519_AESGCM_SHA256\0\0\0\0"
+    /// The protocol name, padded with zeros to the hash length
+    fn get_protocol_name(&self) -> [u8; SHA256_SIZE] {
+        let name = format!(
+            "Noise_XX_{}_{}_SHA256",
+            self.curve.noise_name(),
+            self.cipher.noise_name()
+        );
+        let mut padded = [0u8; SHA256_SIZE];
+        padded[..name.len()].copy_from_slice(name.as_bytes());
+        padded
     }
 
     /// Create a new `HandshakeState` starting with the prologue
     fn prologue(&mut self) -> ockam_core::Result<()> {
-        let attributes = SecretAttributes::new(
-            SecretType::Curve25519,
-            SecretPersistence::Ephemeral,
-            CURVE25519_SECRET_LENGTH,
-        );
+        let attributes = self.curve.secret_attributes();
         // 1. Generate a static key pair for this handshake and set it to `s`
         let mut vault = self.vault.lock().unwrap();
         if let Some(ik) = &self.identity_key {
@@ -190,9 +189,8 @@ impl State {
         // 5. h = SHA256(h || prologue),
         // prologue is empty
         // mix_hash(xx, NULL, 0);
-        let mut h = [0u8; SHA256_SIZE];
-        h[..self.get_protocol_name().len()].copy_from_slice(self.get_protocol_name());
-        self.dh_state = DhState::new(&h, vault.deref_mut())?;
+        let h = self.get_protocol_name();
+        self.dh_state = DhState::new(&h, self.cipher, vault.deref_mut())?;
         self.h = Some(vault.sha256(&h)?);
 
         Ok(())
@@ -220,12 +218,12 @@ impl State {
     ) -> ockam_core::Result<(Vec<u8>, [u8; 32])> {
         let h = &self.h.ok_or(XXError::InvalidState)?;
 
-        let mut nonce = [0u8; 12];
-        nonce[10..].copy_from_slice(&self.nonce.to_be_bytes());
+        let nonce = self.cipher.nonce(self.nonce);
 
         let ciphertext_and_tag = {
             let key = self.dh_state.key().ok_or(XXError::InvalidState)?;
-            vault.aead_aes_gcm_encrypt(key, plaintext.as_ref(), nonce.as_ref(), h)?
+            self.cipher
+                .encrypt(vault, key, plaintext.as_ref(), nonce.as_ref(), h)?
         };
         let h = self.mix_hash(&ciphertext_and_tag, vault)?;
         Ok((ciphertext_and_tag, h))
@@ -239,12 +237,12 @@ impl State {
     ) -> ockam_core::Result<(Vec<u8>, [u8; 32])> {
         let h = &self.h.ok_or(XXError::InvalidState)?;
 
-        let mut nonce = [0u8; 12];
-        nonce[10..].copy_from_slice(&self.nonce.to_be_bytes());
+        let nonce = self.cipher.nonce(self.nonce);
         let ciphertext = ciphertext.as_ref();
         let plaintext = {
             let key = self.dh_state.key().ok_or(XXError::InvalidState)?;
-            vault.aead_aes_gcm_decrypt(key, ciphertext, nonce.as_ref(), h)?
+            self.cipher
+                .decrypt(vault, key, ciphertext, nonce.as_ref(), h)?
         };
         let h = self.mix_hash(ciphertext, vault)?;
         Ok((plaintext, h))
@@ -254,12 +252,7 @@ impl State {
     fn split(&self, vault: &mut dyn XXVault) -> ockam_core::Result<(Secret, Secret)> {
         let ck = self.dh_state.ck().ok_or(XXError::InvalidState)?;
 
-        let symmetric_key_info = self.get_symmetric_key_type_and_length();
-        let attributes = SecretAttributes::new(
-            symmetric_key_info.0,
-            SecretPersistence::Ephemeral,
-            symmetric_key_info.1,
-        );
+        let attributes = self.cipher.secret_attributes();
         let mut hkdf_output = vault.hkdf_sha256(ck, b"", None, vec![attributes, attributes])?;
 
         if hkdf_output.len() != 2 {
@@ -332,7 +325,7 @@ impl State {
         &mut self,
         message: B,
     ) -> ockam_core::Result<Vec<u8>> {
-        let public_key_size = CURVE25519_PUBLIC_LENGTH;
+        let public_key_size = self.curve.public_key_length();
         let message = message.as_ref();
         if message.len() < 2 * public_key_size + AES_GCM_TAGSIZE {
             return Err(XXError::MessageLenMismatch.into());
@@ -420,7 +413,7 @@ impl State {
         &mut self,
         message_1: B,
     ) -> ockam_core::Result<Vec<u8>> {
-        let public_key_size = CURVE25519_PUBLIC_LENGTH;
+        let public_key_size = self.curve.public_key_length();
         let message_1 = message_1.as_ref();
         if message_1.len() < public_key_size {
             return Err(XXError::MessageLenMismatch.into());
@@ -485,7 +478,7 @@ impl State {
         &mut self,
         message_3: B,
     ) -> ockam_core::Result<Vec<u8>> {
-        let public_key_size = CURVE25519_PUBLIC_LENGTH;
+        let public_key_size = self.curve.public_key_length();
         let message_3 = message_3.as_ref();
         if message_3.len() < public_key_size + AES_GCM_TAGSIZE {
             return Err(XXError::MessageLenMismatch.into());
@@ -526,7 +519,7 @@ impl State {
 #[cfg(test)]
 mod tests {
     use crate::state::{DhState, State};
-    use crate::{Initiator, Responder, XXVault};
+    use crate::{Initiator, Responder, XXCipher, XXCurve, XXVault};
     use ockam_core::hex::{decode, encode};
     use ockam_key_exchange_core::KeyExchanger;
     use ockam_vault::SoftwareVault;
@@ -543,7 +536,7 @@ mod tests {
             100, 252, 104, 43, 230, 163, 171, 75, 104, 44, 141, 182, 75,
         ];
         let vault = Arc::new(Mutex::new(SoftwareVault::default()));
-        let mut state = State::new(vault.clone());
+        let mut state = State::new(vault.clone(), XXCurve::Curve25519, XXCipher::AesGcm);
         let res = state.prologue();
         assert!(res.is_ok());
         assert_eq!(state.h.unwrap(), exp_h);
@@ -555,6 +548,18 @@ mod tests {
         assert_eq!(state.nonce, 0);
     }
 
+    #[test]
+    fn prologue_chachapoly() {
+        let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+        let mut state = State::new(vault.clone(), XXCurve::Curve25519, XXCipher::ChaChaPoly);
+        state.prologue().unwrap();
+
+        let mut vault = vault.lock().unwrap();
+        let ck = vault.secret_export(&state.dh_state.ck.unwrap()).unwrap();
+
+        assert_eq!(ck.as_ref(), *b"Noise_XX_25519_ChaChaPoly_SHA256");
+    }
+
     #[test]
     fn handshake_1() {
         const INIT_STATIC: &str =
@@ -793,9 +798,12 @@ mod tests {
             dh_state: DhState {
                 key: None,
                 ck: Some(ck),
+                cipher: XXCipher::AesGcm,
             },
             nonce: 0,
             h: Some(h),
+            curve: XXCurve::Curve25519,
+            cipher: XXCipher::AesGcm,
             vault: v,
         }
     }
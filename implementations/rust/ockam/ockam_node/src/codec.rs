@@ -0,0 +1,50 @@
+use ockam_core::{Bincode, Codec, Message};
+use std::{any::TypeId, collections::HashMap, fmt, sync::Arc};
+
+/// The payload codecs used by a node
+///
+/// Message types without a specific codec use the node's default
+/// codec, which is [`Bincode`] unless configured otherwise.
+#[derive(Clone)]
+pub(crate) struct Codecs {
+    default: Arc<dyn Codec>,
+    types: HashMap<TypeId, Arc<dyn Codec>>,
+}
+
+impl Default for Codecs {
+    fn default() -> Self {
+        Self {
+            default: Arc::new(Bincode),
+            types: HashMap::new(),
+        }
+    }
+}
+
+impl fmt::Debug for Codecs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Codecs")
+            .field("default", &self.default.name())
+            .field("types", &self.types.len())
+            .finish()
+    }
+}
+
+impl Codecs {
+    /// Set the default codec
+    pub(crate) fn set_default(&mut self, codec: Arc<dyn Codec>) {
+        self.default = codec;
+    }
+
+    /// Set the codec for a specific message type
+    pub(crate) fn set<M: Message>(&mut self, codec: Arc<dyn Codec>) {
+        self.types.insert(TypeId::of::<M>(), codec);
+    }
+
+    /// Get the codec for a message type
+    pub(crate) fn get<M: Message>(&self) -> &dyn Codec {
+        self.types
+            .get(&TypeId::of::<M>())
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
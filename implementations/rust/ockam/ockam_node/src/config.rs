@@ -0,0 +1,318 @@
+//! Node configuration files
+
+use crate::error::Error;
+use ockam_core::Result;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+/// Node settings loaded from a TOML configuration file
+///
+/// Every setting is optional.  Settings made directly on a
+/// [`NodeBuilder`](crate::NodeBuilder) take precedence over the
+/// values loaded from a configuration file.
+///
+/// ```toml
+/// address = "app"
+/// worker_threads = 4
+/// log_filter = "ockam_node=debug"
+/// tracing = true
+/// echo_service = false
+/// registry_service = false
+/// dead_letter_address = "audit"
+///
+/// [tcp]
+/// listen = ["0.0.0.0:4000"]
+/// connect = ["10.0.0.2:4000"]
+///
+/// [vault]
+/// backend = "software"
+///
+/// [profile]
+/// path = "/var/lib/ockam/profile"
+///
+/// [[workers]]
+/// address = "printer"
+/// kind = "printer"
+/// settings = { color = true }
+/// ```
+///
+/// The node starts the configured `workers` itself, see
+/// [`NodeBuilder::worker_kind`](crate::NodeBuilder::worker_kind).
+/// The `tcp`, `vault` and `profile` sections are applied by the
+/// crates implementing them, see [`NodeBuilder::setup`](crate::NodeBuilder::setup).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeConfig {
+    /// Address of the root application context
+    pub address: Option<String>,
+    /// Number of runtime worker threads
+    pub worker_threads: Option<usize>,
+    /// Log filter directives, used if `OCKAM_LOG` is not set
+    pub log_filter: Option<String>,
+    /// Whether the node should install a tracing subscriber
+    pub tracing: Option<bool>,
+    /// Whether the node should start an echo service
+    pub echo_service: Option<bool>,
+    /// Whether the node should start a registry service
+    pub registry_service: Option<bool>,
+    /// Address that messages denied by access control are reported to
+    pub dead_letter_address: Option<String>,
+    /// TCP listeners and outbound connections
+    pub tcp: Option<TcpConfig>,
+    /// The vault backend storing the node's secrets
+    pub vault: Option<VaultConfig>,
+    /// Where the node's profile is stored
+    pub profile: Option<ProfileConfig>,
+    /// Workers started with the node
+    #[serde(default)]
+    pub workers: Vec<WorkerConfig>,
+}
+
+impl NodeConfig {
+    /// Load a node configuration from a TOML file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path).map_err(|e| {
+            error!("Failed to read node config {}: {}", path.display(), e);
+            Error::FailedLoadConfig
+        })?;
+
+        Self::parse(&data)
+    }
+
+    /// Parse a node configuration from a TOML string
+    pub fn parse(data: &str) -> Result<Self> {
+        toml::from_str(data).map_err(|e| {
+            error!("Failed to parse node config: {}", e);
+            Error::FailedLoadConfig.into()
+        })
+    }
+}
+
+/// TCP transport settings of a node
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TcpConfig {
+    /// Socket addresses to listen for incoming connections on
+    #[serde(default)]
+    pub listen: Vec<SocketAddr>,
+    /// Peers to connect to when the node starts
+    #[serde(default)]
+    pub connect: Vec<SocketAddr>,
+}
+
+/// The vault backend of a node
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase", deny_unknown_fields)]
+pub enum VaultConfig {
+    /// Keep secrets in memory with the software vault
+    Software,
+    /// Keep persistent keys in a PKCS#11 token
+    ///
+    /// ```toml
+    /// [vault]
+    /// backend = "pkcs11"
+    /// module = "/usr/lib/softhsm/libsofthsm2.so"
+    /// token = "ockam"
+    /// pin = "1234"
+    /// ```
+    Pkcs11 {
+        /// Path of the PKCS#11 module
+        module: PathBuf,
+        /// Label of the token
+        token: String,
+        /// User PIN of the token
+        pin: String,
+    },
+}
+
+/// Profile settings of a node
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Path of the file storing the profile
+    ///
+    /// A new profile is created and stored there if the file
+    /// doesn't exist yet.
+    pub path: PathBuf,
+}
+
+/// A worker started with the node
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkerConfig {
+    /// Address of the worker
+    pub address: String,
+    /// Kind of the worker, as registered with
+    /// [`NodeBuilder::worker_kind`](crate::NodeBuilder::worker_kind)
+    pub kind: String,
+    /// Settings specific to the worker kind
+    #[serde(default = "empty_settings")]
+    settings: toml::Value,
+}
+
+fn empty_settings() -> toml::Value {
+    toml::Value::Table(Default::default())
+}
+
+impl WorkerConfig {
+    /// Deserialize the settings of the worker
+    pub fn settings<T: DeserializeOwned>(&self) -> Result<T> {
+        self.settings.clone().try_into().map_err(|e| {
+            error!("Invalid settings for worker {}: {}", self.address, e);
+            Error::FailedLoadConfig.into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty() {
+        let config = NodeConfig::parse("").unwrap();
+        assert_eq!(config.address, None);
+        assert_eq!(config.worker_threads, None);
+        assert_eq!(config.tracing, None);
+        assert!(config.tcp.is_none());
+        assert!(config.vault.is_none());
+        assert!(config.profile.is_none());
+        assert!(config.workers.is_empty());
+    }
+
+    #[test]
+    fn parse_settings() {
+        let config = NodeConfig::parse(
+            r#"
+            address = "app"
+            worker_threads = 4
+            log_filter = "ockam_node=debug"
+            tracing = false
+            echo_service = true
+            registry_service = true
+            dead_letter_address = "audit"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.address.as_deref(), Some("app"));
+        assert_eq!(config.worker_threads, Some(4));
+        assert_eq!(config.log_filter.as_deref(), Some("ockam_node=debug"));
+        assert_eq!(config.tracing, Some(false));
+        assert_eq!(config.echo_service, Some(true));
+        assert_eq!(config.registry_service, Some(true));
+        assert_eq!(config.dead_letter_address.as_deref(), Some("audit"));
+    }
+
+    #[test]
+    fn parse_tcp() {
+        let config = NodeConfig::parse(
+            r#"
+            [tcp]
+            listen = ["0.0.0.0:4000", "[::1]:4001"]
+            connect = ["10.0.0.2:4000"]
+            "#,
+        )
+        .unwrap();
+
+        let tcp = config.tcp.unwrap();
+        assert_eq!(
+            tcp.listen,
+            vec![
+                "0.0.0.0:4000".parse::<SocketAddr>().unwrap(),
+                "[::1]:4001".parse().unwrap()
+            ]
+        );
+        assert_eq!(tcp.connect, vec!["10.0.0.2:4000".parse().unwrap()]);
+
+        let tcp = NodeConfig::parse("[tcp]").unwrap().tcp.unwrap();
+        assert!(tcp.listen.is_empty() && tcp.connect.is_empty());
+
+        assert!(NodeConfig::parse("[tcp]\nlisten = [\"localhost\"]").is_err());
+        assert!(NodeConfig::parse("[tcp]\nbind = []").is_err());
+    }
+
+    #[test]
+    fn parse_vault() {
+        let config = NodeConfig::parse("[vault]\nbackend = \"software\"").unwrap();
+        assert_eq!(config.vault, Some(VaultConfig::Software));
+
+        let config = NodeConfig::parse(
+            r#"
+            [vault]
+            backend = "pkcs11"
+            module = "/usr/lib/softhsm/libsofthsm2.so"
+            token = "ockam"
+            pin = "1234"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.vault,
+            Some(VaultConfig::Pkcs11 {
+                module: "/usr/lib/softhsm/libsofthsm2.so".into(),
+                token: "ockam".into(),
+                pin: "1234".into(),
+            })
+        );
+
+        assert!(NodeConfig::parse("[vault]").is_err());
+        assert!(NodeConfig::parse("[vault]\nbackend = \"tpm\"").is_err());
+        assert!(NodeConfig::parse("[vault]\nbackend = \"pkcs11\"\ntoken = \"ockam\"").is_err());
+    }
+
+    #[test]
+    fn parse_profile() {
+        let config = NodeConfig::parse("[profile]\npath = \"/var/lib/ockam/profile\"").unwrap();
+        assert_eq!(
+            config.profile.unwrap().path,
+            PathBuf::from("/var/lib/ockam/profile")
+        );
+
+        assert!(NodeConfig::parse("[profile]").is_err());
+    }
+
+    #[test]
+    fn parse_workers() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Printer {
+            color: bool,
+        }
+
+        let config = NodeConfig::parse(
+            r#"
+            [[workers]]
+            address = "printer"
+            kind = "printer"
+            settings = { color = true }
+
+            [[workers]]
+            address = "plain"
+            kind = "printer"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.workers.len(), 2);
+        assert_eq!(config.workers[0].address, "printer");
+        assert_eq!(config.workers[0].kind, "printer");
+        assert_eq!(
+            config.workers[0].settings::<Printer>().unwrap(),
+            Printer { color: true }
+        );
+        assert!(config.workers[1].settings::<Printer>().is_err());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_and_invalid_settings() {
+        assert!(NodeConfig::parse("unknown = 1").is_err());
+        assert!(NodeConfig::parse("worker_threads = \"four\"").is_err());
+        assert!(NodeConfig::parse("worker_threads = -1").is_err());
+        assert!(NodeConfig::parse("[[workers]]\naddress = \"a\"").is_err());
+    }
+}
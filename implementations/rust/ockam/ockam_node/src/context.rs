@@ -1,13 +1,28 @@
 use crate::{
+    codec::Codecs,
+    diagnostics::{self, RouteTrace, TraceProbe, PROBE_TIMEOUT},
     error::Error,
     relay::{self, RelayMessage},
-    Cancel, Mailbox, NodeMessage,
+    resources::Resources,
+    Cancel, Mailbox, NodeConfig, NodeMessage, NodeReplyResult, ShutdownReport,
+    DEFAULT_SHUTDOWN_TIMEOUT,
+};
+use ockam_core::{
+    Address, AddressSet, Codec, HopLimitExceeded, Message, PayloadKind, Result, Route,
+    TransportMessage, Typed, Worker,
+};
+use std::{
+    any::Any,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use ockam_core::{Address, AddressSet, Message, Result, Route, TransportMessage, Worker};
-use std::sync::Arc;
 use tokio::{
     runtime::Runtime,
-    sync::mpsc::{channel, Sender},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        watch,
+    },
 };
 
 pub struct Context {
@@ -15,6 +30,15 @@ pub struct Context {
     msg_addr: Option<Address>,
     sender: Sender<NodeMessage>,
     rt: Arc<Runtime>,
+    codecs: Arc<Codecs>,
+    dead_letter: Option<Address>,
+    resources: Arc<Resources>,
+    /// Dropping the sender cancels all tasks spawned by the worker
+    ///
+    /// Only the context of the worker itself holds the sender, the
+    /// contexts of its tasks share its receiver.
+    tasks: Option<watch::Sender<()>>,
+    cancel: watch::Receiver<()>,
     pub(crate) mailbox: Mailbox,
 }
 
@@ -24,12 +48,21 @@ impl Context {
         sender: Sender<NodeMessage>,
         address: AddressSet,
         mailbox: Mailbox,
+        codecs: Arc<Codecs>,
+        dead_letter: Option<Address>,
+        resources: Arc<Resources>,
     ) -> Self {
+        let (tasks, cancel) = watch::channel(());
         Self {
             rt,
             sender,
+            codecs,
+            dead_letter,
+            resources,
             address,
             msg_addr: None,
+            tasks: Some(tasks),
+            cancel,
             mailbox,
         }
     }
@@ -51,42 +84,252 @@ impl Context {
             .unwrap()
     }
 
+    /// Return the codec used for payloads of message type `M`
+    ///
+    /// This is the codec configured for `M` via
+    /// [`NodeBuilder::message_codec`], or the node's default codec.
+    ///
+    /// [`NodeBuilder::message_codec`]: crate::NodeBuilder::message_codec
+    pub fn codec<M: Message>(&self) -> &dyn Codec {
+        self.codecs.get::<M>()
+    }
+
+    /// Return the address denied messages are reported to, if any
+    ///
+    /// See [`NodeBuilder::dead_letter_address`](crate::NodeBuilder::dead_letter_address).
+    pub fn dead_letter_address(&self) -> Option<&Address> {
+        self.dead_letter.as_ref()
+    }
+
+    /// Return the configuration the node was started with
+    ///
+    /// This is empty unless the node loaded a configuration file,
+    /// see [`NodeBuilder::config_file`](crate::NodeBuilder::config_file).
+    pub fn node_config(&self) -> &NodeConfig {
+        self.resources.config()
+    }
+
+    /// Share a value with all workers of the node
+    ///
+    /// This replaces the value of the same type shared before, if
+    /// any.  It is used for example to share the vault and profile
+    /// opened from the configuration file of the node.
+    pub fn set_resource<T: Any + Send + Sync>(&self, value: T) {
+        self.resources.set(value)
+    }
+
+    /// Return the value of type `T` shared with the workers of the node
+    pub fn resource<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.resources.get()
+    }
+
     /// Start a new worker handle at [`Address`](ockam_core::Address)
+    ///
+    /// Fails if one of the worker's addresses is already taken.
     pub async fn start_worker<NM, NW, S>(&self, address: S, worker: NW) -> Result<()>
     where
         S: Into<AddressSet>,
         NM: Message + Send + 'static,
         NW: Worker<Context = Context, Message = NM>,
     {
-        let address = address.into();
+        let mut rx = self.request_start_worker(address.into(), worker).await?;
+        Ok(rx.recv().await.ok_or(Error::InternalIOFailure)??.is_ok()?)
+    }
 
+    /// Start a worker without waiting for the router to register it
+    ///
+    /// This is used to start workers before the router runs.
+    pub(crate) async fn request_start_worker<NM, NW>(
+        &self,
+        address: AddressSet,
+        worker: NW,
+    ) -> Result<Receiver<NodeReplyResult>>
+    where
+        NM: Message + Send + 'static,
+        NW: Worker<Context = Context, Message = NM>,
+    {
         // Build the mailbox first
         let (mb_tx, mb_rx) = channel(32);
         let mb = Mailbox::new(mb_rx, mb_tx.clone());
 
         // Pass it to the context
-        let ctx = Context::new(self.rt.clone(), self.sender.clone(), address.clone(), mb);
+        let ctx = Context::new(
+            self.rt.clone(),
+            self.sender.clone(),
+            address.clone(),
+            mb,
+            self.codecs.clone(),
+            self.dead_letter.clone(),
+            self.resources.clone(),
+        );
 
         // Then initialise the worker message relay
-        let sender = relay::build::<NW, NM>(self.rt.as_ref(), worker, ctx);
+        let (sender, task) = relay::build::<NW, NM>(self.rt.as_ref(), worker, ctx);
 
-        let msg = NodeMessage::start_worker(address, sender);
-        let _result: Result<()> = match self.sender.send(msg).await {
-            Ok(()) => Ok(()),
-            Err(_e) => Err(Error::FailedStartWorker.into()),
-        };
+        let (msg, rx) = NodeMessage::start_worker(address, sender, Some(task));
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| Error::FailedStartWorker)?;
+        Ok(rx)
+    }
+
+    /// Spawn a background task owned by this worker
+    ///
+    /// The task is given its own context, which shares the worker's
+    /// address and can be used to send messages.  It does not have a
+    /// mailbox of its own: replies are delivered to the worker.
+    ///
+    /// The task is cancelled when the worker stops, and so are the
+    /// tasks it spawns itself.
+    pub fn spawn<F, Fut>(&self, f: F)
+    where
+        F: FnOnce(Context) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (mb_tx, mb_rx) = channel(1);
+        let mut ctx = Context::new(
+            self.rt.clone(),
+            self.sender.clone(),
+            self.address.clone(),
+            Mailbox::new(mb_rx, mb_tx),
+            self.codecs.clone(),
+            self.dead_letter.clone(),
+            self.resources.clone(),
+        );
+        ctx.tasks = None;
+        ctx.cancel = self.cancel.clone();
+
+        let addr = self.address.first();
+        let mut cancel = self.cancel.clone();
+        let task = f(ctx);
+        self.rt.spawn(async move {
+            tokio::select! {
+                res = task => if let Err(e) = res {
+                    error!("Task of worker {} failed: {}", addr, e);
+                },
+                // Fails once the worker's context cancelled its tasks
+                _ = cancel.changed() => trace!("Cancelled task of worker {}", addr),
+            }
+        });
+    }
+
+    /// Cancel all tasks spawned by this worker
+    pub(crate) fn cancel_tasks(&mut self) {
+        let (tasks, cancel) = watch::channel(());
+        self.tasks = Some(tasks);
+        self.cancel = cancel;
+    }
+
+    /// Create a context with its own address which is not backed by a worker
+    ///
+    /// Messages sent to `address` are queued until they are received
+    /// via the new context, like for the root application context.
+    /// This lets other types than workers send requests and wait for
+    /// replies.  Fails if the address is already taken.
+    pub async fn new_context<A: Into<Address>>(&self, address: A) -> Result<Context> {
+        let address = AddressSet::from(address.into());
+        let (mb_tx, mb_rx) = channel(32);
+        let ctx = Context::new(
+            self.rt.clone(),
+            self.sender.clone(),
+            address.clone(),
+            Mailbox::new(mb_rx, mb_tx),
+            self.codecs.clone(),
+            self.dead_letter.clone(),
+            self.resources.clone(),
+        );
+
+        let sender = relay::build_detached(self.rt.as_ref(), &ctx.mailbox);
+        let (msg, mut rx) = NodeMessage::start_worker(address, sender, None);
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| Error::FailedStartWorker)?;
+
+        rx.recv().await.ok_or(Error::InternalIOFailure)??.is_ok()?;
+        Ok(ctx)
+    }
+
+    /// Return all addresses of this worker
+    ///
+    /// The first one is the primary address of the worker.
+    pub fn addresses(&self) -> &AddressSet {
+        &self.address
+    }
 
+    /// Add an address to this worker
+    ///
+    /// Messages sent to the new address are delivered to this
+    /// worker.  Fails if another worker already uses the address.
+    pub async fn add_address<A: Into<Address>>(&mut self, addr: A) -> Result<()> {
+        let addr = addr.into();
+        let (msg, mut rx) = NodeMessage::add_address(self.address.first(), addr.clone());
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| Error::InternalIOFailure)?;
+
+        rx.recv().await.ok_or(Error::InternalIOFailure)??.is_ok()?;
+        self.address.push(addr);
+        Ok(())
+    }
+
+    /// Remove an address from this worker
+    ///
+    /// The primary address of a worker can't be removed.
+    pub async fn remove_address<A: Into<Address>>(&mut self, addr: A) -> Result<()> {
+        let addr = addr.into();
+        let (msg, mut rx) = NodeMessage::remove_address(self.address.first(), addr.clone());
+        self.sender
+            .send(msg)
+            .await
+            .map_err(|_| Error::InternalIOFailure)?;
+
+        rx.recv().await.ok_or(Error::InternalIOFailure)??.is_ok()?;
+        self.address.remove(&addr);
         Ok(())
     }
 
     /// Signal to the local application runner to shut down
+    ///
+    /// Workers are stopped gracefully, waiting up to
+    /// [`DEFAULT_SHUTDOWN_TIMEOUT`].  This does not wait for the
+    /// node to stop, so it can be called from within a worker.
     pub async fn stop(&self) -> Result<()> {
-        let tx = self.sender.clone();
         info!("Shutting down all workers");
-        match tx.send(NodeMessage::StopNode).await {
-            Ok(()) => Ok(()),
-            Err(_e) => Err(Error::FailedStopNode.into()),
-        }
+        let (req, _rx) = NodeMessage::stop_node(DEFAULT_SHUTDOWN_TIMEOUT);
+        self.sender
+            .send(req)
+            .await
+            .map_err(|_| Error::FailedStopNode.into())
+    }
+
+    /// Stop the node gracefully and wait for it to stop
+    ///
+    /// New workers, messages for external routers and messages
+    /// arriving from transports are refused.  Workers are then
+    /// stopped in reverse start order: each handles the messages
+    /// already in its mailbox and runs its shutdown hook.  Workers
+    /// which have not stopped when `timeout` expires, including those
+    /// which were not asked to stop yet, are aborted and listed as
+    /// failed in the returned report.
+    ///
+    /// Don't call this from within a worker's message handler, the
+    /// worker could not stop until the handler returns.
+    pub async fn stop_with_timeout(&self, timeout: Duration) -> Result<ShutdownReport> {
+        info!("Shutting down all workers");
+        let (req, mut rx) = NodeMessage::stop_node(timeout);
+        self.sender
+            .send(req)
+            .await
+            .map_err(|_| Error::FailedStopNode)?;
+
+        Ok(rx
+            .recv()
+            .await
+            .ok_or(Error::FailedStopNode)??
+            .take_shutdown_report()?)
     }
 
     /// Shut down a worker by its primary address
@@ -116,6 +359,15 @@ impl Context {
     /// [`Address`]: ockam_core::Address
     /// [`RouteBuilder`]: ockem_core::RouteBuilder
     pub async fn send_message<R, M>(&self, route: R, msg: M) -> Result<()>
+    where
+        R: Into<Route>,
+        M: Message + Send + 'static,
+    {
+        self.send_kind(route, msg, PayloadKind::Data).await
+    }
+
+    /// Send a message of a kind generated by the node, e.g. a report
+    pub(crate) async fn send_kind<R, M>(&self, route: R, msg: M, kind: PayloadKind) -> Result<()>
     where
         R: Into<Route>,
         M: Message + Send + 'static,
@@ -123,7 +375,7 @@ impl Context {
         let route = route.into();
         let (reply_tx, mut reply_rx) = channel(1);
         let next = route.next().unwrap(); // TODO: communicate bad routes
-        let req = NodeMessage::SenderReq(next.clone(), reply_tx);
+        let req = NodeMessage::SenderReq(next.clone(), false, reply_tx);
 
         // First resolve the next hop in the route
         self.sender.send(req).await.map_err(|e| Error::from(e))?;
@@ -133,9 +385,11 @@ impl Context {
             .ok_or(Error::InternalIOFailure)??
             .take_sender()?;
 
-        // Pack the payload into a TransportMessage
-        let payload = msg.encode().unwrap();
-        let mut data = TransportMessage::v1(route.clone(), payload);
+        // Pack the payload into a TransportMessage.  Transports send it
+        // in the version 1 layout to peers not known to support version 2
+        let payload = self.codec::<M>().encode_message(&msg)?;
+        let mut data = TransportMessage::v2(route.clone(), payload);
+        data.kind = kind;
         data.return_.modify().append(self.address());
 
         // Pack transport message into relay message wrapper
@@ -161,29 +415,118 @@ impl Context {
     /// [`Context::send_message`] instead, unless you are writing an
     /// external router implementation for ockam node.
     ///
+    /// This is the only place where the hop limit of a message is
+    /// decremented, once for every time the message is forwarded.
+    /// Messages received by a transport are forwarded to the node,
+    /// and messages passed to a router are forwarded by the router,
+    /// so a message relayed by a node takes one hop.  Routers which
+    /// hand messages to their connection workers don't forward them,
+    /// and don't take a hop.  If the hop limit has expired, a
+    /// [`HopLimitExceeded`] message is sent to the return route of
+    /// the message, and an error is returned.
+    ///
     /// [`Context::send_message`]: crate::Context::send_message
     /// [`TransportMessage`]: ockam_core::TransportMessage
-    pub async fn forward_message(&self, data: TransportMessage) -> Result<()> {
+    /// [`HopLimitExceeded`]: ockam_core::HopLimitExceeded
+    pub async fn forward_message(&self, mut data: TransportMessage) -> Result<()> {
+        self.take_hop(&mut data).await?;
+
         // Resolve the sender for the next hop in the messages route
         let (reply_tx, mut reply_rx) = channel(1);
         let next = data.onward.next().unwrap(); // TODO: communicate bad routes
-        let req = NodeMessage::SenderReq(next.clone(), reply_tx);
+        let inbound = data.local.transport.is_some();
+        let req = NodeMessage::SenderReq(next.clone(), inbound, reply_tx);
 
         // First resolve the next hop in the route
         self.sender.send(req).await.map_err(|e| Error::from(e))?;
-        let (addr, sender, _) = reply_rx
+        let (addr, sender, needs_wrapping) = reply_rx
             .recv()
             .await
             .ok_or(Error::InternalIOFailure)??
             .take_sender()?;
 
-        // Pack the transport message into a relay message
-        let msg = RelayMessage::direct(addr, data);
+        // Pack the transport message into a relay message, keeping
+        // its local information
+        let msg = if needs_wrapping {
+            RelayMessage::pre_router(addr, data)
+        } else {
+            RelayMessage::direct(addr, data)
+        };
         sender.send(msg).await.map_err(|e| Error::from(e))?;
 
         Ok(())
     }
 
+    /// Decrement the hop limit of a forwarded message, reporting
+    /// expired messages to their return route
+    async fn take_hop(&self, data: &mut TransportMessage) -> Result<()> {
+        let err = match data.hop() {
+            Ok(()) => {
+                diagnostics::record_hop(self, data);
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        warn!(
+            "Dropping message for {}: hop limit exceeded (return route: {})",
+            data.onward, data.return_
+        );
+
+        // Never report an expired report, to not create a new loop
+        if !data.return_.is_empty() && data.kind != PayloadKind::Report {
+            let report = Typed::new(HopLimitExceeded {
+                onward: data.onward.clone(),
+            });
+
+            // Reporting is best effort, the return route may be broken too
+            let route = data.return_.clone();
+            if let Err(e) = self.send_kind(route, report, PayloadKind::Report).await {
+                debug!("Failed to report expired message: {}", e);
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Trace the hops of a route to an echo service
+    ///
+    /// The route must end at an
+    /// [`EchoService`](crate::EchoService), e.g. at
+    /// [`ECHO_SERVICE_ADDRESS`](crate::ECHO_SERVICE_ADDRESS) on the
+    /// destination node.  The returned trace contains the address
+    /// and time of each router hop in both directions.
+    pub async fn trace_route<R: Into<Route>>(&mut self, route: R) -> Result<RouteTrace> {
+        let probe = TraceProbe::new(self.address());
+        let nonce = probe.nonce;
+        let start = Instant::now();
+        self.send_kind(route, Typed::new(probe), PayloadKind::Probe)
+            .await?;
+
+        let probe = tokio::time::timeout(
+            PROBE_TIMEOUT,
+            self.receive_match(|probe: &Typed<TraceProbe>| probe.nonce == nonce),
+        )
+        .await
+        .map_err(|_| Error::ExecutionTimeout)??
+        .take()
+        .take();
+
+        let mut hops = probe.hops;
+        hops.push(diagnostics::Hop::now(self.address()));
+        Ok(RouteTrace {
+            hops,
+            round_trip: start.elapsed(),
+        })
+    }
+
+    /// Measure the round trip time of a route to an echo service
+    ///
+    /// See [`Context::trace_route`] for details.
+    pub async fn ping<R: Into<Route>>(&mut self, route: R) -> Result<Duration> {
+        Ok(self.trace_route(route).await?.round_trip)
+    }
+
     /// Block the current worker to wait for a typed message
     ///
     /// Will return `None` if the corresponding worker has been
@@ -253,12 +596,197 @@ impl Context {
             .next()
             .await
             .and_then(|relay_msg| {
-                let (addr, data) = relay_msg.transport();
-
-                M::decode(&data.payload)
+                let (addr, mut data) = relay_msg.transport();
+                data.local
+                    .arrival
+                    .get_or_insert_with(diagnostics::unix_micros);
+
+                self.codecs
+                    .get::<M>()
+                    .decode_message(&data.payload)
                     .ok()
                     .map(move |msg| (msg, data, addr))
             })
             .ok_or_else(|| Error::FailedLoadData.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeBuilder;
+    use std::future;
+    use tokio::{
+        sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        time::{sleep, timeout},
+    };
+
+    /// Reports when the task holding it is dropped
+    struct Guard(UnboundedSender<()>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let _ = self.0.send(());
+        }
+    }
+
+    /// Spawns a task which spawns another one running forever
+    struct Spawner {
+        started: UnboundedSender<()>,
+        dropped: UnboundedSender<()>,
+    }
+
+    #[ockam_core::async_trait::async_trait]
+    impl Worker for Spawner {
+        type Message = ();
+        type Context = Context;
+
+        async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+            let (started, dropped) = (self.started.clone(), self.dropped.clone());
+            ctx.spawn(move |ctx| async move {
+                ctx.spawn(move |_| async move {
+                    let _guard = Guard(dropped);
+                    let _ = started.send(());
+                    future::pending().await
+                });
+                Ok(())
+            });
+            Ok(())
+        }
+    }
+
+    /// Requests to a worker which manages its own addresses
+    #[derive(serde::Serialize, serde::Deserialize)]
+    enum AddressRequest {
+        Add(Address),
+        Remove(Address),
+        Ping,
+    }
+
+    /// Answers requests with its primary address, or the error code
+    /// of the request
+    struct Aliases;
+
+    type Answer = std::result::Result<Address, u32>;
+
+    #[ockam_core::async_trait::async_trait]
+    impl Worker for Aliases {
+        type Message = AddressRequest;
+        type Context = Context;
+
+        async fn handle_message(
+            &mut self,
+            ctx: &mut Context,
+            msg: ockam_core::Routed<AddressRequest>,
+        ) -> Result<()> {
+            let reply = msg.reply();
+            let result = match msg.take() {
+                AddressRequest::Add(addr) => ctx.add_address(addr).await,
+                AddressRequest::Remove(addr) => ctx.remove_address(addr).await,
+                AddressRequest::Ping => Ok(()),
+            };
+            let answer: Answer = result
+                .map(|_| ctx.addresses().first())
+                .map_err(|e| e.code());
+            ctx.send_message(reply, answer).await
+        }
+    }
+
+    /// Send a request to `addr`, returning the answer of the worker
+    async fn request(ctx: &mut Context, addr: &str, request: AddressRequest) -> Result<Answer> {
+        ctx.send_message(addr, request).await?;
+        Ok(ctx.receive::<Answer>().await?.take())
+    }
+
+    async fn recv(rx: &mut UnboundedReceiver<()>) -> Result<()> {
+        timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .map_err(|_| Error::ExecutionTimeout)?
+            .ok_or_else(|| Error::InternalIOFailure.into())
+    }
+
+    #[test]
+    fn stopping_a_worker_cancels_nested_tasks() {
+        let (ctx, mut executor) = NodeBuilder::new().tracing(false).build().unwrap();
+        let (started, mut started_rx) = unbounded_channel();
+        let (dropped, mut dropped_rx) = unbounded_channel();
+
+        executor
+            .execute_test(
+                async move {
+                    ctx.start_worker("spawner", Spawner { started, dropped })
+                        .await?;
+                    recv(&mut started_rx).await?;
+
+                    // The nested task outlives the task which spawned it...
+                    sleep(Duration::from_millis(50)).await;
+                    assert!(dropped_rx.try_recv().is_err());
+
+                    // ...until the worker stops
+                    ctx.stop_worker("spawner").await?;
+                    recv(&mut dropped_rx).await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn running_workers_add_and_remove_addresses() {
+        let (mut ctx, mut executor) = NodeBuilder::new().tracing(false).build().unwrap();
+        executor
+            .execute_test(
+                async move {
+                    ctx.start_worker("aliases", Aliases).await?;
+                    ctx.start_worker("other", Aliases).await?;
+                    let aliases = Ok(Address::from("aliases"));
+                    let other = Ok(Address::from("other"));
+                    let taken = Err(ockam_core::Error::from(Error::AddressTaken).code());
+
+                    // Messages to an added address reach the running worker
+                    let add = AddressRequest::Add("alias".into());
+                    assert_eq!(request(&mut ctx, "aliases", add).await?, aliases);
+                    let ping = request(&mut ctx, "alias", AddressRequest::Ping).await?;
+                    assert_eq!(ping, aliases);
+
+                    // Addresses of other workers can't be added
+                    let add = AddressRequest::Add("alias".into());
+                    assert_eq!(request(&mut ctx, "other", add).await?, taken);
+                    let add = AddressRequest::Add("aliases".into());
+                    assert_eq!(request(&mut ctx, "other", add).await?, taken);
+
+                    // Workers can't be started on a taken address, which
+                    // still reaches the worker owning it
+                    let err = ctx.start_worker("alias", Aliases).await.unwrap_err();
+                    assert_eq!(Err(err.code()), taken);
+                    let err = ctx
+                        .start_worker(vec!["fresh", "other"], Aliases)
+                        .await
+                        .unwrap_err();
+                    assert_eq!(Err(err.code()), taken);
+                    let ping = request(&mut ctx, "alias", AddressRequest::Ping).await?;
+                    assert_eq!(ping, aliases);
+                    let ping = request(&mut ctx, "other", AddressRequest::Ping).await?;
+                    assert_eq!(ping, other);
+                    assert!(ctx
+                        .send_message("fresh", AddressRequest::Ping)
+                        .await
+                        .is_err());
+
+                    // A removed address doesn't resolve anymore
+                    let remove = AddressRequest::Remove("alias".into());
+                    assert_eq!(request(&mut ctx, "aliases", remove).await?, aliases);
+                    assert!(ctx
+                        .send_message("alias", AddressRequest::Ping)
+                        .await
+                        .is_err());
+                    let ping = request(&mut ctx, "aliases", AddressRequest::Ping).await?;
+                    assert_eq!(ping, aliases);
+
+                    ctx.stop().await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,51 @@
+//! Reporting of messages denied by worker access control
+//!
+//! Nodes configured with
+//! [`NodeBuilder::dead_letter_address`](crate::NodeBuilder::dead_letter_address)
+//! send a [`DeadLetter`] to that address for every message a worker's
+//! [`AccessControl`](ockam_core::AccessControl) policy rejects.
+
+use crate::Context;
+use ockam_core::{Address, PayloadKind, Route, Typed, TypedMessage};
+use serde::{Deserialize, Serialize};
+
+/// A message which was denied by the access control of a worker
+///
+/// Receive it as a [`Typed`] message to audit denied messages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// The address the message was sent to
+    pub address: Address,
+    /// The return route of the message
+    pub return_route: Route,
+    /// The encoded message payload
+    pub payload: Vec<u8>,
+}
+
+impl TypedMessage for DeadLetter {
+    const TYPE_ID: &'static str = "ockam.access_control.dead_letter";
+    const VERSION: u16 = 1;
+}
+
+/// Log a denied message and report it to the dead letter address
+pub(crate) async fn report(ctx: &Context, letter: DeadLetter, kind: PayloadKind) {
+    warn!(
+        "Message from {} to worker {} denied by access control",
+        letter.return_route, letter.address
+    );
+
+    let sink = match ctx.dead_letter_address() {
+        Some(sink) => sink.clone(),
+        None => return,
+    };
+
+    // Never report denied reports, which could loop forever
+    if kind == PayloadKind::Report {
+        return;
+    }
+
+    let letter = Typed::new(letter);
+    if let Err(e) = ctx.send_kind(sink, letter, PayloadKind::Report).await {
+        debug!("Failed to send dead letter: {}", e);
+    }
+}
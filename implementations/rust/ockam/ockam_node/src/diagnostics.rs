@@ -0,0 +1,158 @@
+//! Route diagnostics: an echo service and route tracing
+//!
+//! A [`TraceProbe`] sent to an [`EchoService`] records the address
+//! and time of every router hop it passes, in both directions.  Use
+//! [`Context::trace_route`] and [`Context::ping`] to send probes.
+
+use crate::Context;
+use ockam_core::{
+    Address, PayloadKind, Result, Routed, TransportMessage, Typed, TypedMessage, Worker,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The address of the echo service started by a node
+pub const ECHO_SERVICE_ADDRESS: &str = "ockam.echo";
+
+/// How long to wait for a probe to return
+pub(crate) const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The current time, in microseconds since the UNIX epoch
+pub(crate) fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// A single hop recorded by a [`TraceProbe`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    /// The address of the worker handling the probe
+    pub address: Address,
+    /// The time of the hop, in microseconds since the UNIX epoch
+    pub timestamp: u64,
+}
+
+impl Hop {
+    /// Record a hop at the current time
+    pub fn now(address: Address) -> Self {
+        Self {
+            address,
+            timestamp: unix_micros(),
+        }
+    }
+}
+
+/// A message recording the hops of its route
+///
+/// Routers append a [`Hop`] to probes they forward, and the
+/// [`EchoService`] returns probes to their sender.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TraceProbe {
+    /// Identifies the probe to the sender
+    pub nonce: u64,
+    /// The hops recorded so far
+    pub hops: Vec<Hop>,
+}
+
+impl TypedMessage for TraceProbe {
+    const TYPE_ID: &'static str = "ockam.diagnostics.trace";
+    const VERSION: u16 = 1;
+}
+
+impl TraceProbe {
+    /// Create a new probe, starting at the given address
+    pub(crate) fn new(origin: Address) -> Self {
+        static NONCE: AtomicU64 = AtomicU64::new(0);
+
+        Self {
+            nonce: NONCE.fetch_add(1, Ordering::Relaxed),
+            hops: vec![Hop::now(origin)],
+        }
+    }
+}
+
+/// Record a hop if a transport message carries a [`TraceProbe`]
+pub(crate) fn record_hop(ctx: &Context, data: &mut TransportMessage) {
+    if data.kind != PayloadKind::Probe {
+        return;
+    }
+
+    let codec = ctx.codec::<Typed<TraceProbe>>();
+    let payload = codec
+        .decode_message::<Typed<TraceProbe>>(&data.payload)
+        .and_then(|mut probe| {
+            probe.hops.push(Hop::now(ctx.address()));
+            codec.encode_message(&probe)
+        });
+
+    match payload {
+        Ok(payload) => data.payload = payload,
+        Err(e) => debug!("Failed to record trace probe hop: {}", e),
+    }
+}
+
+/// The result of tracing a route
+#[derive(Debug, Clone)]
+pub struct RouteTrace {
+    /// The recorded hops, starting and ending at the sender
+    pub hops: Vec<Hop>,
+    /// The round trip time measured by the sender
+    pub round_trip: Duration,
+}
+
+impl RouteTrace {
+    /// The time elapsed between each hop and the previous one
+    ///
+    /// Timestamps are taken from the clock of each node, so
+    /// latencies between different nodes are only approximate.
+    pub fn latencies(&self) -> Vec<(Address, Duration)> {
+        self.hops
+            .windows(2)
+            .map(|pair| {
+                let micros = pair[1].timestamp.saturating_sub(pair[0].timestamp);
+                (pair[1].address.clone(), Duration::from_micros(micros))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RouteTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(first) = self.hops.first() {
+            writeln!(f, "{}", first.address)?;
+        }
+        for (address, latency) in self.latencies() {
+            writeln!(f, "{} (+{:?})", address, latency)?;
+        }
+        write!(f, "round trip: {:?}", self.round_trip)
+    }
+}
+
+/// A worker returning every [`TraceProbe`] to its sender
+///
+/// Nodes start this service at [`ECHO_SERVICE_ADDRESS`] if enabled
+/// with [`NodeBuilder::echo_service`](crate::NodeBuilder::echo_service).
+pub struct EchoService;
+
+#[ockam_core::async_trait::async_trait]
+impl Worker for EchoService {
+    type Message = Typed<TraceProbe>;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Typed<TraceProbe>>,
+    ) -> Result<()> {
+        let route = msg.reply();
+        let mut probe = msg.take();
+        probe.hops.push(Hop::now(ctx.address()));
+        ctx.send_kind(route, probe, PayloadKind::Probe).await
+    }
+}
@@ -18,6 +18,22 @@ pub enum Error {
     FailedLoadData,
     /// An umbrella for internal I/O failures
     InternalIOFailure,
+    /// Execution did not complete before its deadline
+    ExecutionTimeout,
+    /// Unable to load or parse the node configuration
+    FailedLoadConfig,
+    /// Unable to build the node runtime
+    FailedBuildRuntime,
+    /// The address is already used by another worker
+    AddressTaken,
+    /// The worker does not have the address
+    NoSuchAddress,
+    /// The primary address of a worker can't be removed
+    RemovePrimaryAddress,
+    /// The node is shutting down
+    NodeStopping,
+    /// The time to live of a service is too short to refresh it
+    InvalidServiceTtl,
 }
 
 impl Error {
@@ -34,8 +50,16 @@ impl From<Error> for ockam_core::Error {
 }
 
 impl From<crate::NodeError> for ockam_core::Error {
-    fn from(_: crate::NodeError) -> Self {
-        Error::InternalIOFailure.into()
+    fn from(e: crate::NodeError) -> Self {
+        use crate::NodeError::*;
+        match e {
+            AddressTaken(_) => Error::AddressTaken,
+            NoSuchAddress(_) => Error::NoSuchAddress,
+            PrimaryAddress(_) => Error::RemovePrimaryAddress,
+            NodeStopping => Error::NodeStopping,
+            NoSuchWorker(_) | RouterExists => Error::InternalIOFailure,
+        }
+        .into()
     }
 }
 
@@ -1,9 +1,11 @@
 // use crate::message::BaseMessage;
 
-use crate::{relay::RelayMessage, router::Router, NodeMessage};
+use crate::{
+    error::Error, relay::RelayMessage, router::Router, NodeMessage, DEFAULT_SHUTDOWN_TIMEOUT,
+};
 use ockam_core::{Address, Result};
 
-use std::{future::Future, sync::Arc};
+use std::{future::Future, sync::Arc, time::Duration};
 use tokio::{runtime::Runtime, sync::mpsc::Sender};
 
 /// Ockam node and worker executor
@@ -16,9 +18,7 @@ pub struct Executor {
 
 impl Default for Executor {
     fn default() -> Self {
-        let rt = Arc::new(Runtime::new().unwrap());
-        let router = Router::new();
-        Self { rt, router }
+        Self::with_runtime(Runtime::new().unwrap())
     }
 }
 
@@ -28,6 +28,13 @@ impl Executor {
         Executor::default()
     }
 
+    /// Create a new [`Executor`] driven by the given runtime
+    pub(crate) fn with_runtime(rt: Runtime) -> Self {
+        let rt = Arc::new(rt);
+        let router = Router::new();
+        Self { rt, router }
+    }
+
     pub(crate) fn sender(&self) -> Sender<NodeMessage> {
         self.router.sender()
     }
@@ -58,4 +65,43 @@ impl Executor {
         // returning any critical failures that it encounters.
         rt.block_on(self.router.run())
     }
+
+    /// Execute a test future, stopping the node when it completes
+    ///
+    /// Unlike [`Executor::execute`], the result of the future is
+    /// returned to the caller.  If the future does not complete
+    /// within `timeout` it is cancelled and an error is returned.
+    /// The node is always stopped afterwards, even if the future
+    /// panicked, in which case the panic is resumed on the calling
+    /// thread.
+    pub fn execute_test<F>(&mut self, future: F, timeout: Duration) -> Result<()>
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let rt = Arc::clone(&self.rt);
+        let sender = self.sender();
+
+        // Run the future in its own task so that a panic can't skip
+        // stopping the node
+        let mut test = rt.spawn(future);
+        let watcher = rt.spawn(async move {
+            let res = tokio::time::timeout(timeout, &mut test).await;
+            if res.is_err() {
+                test.abort();
+            }
+
+            let (req, _rx) = NodeMessage::stop_node(DEFAULT_SHUTDOWN_TIMEOUT);
+            let _ = sender.send(req).await;
+            res
+        });
+
+        rt.block_on(self.router.run())?;
+
+        match rt.block_on(watcher) {
+            Ok(Ok(Ok(res))) => res,
+            Ok(Ok(Err(e))) | Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Ok(Err(_)) => Err(Error::ExecutionTimeout.into()),
+            _ => Err(Error::InternalIOFailure.into()),
+        }
+    }
 }
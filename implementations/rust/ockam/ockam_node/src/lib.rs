@@ -12,18 +12,33 @@
 #[macro_use]
 extern crate tracing;
 
+mod codec;
+mod config;
 mod context;
+mod dead_letter;
+mod diagnostics;
 mod error;
 mod executor;
 mod mailbox;
 mod messages;
 mod node;
+mod registry;
 mod relay;
+mod resources;
 mod router;
+mod shutdown;
 
+pub use config::{NodeConfig, ProfileConfig, TcpConfig, VaultConfig, WorkerConfig};
 pub use context::*;
+pub use dead_letter::DeadLetter;
+pub use diagnostics::{EchoService, Hop, RouteTrace, TraceProbe, ECHO_SERVICE_ADDRESS};
 pub use executor::*;
 pub use mailbox::*;
 pub use messages::*;
 
-pub use node::start_node;
+pub use node::{start_node, NodeBuilder};
+pub use registry::{
+    LocalRegistry, RegistryRequest, RegistryResponse, RegistryService, RemoteRegistry,
+    ServiceDiscovery, ServiceEntry, ServiceInfo, REGISTRY_SERVICE_ADDRESS,
+};
+pub use shutdown::{ShutdownReport, DEFAULT_SHUTDOWN_TIMEOUT};
@@ -1,7 +1,13 @@
-use crate::{relay::RelayMessage, Context};
-use ockam_core::{Address, Message, TransportMessage};
+use crate::{
+    relay::{RelayMessage, RelayPayload},
+    Context,
+};
+use ockam_core::{Address, LocalInfo, Message, TransportMessage};
 use std::fmt::{self, Debug, Display, Formatter};
-use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::{
+    mpsc::{Receiver, Sender},
+    oneshot,
+};
 
 /// A mailbox for encoded messages
 ///
@@ -12,11 +18,12 @@ use tokio::sync::mpsc::{Receiver, Sender};
 pub struct Mailbox {
     rx: Receiver<RelayMessage>,
     tx: Sender<RelayMessage>,
+    stop: Option<oneshot::Sender<()>>,
 }
 
 impl Mailbox {
     pub fn new(rx: Receiver<RelayMessage>, tx: Sender<RelayMessage>) -> Self {
-        Self { rx, tx }
+        Self { rx, tx, stop: None }
     }
 
     pub fn sender(&self) -> Sender<RelayMessage> {
@@ -24,8 +31,29 @@ impl Mailbox {
     }
 
     /// Get the next message from the mailbox
+    ///
+    /// Returns `None` once the worker was asked to stop, after all
+    /// messages queued before the stop signal have been returned.
     pub async fn next(&mut self) -> Option<RelayMessage> {
-        self.rx.recv().await
+        if self.stop.is_some() {
+            return None;
+        }
+
+        match self.rx.recv().await? {
+            RelayMessage {
+                data: RelayPayload::Stop(ack),
+                ..
+            } => {
+                self.stop = Some(ack);
+                None
+            }
+            msg => Some(msg),
+        }
+    }
+
+    /// Take the acknowledgement handle of a received stop signal
+    pub(crate) fn take_stop(&mut self) -> Option<oneshot::Sender<()>> {
+        self.stop.take()
     }
 
     /// If a message wasn't expected, requeue it
@@ -61,6 +89,16 @@ impl<'ctx, M: Message> Cancel<'ctx, M> {
         }
     }
 
+    /// Get the local information about how the message arrived
+    pub fn local(&self) -> &LocalInfo {
+        &self.trans.local
+    }
+
+    /// Consume the wrapper, returning the inner message
+    pub fn take(self) -> M {
+        self.inner
+    }
+
     /// Cancel this message
     pub async fn cancel(self) {
         let ctx = self.ctx;
@@ -1,28 +1,53 @@
-use crate::{error::Error, relay::RelayMessage};
+use crate::{error::Error, relay::RelayMessage, ShutdownReport};
 use ockam_core::{Address, AddressSet};
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+use tokio::{
+    sync::mpsc::{channel, Receiver, Sender},
+    task::JoinHandle,
+};
 
 /// Messages sent from the Node to the Executor
 #[derive(Debug)]
 pub enum NodeMessage {
     /// Start a new worker and store the send handle
-    StartWorker(AddressSet, Sender<RelayMessage>),
+    ///
+    /// The task running the worker, if any, is aborted if the worker
+    /// does not stop before the node shutdown deadline.
+    StartWorker(
+        AddressSet,
+        Sender<RelayMessage>,
+        Option<JoinHandle<()>>,
+        Sender<NodeReplyResult>,
+    ),
     /// Return a list of all worker addresses
     ListWorkers(Sender<NodeReplyResult>),
     /// Stop an existing worker
     StopWorker(Address, Sender<NodeReplyResult>),
-    /// Stop the node (and all workers)
-    StopNode,
+    /// Add an address to the worker with the given primary address
+    AddAddress(Address, Address, Sender<NodeReplyResult>),
+    /// Remove an address from the worker with the given primary address
+    RemoveAddress(Address, Address, Sender<NodeReplyResult>),
+    /// Stop the node (and all workers) within a timeout
+    StopNode(Duration, Sender<NodeReplyResult>),
+    /// A worker stopped during node shutdown, or failed to in time
+    StopAck(Address, bool),
     /// Request the sender for a worker address
-    SenderReq(Address, Sender<NodeReplyResult>),
+    ///
+    /// The flag is set for messages which arrived from a transport.
+    SenderReq(Address, bool, Sender<NodeReplyResult>),
     /// Register a new router for a route id type
     Router(u8, Address, Sender<NodeReplyResult>),
 }
 
 impl NodeMessage {
-    /// Create a start worker message
-    pub fn start_worker(address: AddressSet, sender: Sender<RelayMessage>) -> Self {
-        Self::StartWorker(address, sender)
+    /// Create a start worker message and reply receiver
+    pub fn start_worker(
+        address: AddressSet,
+        sender: Sender<RelayMessage>,
+        task: Option<JoinHandle<()>>,
+    ) -> (Self, Receiver<NodeReplyResult>) {
+        let (tx, rx) = channel(1);
+        (Self::StartWorker(address, sender, task, tx), rx)
     }
 
     /// Create a list worker message and reply receiver
@@ -37,15 +62,28 @@ impl NodeMessage {
         (Self::StopWorker(address, tx), rx)
     }
 
-    /// Create a stop node message
-    pub fn stop_node() -> Self {
-        Self::StopNode
+    /// Create an add address message and reply receiver
+    pub fn add_address(primary: Address, address: Address) -> (Self, Receiver<NodeReplyResult>) {
+        let (tx, rx) = channel(1);
+        (Self::AddAddress(primary, address, tx), rx)
+    }
+
+    /// Create a remove address message and reply receiver
+    pub fn remove_address(primary: Address, address: Address) -> (Self, Receiver<NodeReplyResult>) {
+        let (tx, rx) = channel(1);
+        (Self::RemoveAddress(primary, address, tx), rx)
+    }
+
+    /// Create a stop node message and reply receiver
+    pub fn stop_node(timeout: Duration) -> (Self, Receiver<NodeReplyResult>) {
+        let (tx, rx) = channel(1);
+        (Self::StopNode(timeout, tx), rx)
     }
 
     /// Create a sender request message and reply receiver
-    pub fn sender_request(route: Address) -> (Self, Receiver<NodeReplyResult>) {
+    pub fn sender_request(route: Address, inbound: bool) -> (Self, Receiver<NodeReplyResult>) {
         let (tx, rx) = channel(1);
-        (Self::SenderReq(route, tx), rx)
+        (Self::SenderReq(route, inbound, tx), rx)
     }
 }
 
@@ -68,6 +106,8 @@ pub enum NodeReply {
         /// with router wrapping.
         wrap: bool,
     },
+    /// The outcome of stopping the node
+    Shutdown(ShutdownReport),
 }
 
 /// Failure states from a router command
@@ -75,6 +115,10 @@ pub enum NodeReply {
 pub enum NodeError {
     NoSuchWorker(Address),
     RouterExists,
+    NodeStopping,
+    AddressTaken(Address),
+    NoSuchAddress(Address),
+    PrimaryAddress(Address),
 }
 
 impl NodeReply {
@@ -90,6 +134,26 @@ impl NodeReply {
         Err(NodeError::RouterExists)
     }
 
+    pub fn address_taken(a: Address) -> NodeReplyResult {
+        Err(NodeError::AddressTaken(a))
+    }
+
+    pub fn no_such_address(a: Address) -> NodeReplyResult {
+        Err(NodeError::NoSuchAddress(a))
+    }
+
+    pub fn primary_address(a: Address) -> NodeReplyResult {
+        Err(NodeError::PrimaryAddress(a))
+    }
+
+    pub fn node_stopping() -> NodeReplyResult {
+        Err(NodeError::NodeStopping)
+    }
+
+    pub fn shutdown(report: ShutdownReport) -> NodeReplyResult {
+        Ok(Self::Shutdown(report))
+    }
+
     pub fn workers(v: Vec<Address>) -> NodeReplyResult {
         Ok(Self::Workers(v))
     }
@@ -112,6 +176,13 @@ impl NodeReply {
         }
     }
 
+    pub fn take_shutdown_report(self) -> Result<ShutdownReport, Error> {
+        match self {
+            Self::Shutdown(r) => Ok(r),
+            _ => Err(Error::InternalIOFailure),
+        }
+    }
+
     pub fn is_ok(self) -> Result<(), Error> {
         match self {
             Self::Ok => Ok(()),
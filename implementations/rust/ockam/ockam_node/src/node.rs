@@ -1,10 +1,17 @@
-use crate::{relay, Context, Executor, Mailbox, NodeMessage};
-use ockam_core::Address;
-use std::sync::Arc;
-use tokio::runtime::Runtime;
+use crate::{
+    codec::Codecs, error::Error, relay, resources::Resources, Context, EchoService, Executor,
+    Mailbox, NodeConfig, NodeMessage, RegistryService, WorkerConfig, ECHO_SERVICE_ADDRESS,
+    REGISTRY_SERVICE_ADDRESS,
+};
+use ockam_core::{Address, Codec, Message, Result};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+use tokio::runtime::{Builder, Runtime};
 use tokio::sync::mpsc::{channel, Sender};
 use tracing_subscriber::{filter::LevelFilter, fmt, EnvFilter};
 
+/// The default address of the root application context
+const DEFAULT_ROOT_ADDRESS: &str = "app";
+
 pub struct App;
 
 impl ockam_core::Worker for App {
@@ -12,41 +19,538 @@ impl ockam_core::Worker for App {
     type Message = (); // This message type is never used
 }
 
+/// Start a node with the default settings
+///
+/// Use [`NodeBuilder`] to change the root address, runtime or
+/// tracing setup.
 pub fn start_node() -> (Context, Executor) {
-    setup_tracing();
+    NodeBuilder::new()
+        .build()
+        .expect("Failed to start ockam node")
+}
+
+/// Starts a configured worker of one kind on a node
+type WorkerFactory = Box<dyn Fn(&Runtime, &Context, &WorkerConfig) -> Result<()>>;
+
+/// The worker kinds which may be started from a configuration file
+#[derive(Default)]
+struct WorkerKinds(BTreeMap<String, WorkerFactory>);
+
+impl std::fmt::Debug for WorkerKinds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Applies sections of the configuration file implemented by other crates
+type Setup = Box<dyn FnOnce(&Context, &NodeConfig) -> Result<()>>;
+
+/// The setups applied when the node is built, in order
+#[derive(Default)]
+struct Setups(Vec<Setup>);
+
+impl std::fmt::Debug for Setups {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} setups", self.0.len())
+    }
+}
+
+/// A builder to configure and start an ockam node
+///
+/// ```ignore
+/// let (ctx, mut executor) = NodeBuilder::new()
+///     .root_address("app")
+///     .worker_threads(2)
+///     .build()?;
+/// ```
+#[derive(Debug, Default)]
+pub struct NodeBuilder {
+    root_address: Option<Address>,
+    worker_threads: Option<usize>,
+    log_filter: Option<String>,
+    tracing: Option<bool>,
+    echo_service: Option<bool>,
+    registry_service: Option<bool>,
+    dead_letter_address: Option<Address>,
+    config_file: Option<PathBuf>,
+    worker_kinds: WorkerKinds,
+    setups: Setups,
+    codecs: Codecs,
+}
+
+impl NodeBuilder {
+    /// Create a new builder with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the address of the root application context
+    pub fn root_address<A: Into<Address>>(mut self, addr: A) -> Self {
+        self.root_address = Some(addr.into());
+        self
+    }
+
+    /// Set the number of runtime worker threads
+    pub fn worker_threads(mut self, threads: usize) -> Self {
+        self.worker_threads = Some(threads);
+        self
+    }
+
+    /// Set the log filter directives used if `OCKAM_LOG` is not set
+    pub fn log_filter<S: Into<String>>(mut self, filter: S) -> Self {
+        self.log_filter = Some(filter.into());
+        self
+    }
+
+    /// Enable or disable installing the global tracing subscriber
+    pub fn tracing(mut self, enabled: bool) -> Self {
+        self.tracing = Some(enabled);
+        self
+    }
+
+    /// Enable or disable the echo service used for route diagnostics
+    ///
+    /// The service is started at
+    /// [`ECHO_SERVICE_ADDRESS`](crate::ECHO_SERVICE_ADDRESS), and
+    /// is disabled by default.
+    pub fn echo_service(mut self, enabled: bool) -> Self {
+        self.echo_service = Some(enabled);
+        self
+    }
+
+    /// Enable or disable the registry service used for service
+    /// discovery
+    ///
+    /// The service is started at
+    /// [`REGISTRY_SERVICE_ADDRESS`](crate::REGISTRY_SERVICE_ADDRESS),
+    /// and is disabled by default.
+    pub fn registry_service(mut self, enabled: bool) -> Self {
+        self.registry_service = Some(enabled);
+        self
+    }
+
+    /// Set the address messages denied by worker access control
+    /// are reported to
+    ///
+    /// Denied messages are sent to this address as
+    /// [`DeadLetter`](crate::DeadLetter) reports.  By default they
+    /// are only logged.
+    pub fn dead_letter_address<A: Into<Address>>(mut self, addr: A) -> Self {
+        self.dead_letter_address = Some(addr.into());
+        self
+    }
+
+    /// Load additional node settings from a configuration file
+    ///
+    /// See [`NodeConfig`] for the file format.  Settings made on the
+    /// builder take precedence over the ones in the file.
+    pub fn config_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Allow configuration files to start workers of a kind
+    ///
+    /// For every entry of the `workers` list of the configuration
+    /// file with this `kind`, `factory` creates the worker from the
+    /// entry, and the node starts it at the configured address.
+    ///
+    /// ```ignore
+    /// let (ctx, mut executor) = NodeBuilder::new()
+    ///     .config_file("node.toml")
+    ///     .worker_kind("printer", |config| Ok(Printer::new(config.settings()?)))
+    ///     .build()?;
+    /// ```
+    pub fn worker_kind<K, W, F>(mut self, kind: K, factory: F) -> Self
+    where
+        K: Into<String>,
+        W: ockam_core::Worker<Context = Context>,
+        F: Fn(&WorkerConfig) -> Result<W> + 'static,
+    {
+        let start = move |rt: &Runtime, ctx: &Context, config: &WorkerConfig| {
+            let worker = factory(config)?;
+            let addr = Address::from(config.address.as_str());
+            rt.block_on(ctx.request_start_worker(addr.into(), worker))?;
+            Ok(())
+        };
+        self.worker_kinds.0.insert(kind.into(), Box::new(start));
+        self
+    }
+
+    /// Apply sections of the configuration file implemented by
+    /// other crates, e.g. the `tcp`, `vault` and `profile` sections
+    ///
+    /// `setup` is called with the root context and the loaded
+    /// configuration when the node is built, before the configured
+    /// workers are started.  It may share what it opens with the
+    /// workers via [`Context::set_resource`], and spawn tasks on the
+    /// root context, which run once the node is executed.  Setups are
+    /// applied in the order they were added.
+    ///
+    /// ```ignore
+    /// let (ctx, mut executor) = NodeBuilder::new()
+    ///     .config_file("node.toml")
+    ///     .setup(ockam::setup_vault_and_profile)
+    ///     .setup(ockam_transport_tcp::setup_tcp)
+    ///     .build()?;
+    /// ```
+    pub fn setup<F>(mut self, setup: F) -> Self
+    where
+        F: FnOnce(&Context, &NodeConfig) -> Result<()> + 'static,
+    {
+        self.setups.0.push(Box::new(setup));
+        self
+    }
+
+    /// Set the default codec for message payloads
+    ///
+    /// Nodes use [`Bincode`](ockam_core::Bincode) by default.
+    pub fn codec<C: Codec>(mut self, codec: C) -> Self {
+        self.codecs.set_default(Arc::new(codec));
+        self
+    }
+
+    /// Set the codec for payloads of message type `M`
+    ///
+    /// This takes precedence over the default codec of the node.
+    pub fn message_codec<M: Message, C: Codec>(mut self, codec: C) -> Self {
+        self.codecs.set::<M>(Arc::new(codec));
+        self
+    }
+
+    /// Build the node, returning its root context and executor
+    pub fn build(self) -> Result<(Context, Executor)> {
+        let config = match self.config_file {
+            Some(ref path) => NodeConfig::load(path)?,
+            None => NodeConfig::default(),
+        };
+
+        let settings = self.settings(&config);
+        if settings.tracing {
+            setup_tracing(settings.log_filter);
+        }
+
+        info!("Initializing ockam node");
+
+        let rt = build_runtime(settings.worker_threads)?;
+        let mut exe = Executor::with_runtime(rt);
+        let addr = settings.address;
+        let dead_letter = settings.dead_letter_address;
+
+        // The root application worker needs a mailbox and relay to accept
+        // messages from workers, and to buffer incoming transcoded data.
+        let ctx = root_app_context(
+            exe.runtime(),
+            &addr,
+            exe.sender(),
+            Arc::new(self.codecs),
+            dead_letter,
+            Arc::new(Resources::new(config)),
+        );
+
+        // Build a mailbox worker to buffer messages
+        let sender = relay::build_root::<App, _>(exe.runtime(), &ctx.mailbox);
+
+        // Register this mailbox handle with the executor
+        exe.initialize_system(addr, sender);
 
-    info!("Initializing ockam node");
+        if settings.echo_service {
+            // The router only runs once the node is executed
+            exe.runtime()
+                .block_on(ctx.request_start_worker(ECHO_SERVICE_ADDRESS.into(), EchoService))?;
+        }
 
-    let mut exe = Executor::new();
-    let addr = "app".into();
+        if settings.registry_service {
+            exe.runtime().block_on(ctx.request_start_worker(
+                REGISTRY_SERVICE_ADDRESS.into(),
+                RegistryService::default(),
+            ))?;
+        }
 
-    // The root application worker needs a mailbox and relay to accept
-    // messages from workers, and to buffer incoming transcoded data.
-    let ctx = root_app_context(exe.runtime(), &addr, exe.sender());
+        let config = ctx.node_config();
+        for setup in self.setups.0 {
+            setup(&ctx, config)?;
+        }
 
-    // Build a mailbox worker to buffer messages
-    let sender = relay::build_root::<App, _>(exe.runtime(), &ctx.mailbox);
+        for worker in &config.workers {
+            let start = self.worker_kinds.0.get(&worker.kind).ok_or_else(|| {
+                error!(
+                    "Unknown kind '{}' of worker {}",
+                    worker.kind, worker.address
+                );
+                Error::FailedLoadConfig
+            })?;
+            start(&exe.runtime(), &ctx, worker)?;
+        }
 
-    // Register this mailbox handle with the executor
-    exe.initialize_system("app", sender);
+        Ok((ctx, exe))
+    }
 
-    (ctx, exe)
+    /// Merge the settings made on this builder over the ones loaded
+    /// from a configuration file
+    fn settings(&self, config: &NodeConfig) -> Settings {
+        Settings {
+            address: self
+                .root_address
+                .clone()
+                .or_else(|| config.address.as_deref().map(Address::from))
+                .unwrap_or_else(|| DEFAULT_ROOT_ADDRESS.into()),
+            worker_threads: self.worker_threads.or(config.worker_threads),
+            log_filter: self
+                .log_filter
+                .clone()
+                .or_else(|| config.log_filter.clone()),
+            tracing: self.tracing.or(config.tracing).unwrap_or(true),
+            echo_service: self.echo_service.or(config.echo_service).unwrap_or(false),
+            registry_service: self
+                .registry_service
+                .or(config.registry_service)
+                .unwrap_or(false),
+            dead_letter_address: self
+                .dead_letter_address
+                .clone()
+                .or_else(|| config.dead_letter_address.as_deref().map(Address::from)),
+        }
+    }
+}
+
+/// The effective settings of a node being built
+#[derive(Debug)]
+struct Settings {
+    address: Address,
+    worker_threads: Option<usize>,
+    log_filter: Option<String>,
+    tracing: bool,
+    echo_service: bool,
+    registry_service: bool,
+    dead_letter_address: Option<Address>,
+}
+
+/// Build a multi-threaded runtime, optionally with a fixed thread count
+fn build_runtime(worker_threads: Option<usize>) -> Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    if let Some(threads) = worker_threads {
+        // The tokio builder panics on zero worker threads
+        if threads == 0 {
+            error!("Failed to build node runtime: worker_threads must be at least 1");
+            return Err(Error::FailedBuildRuntime.into());
+        }
+        builder.worker_threads(threads);
+    }
+
+    builder.enable_all().build().map_err(|e| {
+        error!("Failed to build node runtime: {}", e);
+        Error::FailedBuildRuntime.into()
+    })
 }
 
 /// Utility to setup tracing-subscriber from the environment
-fn setup_tracing() {
+fn setup_tracing(filter: Option<String>) {
     fmt()
         .with_env_filter(EnvFilter::try_from_env("OCKAM_LOG").unwrap_or_else(|_| {
-            EnvFilter::default()
-                .add_directive(LevelFilter::INFO.into())
-                .add_directive("ockam_node=info".parse().unwrap())
+            match filter {
+                Some(filter) => EnvFilter::new(filter),
+                None => EnvFilter::default()
+                    .add_directive(LevelFilter::INFO.into())
+                    .add_directive("ockam_node=info".parse().unwrap()),
+            }
         }))
-        .init();
+        // Several nodes may be started in the same process (e.g. in
+        // tests), only the first one installs the global subscriber
+        .try_init()
+        .ok();
 }
 
-fn root_app_context(rt: Arc<Runtime>, addr: &Address, tx: Sender<NodeMessage>) -> Context {
+fn root_app_context(
+    rt: Arc<Runtime>,
+    addr: &Address,
+    tx: Sender<NodeMessage>,
+    codecs: Arc<Codecs>,
+    dead_letter: Option<Address>,
+    resources: Arc<Resources>,
+) -> Context {
     let (mb_tx, mb_rx) = channel(32);
     let mb = Mailbox::new(mb_rx, mb_tx.clone());
-    let ctx = Context::new(rt, tx, addr.into(), mb);
+    let ctx = Context::new(rt, tx, addr.into(), mb, codecs, dead_letter, resources);
     ctx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_settings_take_precedence() {
+        let config = NodeConfig::parse(
+            r#"
+            address = "file"
+            worker_threads = 4
+            log_filter = "ockam_node=debug"
+            tracing = false
+            echo_service = true
+            dead_letter_address = "audit"
+            "#,
+        )
+        .unwrap();
+
+        let settings = NodeBuilder::new()
+            .root_address("builder")
+            .worker_threads(2)
+            .tracing(true)
+            .echo_service(false)
+            .settings(&config);
+
+        assert_eq!(settings.address, Address::from("builder"));
+        assert_eq!(settings.worker_threads, Some(2));
+        assert!(settings.tracing);
+        assert!(!settings.echo_service);
+
+        // Settings which are not made on the builder come from the file
+        assert_eq!(settings.log_filter.as_deref(), Some("ockam_node=debug"));
+        assert_eq!(settings.dead_letter_address, Some(Address::from("audit")));
+        assert!(!settings.registry_service);
+    }
+
+    #[test]
+    fn default_settings() {
+        let settings = NodeBuilder::new().settings(&NodeConfig::default());
+
+        assert_eq!(settings.address, Address::from(DEFAULT_ROOT_ADDRESS));
+        assert_eq!(settings.worker_threads, None);
+        assert_eq!(settings.log_filter, None);
+        assert!(settings.tracing);
+        assert!(!settings.echo_service);
+        assert!(!settings.registry_service);
+        assert_eq!(settings.dead_letter_address, None);
+    }
+
+    /// Write a configuration file for a test
+    fn config_file(name: &str, data: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ockam_node_{}.toml", name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    struct Printer;
+
+    impl ockam_core::Worker for Printer {
+        type Message = ();
+        type Context = Context;
+    }
+
+    #[test]
+    fn starts_configured_workers() {
+        let path = config_file(
+            "starts_configured_workers",
+            r#"
+            address = "file"
+            tracing = false
+
+            [[workers]]
+            address = "printer"
+            kind = "printer"
+            "#,
+        );
+
+        let (ctx, mut executor) = NodeBuilder::new()
+            .root_address("builder")
+            .config_file(&path)
+            .worker_kind("printer", |_| Ok(Printer))
+            .build()
+            .unwrap();
+
+        executor
+            .execute_test(
+                async move {
+                    assert_eq!(ctx.address(), Address::from("builder"));
+                    let workers = ctx.list_workers().await?;
+                    assert!(workers.contains(&Address::from("printer")));
+                    Ok(())
+                },
+                std::time::Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+
+    /// The profile path a setup applied from the configuration file
+    #[derive(Debug, PartialEq)]
+    struct ProfilePath(PathBuf);
+
+    #[test]
+    fn setups_share_configured_resources() {
+        let path = config_file(
+            "setups_share_configured_resources",
+            r#"
+            tracing = false
+
+            [profile]
+            path = "/var/lib/ockam/profile"
+            "#,
+        );
+
+        let (ctx, mut executor) = NodeBuilder::new()
+            .config_file(&path)
+            .setup(|ctx, config| {
+                let profile = config.profile.as_ref().ok_or(Error::FailedLoadConfig)?;
+                ctx.set_resource(ProfilePath(profile.path.clone()));
+                Ok(())
+            })
+            .build()
+            .unwrap();
+
+        executor
+            .execute_test(
+                async move {
+                    let profile = ctx.node_config().profile.as_ref().unwrap();
+                    assert_eq!(profile.path, PathBuf::from("/var/lib/ockam/profile"));
+
+                    // The resources of the node are shared with all of its workers
+                    let worker = ctx.new_context("worker").await?;
+                    assert_eq!(
+                        worker.resource::<ProfilePath>().as_deref(),
+                        Some(&ProfilePath("/var/lib/ockam/profile".into()))
+                    );
+                    assert!(worker.resource::<String>().is_none());
+                    Ok(())
+                },
+                std::time::Duration::from_secs(10),
+            )
+            .unwrap();
+
+        let failing = NodeBuilder::new()
+            .tracing(false)
+            .setup(|_, _| Err(Error::FailedLoadConfig.into()))
+            .build();
+        assert!(failing.is_err());
+    }
+
+    #[test]
+    fn unknown_worker_kind_is_an_error() {
+        let path = config_file(
+            "unknown_worker_kind_is_an_error",
+            r#"
+            tracing = false
+
+            [[workers]]
+            address = "printer"
+            kind = "printer"
+            "#,
+        );
+
+        assert!(NodeBuilder::new().config_file(&path).build().is_err());
+        assert!(NodeBuilder::new()
+            .config_file(path.with_extension("missing"))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn zero_worker_threads_is_an_error() {
+        assert!(build_runtime(Some(0)).is_err());
+        assert!(build_runtime(Some(1)).is_ok());
+
+        let config = NodeConfig::parse("worker_threads = 0").unwrap();
+        let settings = NodeBuilder::new().settings(&config);
+        assert!(build_runtime(settings.worker_threads).is_err());
+    }
+}
@@ -0,0 +1,469 @@
+//! Service discovery: a registry of named services
+//!
+//! Workers publish services with a name, metadata and a time to live
+//! to a [`RegistryService`], which other nodes query over routes via
+//! a [`RemoteRegistry`].  Entries expire unless they are published
+//! again before their time to live runs out, see
+//! [`ServiceDiscovery::keep_published`].
+//!
+//! [`LocalRegistry`] keeps entries in memory instead, and can stand
+//! in for a remote registry in tests.
+
+use crate::{diagnostics::unix_micros, error::Error, Context};
+use ockam_core::{
+    async_trait::async_trait, Address, Result, Route, Routed, Typed, TypedMessage, Worker,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+/// The address of the registry service started by a node
+pub const REGISTRY_SERVICE_ADDRESS: &str = "ockam.registry";
+
+/// How long to wait for a registry to answer a query
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before publishing a service again after a
+/// failure, doubled on every failure
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A service to publish
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceInfo {
+    /// The name of the service
+    pub name: String,
+    /// The address of the worker providing the service
+    pub address: Address,
+    /// Additional information about the service
+    pub metadata: BTreeMap<String, String>,
+    /// How long the service is listed after being published
+    pub ttl: Duration,
+}
+
+impl ServiceInfo {
+    /// Describe a service provided by the worker at `address`
+    pub fn new<S: Into<String>, A: Into<Address>>(name: S, address: A, ttl: Duration) -> Self {
+        Self {
+            name: name.into(),
+            address: address.into(),
+            metadata: BTreeMap::new(),
+            ttl,
+        }
+    }
+
+    /// Add metadata to the service
+    pub fn metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A service listed by a registry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServiceEntry {
+    /// The name of the service
+    pub name: String,
+    /// The route from the registry to the service
+    pub route: Route,
+    /// Additional information about the service
+    pub metadata: BTreeMap<String, String>,
+    /// When the entry expires, in microseconds since the UNIX epoch
+    pub expires: u64,
+}
+
+/// A request sent to a [`RegistryService`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryRequest {
+    /// Publish or refresh a service
+    Publish(ServiceInfo),
+    /// Remove a service before it expires
+    Unpublish {
+        /// The name of the service
+        name: String,
+        /// The address of the worker providing the service
+        address: Address,
+    },
+    /// List the services with a name
+    Query {
+        /// Identifies the query to the sender
+        id: u64,
+        /// The name of the service
+        name: String,
+    },
+}
+
+impl TypedMessage for RegistryRequest {
+    const TYPE_ID: &'static str = "ockam.registry.request";
+    const VERSION: u16 = 1;
+}
+
+/// The answer of a [`RegistryService`] to a query
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RegistryResponse {
+    /// The id of the query
+    pub id: u64,
+    /// The services found
+    pub services: Vec<ServiceEntry>,
+}
+
+impl TypedMessage for RegistryResponse {
+    const TYPE_ID: &'static str = "ockam.registry.response";
+    const VERSION: u16 = 1;
+}
+
+/// The entries of a registry
+#[derive(Debug, Default)]
+struct Entries(Vec<ServiceEntry>);
+
+impl Entries {
+    fn publish(&mut self, service: ServiceInfo, route: Route) {
+        self.unpublish(&service.name, &route);
+        self.0.push(ServiceEntry {
+            name: service.name,
+            route,
+            metadata: service.metadata,
+            expires: unix_micros().saturating_add(service.ttl.as_micros() as u64),
+        });
+    }
+
+    fn unpublish(&mut self, name: &str, route: &Route) {
+        self.0
+            .retain(|entry| !(entry.name == name && &entry.route == route));
+    }
+
+    fn query(&mut self, name: &str) -> Vec<ServiceEntry> {
+        let now = unix_micros();
+        self.0.retain(|entry| entry.expires > now);
+        self.0
+            .iter()
+            .filter(|entry| entry.name == name)
+            .cloned()
+            .collect()
+    }
+}
+
+/// The route from a registry to a service, given the return route of
+/// the request
+fn service_route(mut reply: Route, address: Address) -> Route {
+    reply.modify().pop_back().append(address);
+    reply
+}
+
+/// A worker keeping a registry of services
+///
+/// Nodes start this service at [`REGISTRY_SERVICE_ADDRESS`] if
+/// enabled with
+/// [`NodeBuilder::registry_service`](crate::NodeBuilder::registry_service).
+#[derive(Debug, Default)]
+pub struct RegistryService {
+    entries: Entries,
+}
+
+#[async_trait]
+impl Worker for RegistryService {
+    type Message = Typed<RegistryRequest>;
+    type Context = Context;
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Typed<RegistryRequest>>,
+    ) -> Result<()> {
+        let reply = msg.reply();
+        match msg.take().take() {
+            RegistryRequest::Publish(service) => {
+                let route = service_route(reply, service.address.clone());
+                debug!("Publishing service '{}' at {}", service.name, route);
+                self.entries.publish(service, route);
+            }
+            RegistryRequest::Unpublish { name, address } => {
+                let route = service_route(reply, address);
+                debug!("Unpublishing service '{}' at {}", name, route);
+                self.entries.unpublish(&name, &route);
+            }
+            RegistryRequest::Query { id, name } => {
+                let services = self.entries.query(&name);
+                ctx.send_message(reply, Typed::new(RegistryResponse { id, services }))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An API to publish and find services
+#[async_trait]
+pub trait ServiceDiscovery: Send + Sync {
+    /// Publish or refresh a service
+    async fn publish(&self, ctx: &Context, service: ServiceInfo) -> Result<()>;
+
+    /// Remove a service before it expires
+    async fn unpublish(&self, ctx: &Context, name: &str, address: &Address) -> Result<()>;
+
+    /// List the services with a name
+    async fn query(&self, ctx: &mut Context, name: &str) -> Result<Vec<ServiceEntry>>;
+
+    /// Publish a service, and refresh it until the worker stops
+    ///
+    /// The service is published again every half of its time to
+    /// live, from a task owned by the worker of `ctx`.  Failed
+    /// publications are retried with an exponential backoff.  Fails
+    /// if the time to live is too short to be refreshed.
+    fn keep_published(&self, ctx: &Context, service: ServiceInfo) -> Result<()>
+    where
+        Self: Clone + Sized + 'static,
+    {
+        let refresh = service.ttl / 2;
+        if refresh == Duration::from_secs(0) {
+            return Err(Error::InvalidServiceTtl.into());
+        }
+
+        let registry = self.clone();
+        ctx.spawn(move |ctx| async move {
+            let mut backoff = RETRY_DELAY.min(refresh);
+            loop {
+                let delay = match registry.publish(&ctx, service.clone()).await {
+                    Ok(()) => {
+                        backoff = RETRY_DELAY.min(refresh);
+                        refresh
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to publish service '{}', retrying in {:?}: {}",
+                            service.name, backoff, e
+                        );
+                        let delay = backoff;
+                        backoff = (backoff * 2).min(refresh);
+                        delay
+                    }
+                };
+                tokio::time::sleep(delay).await;
+            }
+        });
+        Ok(())
+    }
+}
+
+/// A client of a [`RegistryService`] reachable via a route
+#[derive(Debug, Clone)]
+pub struct RemoteRegistry {
+    route: Route,
+}
+
+impl RemoteRegistry {
+    /// Create a client for the registry at `route`
+    pub fn new<R: Into<Route>>(route: R) -> Self {
+        Self {
+            route: route.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl ServiceDiscovery for RemoteRegistry {
+    async fn publish(&self, ctx: &Context, service: ServiceInfo) -> Result<()> {
+        let request = RegistryRequest::Publish(service);
+        ctx.send_message(self.route.clone(), Typed::new(request))
+            .await
+    }
+
+    async fn unpublish(&self, ctx: &Context, name: &str, address: &Address) -> Result<()> {
+        let request = RegistryRequest::Unpublish {
+            name: name.into(),
+            address: address.clone(),
+        };
+        ctx.send_message(self.route.clone(), Typed::new(request))
+            .await
+    }
+
+    async fn query(&self, ctx: &mut Context, name: &str) -> Result<Vec<ServiceEntry>> {
+        static QUERY_ID: AtomicU64 = AtomicU64::new(0);
+        let id = QUERY_ID.fetch_add(1, Ordering::Relaxed);
+
+        let request = RegistryRequest::Query {
+            id,
+            name: name.into(),
+        };
+        ctx.send_message(self.route.clone(), Typed::new(request))
+            .await?;
+
+        let response = tokio::time::timeout(
+            QUERY_TIMEOUT,
+            ctx.receive_match(|r: &Typed<RegistryResponse>| r.id == id),
+        )
+        .await
+        .map_err(|_| Error::ExecutionTimeout)??
+        .take()
+        .take();
+
+        Ok(response.services)
+    }
+}
+
+/// A registry kept in memory, without a registry service
+///
+/// Clones share the same entries.  Services are listed with a route
+/// to their local address.
+#[derive(Debug, Clone, Default)]
+pub struct LocalRegistry {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl LocalRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ServiceDiscovery for LocalRegistry {
+    async fn publish(&self, _: &Context, service: ServiceInfo) -> Result<()> {
+        let route = service.address.clone().into();
+        self.entries.lock().unwrap().publish(service, route);
+        Ok(())
+    }
+
+    async fn unpublish(&self, _: &Context, name: &str, address: &Address) -> Result<()> {
+        let route = address.clone().into();
+        self.entries.lock().unwrap().unpublish(name, &route);
+        Ok(())
+    }
+
+    async fn query(&self, _: &mut Context, name: &str) -> Result<Vec<ServiceEntry>> {
+        Ok(self.entries.lock().unwrap().query(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeBuilder;
+    use std::{future::Future, sync::atomic::AtomicUsize};
+    use tokio::time::sleep;
+
+    fn run<F>(f: impl FnOnce(Context) -> F)
+    where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let (ctx, mut executor) = NodeBuilder::new().tracing(false).build().unwrap();
+        executor
+            .execute_test(f(ctx), Duration::from_secs(10))
+            .unwrap();
+    }
+
+    /// A registry failing the given number of publications first
+    #[derive(Clone)]
+    struct Flaky {
+        failures: Arc<AtomicUsize>,
+        inner: LocalRegistry,
+    }
+
+    #[async_trait]
+    impl ServiceDiscovery for Flaky {
+        async fn publish(&self, ctx: &Context, service: ServiceInfo) -> Result<()> {
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if fail {
+                return Err(Error::InternalIOFailure.into());
+            }
+            self.inner.publish(ctx, service).await
+        }
+
+        async fn unpublish(&self, ctx: &Context, name: &str, address: &Address) -> Result<()> {
+            self.inner.unpublish(ctx, name, address).await
+        }
+
+        async fn query(&self, ctx: &mut Context, name: &str) -> Result<Vec<ServiceEntry>> {
+            self.inner.query(ctx, name).await
+        }
+    }
+
+    #[test]
+    fn entries_expire() {
+        let mut entries = Entries::default();
+        let service = ServiceInfo::new("echo", "echoer", Duration::from_millis(1));
+        entries.publish(service, "echoer".into());
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(entries.query("echo").is_empty());
+    }
+
+    #[test]
+    fn entries_are_replaced() {
+        let mut entries = Entries::default();
+        let ttl = Duration::from_secs(10);
+        entries.publish(ServiceInfo::new("echo", "a", ttl), "a".into());
+        entries.publish(ServiceInfo::new("echo", "b", ttl), "b".into());
+        entries.publish(
+            ServiceInfo::new("echo", "a", ttl).metadata("v", "2"),
+            "a".into(),
+        );
+
+        let found = entries.query("echo");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].route, "a".into());
+        assert_eq!(found[1].metadata["v"], "2");
+        assert!(entries.query("other").is_empty());
+    }
+
+    #[test]
+    fn local_registry() {
+        run(|mut ctx| async move {
+            let registry = LocalRegistry::new();
+            let service = ServiceInfo::new("echo", "echoer", Duration::from_secs(10));
+            registry.publish(&ctx, service).await?;
+
+            // Clones share the entries
+            let found = registry.clone().query(&mut ctx, "echo").await?;
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].route, "echoer".into());
+
+            registry.unpublish(&ctx, "echo", &"echoer".into()).await?;
+            assert!(registry.query(&mut ctx, "echo").await?.is_empty());
+            ctx.stop().await
+        });
+    }
+
+    #[test]
+    fn keep_published_rejects_zero_ttl() {
+        run(|ctx| async move {
+            let service = ServiceInfo::new("echo", "echoer", Duration::from_secs(0));
+            let err = LocalRegistry::new()
+                .keep_published(&ctx, service)
+                .unwrap_err();
+            assert_eq!(
+                err.code(),
+                Error::DOMAIN_CODE + Error::InvalidServiceTtl as u32
+            );
+            ctx.stop().await
+        });
+    }
+
+    #[test]
+    fn keep_published_refreshes_and_retries() {
+        run(|mut ctx| async move {
+            let registry = Flaky {
+                failures: Arc::new(AtomicUsize::new(2)),
+                inner: LocalRegistry::new(),
+            };
+            let service = ServiceInfo::new("echo", "echoer", Duration::from_millis(200));
+            registry.keep_published(&ctx, service)?;
+
+            // Published after two retries, then refreshed past its
+            // time to live
+            sleep(Duration::from_millis(600)).await;
+            assert_eq!(registry.failures.load(Ordering::SeqCst), 0);
+            assert_eq!(registry.query(&mut ctx, "echo").await?.len(), 1);
+            ctx.stop().await
+        });
+    }
+}
@@ -15,19 +15,24 @@
 //! The `Relay` is then responsible for turning the message back into
 //! a type and notifying the companion actor.
 
-use crate::{Context, Mailbox};
+use crate::{dead_letter, diagnostics, Context, DeadLetter, Mailbox};
 use ockam_core::{
-    Address, Message, Result, Route, Routed, RouterMessage, TransportMessage, Worker,
+    AccessControl, Address, EnvelopeHeader, Message, Result, Routed, RouterMessage,
+    TransportMessage, Worker,
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{any::Any, marker::PhantomData, sync::Arc};
 use tokio::runtime::Runtime;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::sync::{
+    mpsc::{channel, Receiver, Sender},
+    oneshot,
+};
+use tokio::task::JoinHandle;
 
 /// A message addressed to a relay
 #[derive(Debug)]
 pub struct RelayMessage {
-    addr: Address,
-    data: RelayPayload,
+    pub(crate) addr: Address,
+    pub(crate) data: RelayPayload,
 }
 
 impl RelayMessage {
@@ -40,13 +45,23 @@ impl RelayMessage {
     }
 
     /// Construct a message addressed to an middleware router
+    ///
+    /// The router relay wraps it into a [`RouterMessage::Route`].
     #[inline]
     pub fn pre_router(addr: Address, data: TransportMessage) -> Self {
-        let route = data.return_.clone();
-        let r_msg = RouterMessage::Route(data);
         Self {
             addr,
-            data: RelayPayload::PreRouter(r_msg.encode().unwrap(), route),
+            data: RelayPayload::PreRouter(data),
+        }
+    }
+
+    /// Construct a signal asking a worker to stop
+    ///
+    /// The worker acknowledges via `ack` once it has stopped.
+    pub(crate) fn stop(addr: Address, ack: oneshot::Sender<()>) -> Self {
+        Self {
+            addr,
+            data: RelayPayload::Stop(ack),
         }
     }
 
@@ -66,7 +81,8 @@ impl RelayMessage {
 #[derive(Debug)]
 pub enum RelayPayload {
     Direct(TransportMessage),
-    PreRouter(Vec<u8>, Route),
+    PreRouter(TransportMessage),
+    Stop(oneshot::Sender<()>),
 }
 
 pub struct Relay<W, M>
@@ -94,58 +110,132 @@ where
 
     /// Convenience function to handle an incoming direct message
     #[inline]
-    fn handle_direct(&mut self, msg: TransportMessage) -> Result<(M, Route)> {
-        M::decode(&msg.payload)
+    fn handle_direct(&mut self, msg: TransportMessage) -> Result<Routed<M>> {
+        self.ctx
+            .codec::<M>()
+            .decode_message::<M>(&msg.payload)
             .map_err(|e| {
-                error!(
-                    "Failed to decode message payload for worker {}",
-                    self.ctx.address()
-                );
-                e.into()
+                // Typed payloads tell us which message was received
+                match EnvelopeHeader::peek(&msg.payload) {
+                    Some(header) => error!(
+                        "Failed to decode message payload {} from {} for worker {}: {}",
+                        header,
+                        msg.return_,
+                        self.ctx.address(),
+                        e
+                    ),
+                    None => error!(
+                        "Failed to decode message payload from {} for worker {}: {}",
+                        msg.return_,
+                        self.ctx.address(),
+                        e
+                    ),
+                }
+                e
+            })
+            .map(|m| {
+                let mut local = msg.local;
+                local.arrival.get_or_insert_with(diagnostics::unix_micros);
+                Routed::new(m, msg.return_).with_local(local)
             })
-            .map(|m| (m, msg.return_.clone()))
     }
 
+    /// Wrap a message addressed to a router into a `RouterMessage`
+    ///
+    /// Routers accepting `RouterMessage` get the transport message as
+    /// is, with its local information.  Other message types are
+    /// transcoded via the codecs of the node.
     #[inline]
-    fn handle_pre_router(&mut self, msg: Vec<u8>) -> Result<M> {
-        M::decode(&msg).map_err(|e| {
-            error!(
-                "Failed to decode wrapped router message for worker {}.  \
+    fn handle_pre_router(&mut self, mut msg: TransportMessage) -> Result<Routed<M>> {
+        msg.local
+            .arrival
+            .get_or_insert_with(diagnostics::unix_micros);
+        let route = msg.return_.clone();
+        let local = msg.local.clone();
+
+        let mut r_msg = Some(RouterMessage::Route(msg));
+        let any: &mut dyn Any = &mut r_msg;
+        let m = match any.downcast_mut::<Option<M>>() {
+            Some(m) => m.take().unwrap(),
+            None => self
+                .ctx
+                .codec::<RouterMessage>()
+                .encode_message(r_msg.as_ref().unwrap())
+                .and_then(|data| self.ctx.codec::<M>().decode_message::<M>(&data))
+                .inspect_err(|_| {
+                    error!(
+                        "Failed to decode wrapped router message for worker {}.  \
 Is your router accepting the correct message type? (ockam_core::RouterMessage)",
-                self.ctx.address()
-            );
-            e.into()
+                        self.ctx.address()
+                    )
+                })?,
+        };
+        Ok(Routed::new(m, route).with_local(local))
+    }
+
+    /// Check a message against the worker's access control policy
+    ///
+    /// Returns a report for messages which must be dropped.
+    fn authorize(
+        policy: &dyn AccessControl,
+        addr: &Address,
+        data: &RelayPayload,
+    ) -> Option<DeadLetter> {
+        let msg = match data {
+            RelayPayload::Direct(msg) | RelayPayload::PreRouter(msg) => msg,
+            RelayPayload::Stop(_) => return None,
+        };
+
+        if policy.is_authorized(&msg.return_, &msg.local) {
+            return None;
+        }
+
+        Some(DeadLetter {
+            address: addr.clone(),
+            return_route: msg.return_.clone(),
+            payload: msg.payload.clone(),
         })
     }
 
     async fn run(mut self) {
         self.worker.initialize(&mut self.ctx).await.unwrap();
+        let policy = self.worker.access_control();
 
         while let Some(RelayMessage { addr, data }) = self.ctx.mailbox.next().await {
             // Set the message address for this transaction chain
-            self.ctx.message_address(addr);
+            self.ctx.message_address(addr.clone());
+
+            // Drop messages the worker does not accept
+            if let Some(letter) = Self::authorize(policy.as_ref(), &addr, &data) {
+                let kind = match data {
+                    RelayPayload::Direct(msg) | RelayPayload::PreRouter(msg) => msg.kind,
+                    RelayPayload::Stop(_) => unreachable!(),
+                };
+                dead_letter::report(&self.ctx, letter, kind).await;
+                continue;
+            }
 
             // Extract the message type based on the relay message
             // wrap state.  Messages addressed to a router will be of
             // type `RouterMessage`, while generic userspace workers
             // can provide any type they want.
-            let (msg, route) = match (|data| -> Result<(M, Route)> {
+            //
+            // The user message is wrapped in a `Routed` to provide
+            // return route and local information via a composition
+            // side-channel
+            let routed = match (|data| -> Result<Routed<M>> {
                 Ok(match data {
                     RelayPayload::Direct(trans_msg) => self.handle_direct(trans_msg)?,
-                    RelayPayload::PreRouter(enc_msg, route) => {
-                        self.handle_pre_router(enc_msg).map(|m| (m, route))?
-                    }
+                    RelayPayload::PreRouter(trans_msg) => self.handle_pre_router(trans_msg)?,
+                    // Stop signals are consumed by the mailbox
+                    RelayPayload::Stop(_) => unreachable!(),
                 })
             })(data)
             {
-                Ok((msg, route)) => (msg, route),
+                Ok(routed) => routed,
                 Err(_) => continue, // Handler functions must log
             };
 
-            // Wrap the user message in a `Routed` to provide return
-            // route information via a composition side-channel
-            let routed = Routed::new(msg, route);
-
             // Call the worker handle function
             self.worker
                 .handle_message(&mut self.ctx, routed)
@@ -156,20 +246,38 @@ Is your router accepting the correct message type? (ockam_core::RouterMessage)",
             self.ctx.message_address(None);
         }
 
-        self.worker.shutdown(&mut self.ctx).unwrap();
+        if let Err(e) = self.worker.stop(&mut self.ctx).await {
+            error!("Failed to stop worker {}: {}", self.ctx.address(), e);
+        }
+        self.ctx.cancel_tasks();
+
+        // Let the node know that this worker has stopped
+        if let Some(ack) = self.ctx.mailbox.take_stop() {
+            let _ = ack.send(());
+        }
     }
 
     /// Run the inner worker and restart it if errors occurs
     async fn run_mailbox(mut rx: Receiver<RelayMessage>, mb_tx: Sender<RelayMessage>) {
-        // Relay messages into the worker mailbox
+        // Relay messages into the worker mailbox, until the worker
+        // is gone
         while let Some(enc) = rx.recv().await {
-            mb_tx.send(enc).await.unwrap();
+            if mb_tx.send(enc).await.is_err() {
+                break;
+            }
         }
     }
 }
 
-/// Build and spawn a new worker relay, returning a send handle to it
-pub(crate) fn build<W, M>(rt: &Runtime, worker: W, ctx: Context) -> Sender<RelayMessage>
+/// Build and spawn a new worker relay
+///
+/// Returns a send handle to the relay, and the task running the
+/// worker.
+pub(crate) fn build<W, M>(
+    rt: &Runtime,
+    worker: W,
+    ctx: Context,
+) -> (Sender<RelayMessage>, JoinHandle<()>)
 where
     W: Worker<Context = Context, Message = M>,
     M: Message + Send + 'static,
@@ -179,7 +287,38 @@ where
     let relay = Relay::<W, M>::new(worker, ctx);
 
     rt.spawn(Relay::<W, M>::run_mailbox(rx, mb_tx));
-    rt.spawn(relay.run());
+    let task = rt.spawn(relay.run());
+    (tx, task)
+}
+
+/// Build and spawn the relay of a context which has no worker
+///
+/// Messages are buffered in the mailbox until they are received, like
+/// for the root application.  Stop signals are acknowledged right away
+/// since there is no worker to stop, and the relay ends once the
+/// context is dropped.
+pub(crate) fn build_detached(rt: &Runtime, mailbox: &Mailbox) -> Sender<RelayMessage> {
+    let (tx, mut rx) = channel(32);
+    let mb_tx = mailbox.sender();
+
+    rt.spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                RelayMessage {
+                    data: RelayPayload::Stop(ack),
+                    ..
+                } => {
+                    let _ = ack.send(());
+                    break;
+                }
+                msg => {
+                    if mb_tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
     tx
 }
 
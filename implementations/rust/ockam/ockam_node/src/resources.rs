@@ -0,0 +1,40 @@
+//! Values shared by the workers of a node
+
+use crate::NodeConfig;
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+/// The configuration a node was started with, and the values its
+/// workers share, by type
+#[derive(Debug, Default)]
+pub(crate) struct Resources {
+    config: NodeConfig,
+    values: RwLock<BTreeMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Resources {
+    pub(crate) fn new(config: NodeConfig) -> Self {
+        Self {
+            config,
+            values: Default::default(),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &NodeConfig {
+        &self.config
+    }
+
+    pub(crate) fn set<T: Any + Send + Sync>(&self, value: T) {
+        let mut values = self.values.write().unwrap();
+        values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub(crate) fn get<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        let values = self.values.read().unwrap();
+        let value = values.get(&TypeId::of::<T>())?.clone();
+        value.downcast().ok()
+    }
+}
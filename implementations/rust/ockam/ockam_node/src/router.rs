@@ -1,7 +1,15 @@
-use crate::{error::Error, relay::RelayMessage, NodeMessage, NodeReply, NodeReplyResult};
+use crate::{
+    error::Error,
+    relay::RelayMessage,
+    shutdown::{self, Shutdown},
+    NodeMessage, NodeReply, NodeReplyResult,
+};
 use ockam_core::{Address, AddressSet, Result};
 use std::collections::BTreeMap;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::{
+    sync::mpsc::{channel, Receiver, Sender},
+    task::JoinHandle,
+};
 
 /// A combined address type and local worker router
 ///
@@ -22,6 +30,12 @@ pub struct Router {
     /// secondary address senders also need to be cleared for the
     /// worker to shut down
     addr_map: BTreeMap<Address, AddressSet>,
+    /// Primary addresses of workers, in the order they were started
+    started: Vec<Address>,
+    /// Tasks running workers, by primary address
+    tasks: BTreeMap<Address, JoinHandle<()>>,
+    /// Set while the node is shutting down
+    shutdown: Option<Shutdown>,
     /// Externally registered router components
     external: BTreeMap<u8, Address>,
     /// Receiver for messages from node
@@ -49,6 +63,9 @@ impl Router {
         Self {
             internal: BTreeMap::new(),
             addr_map: BTreeMap::new(),
+            started: Vec::new(),
+            tasks: BTreeMap::new(),
+            shutdown: None,
             external: BTreeMap::new(),
             receiver,
             sender,
@@ -56,7 +73,8 @@ impl Router {
     }
 
     pub fn init(&mut self, addr: Address, mb: Sender<RelayMessage>) {
-        self.internal.insert(addr, mb);
+        self.internal.insert(addr.clone(), mb);
+        self.addr_map.insert(addr.clone(), addr.into());
     }
 
     pub fn sender(&self) -> Sender<NodeMessage> {
@@ -84,13 +102,36 @@ impl Router {
                     .map_err(|_| Error::InternalIOFailure)?,
 
                 // Basic worker control
-                StartWorker(addr, sender) => self.start_worker(addr, sender).await?,
+                StartWorker(addrs, sender, task, ref mut reply) => {
+                    self.start_worker(addrs, sender, task, reply).await?
+                }
                 StopWorker(ref addr, ref mut reply) => self.stop_worker(addr, reply).await?,
+                AddAddress(ref primary, addr, ref mut reply) => {
+                    self.add_address(primary, addr, reply).await?
+                }
+                RemoveAddress(ref primary, ref addr, ref mut reply) => {
+                    self.remove_address(primary, addr, reply).await?
+                }
 
                 // Basic node control
-                StopNode => {
-                    self.internal.clear();
-                    break;
+                StopNode(timeout, reply) => match self.shutdown {
+                    Some(ref mut shutdown) => shutdown.add_reply(reply),
+                    None => {
+                        info!("Stopping node, waiting up to {:?} for workers", timeout);
+                        let started = std::mem::take(&mut self.started);
+                        self.shutdown = Some(Shutdown::new(timeout, started, reply));
+                        if self.stop_next() {
+                            break;
+                        }
+                    }
+                },
+                StopAck(addr, stopped) => {
+                    if let Some(ref mut shutdown) = self.shutdown {
+                        shutdown.record(addr, stopped);
+                    }
+                    if self.stop_next() {
+                        break;
+                    }
                 }
                 ListWorkers(sender) => sender
                     .send(NodeReply::workers(self.internal.keys().cloned().collect()))
@@ -98,7 +139,18 @@ impl Router {
                     .map_err(|_| Error::InternalIOFailure)?,
 
                 // Handle route/ sender requests
-                SenderReq(ref addr, ref mut reply) => match determine_type(addr) {
+                // Don't accept new external messages while stopping,
+                // whether they leave the node or arrived from a transport
+                SenderReq(ref addr, inbound, ref mut reply)
+                    if self.shutdown.is_some()
+                        && (inbound || matches!(determine_type(addr), RouteType::External(_))) =>
+                {
+                    reply
+                        .send(NodeReply::node_stopping())
+                        .await
+                        .map_err(|_| Error::InternalIOFailure)?
+                }
+                SenderReq(ref addr, _, ref mut reply) => match determine_type(addr) {
                     RouteType::Internal(ref addr) => self.resolve(addr, reply, false).await?,
                     RouteType::External(tt) => {
                         let addr = self.router_addr(tt)?;
@@ -108,22 +160,152 @@ impl Router {
             }
         }
 
+        self.internal.clear();
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.finish().await;
+        }
+
         Ok(())
     }
 
+    /// Stop the next worker during node shutdown
+    ///
+    /// Returns `true` once all workers have stopped.
+    fn stop_next(&mut self) -> bool {
+        let addr = match self.shutdown.as_mut().map(Shutdown::next) {
+            Some(Some(addr)) => addr,
+            Some(None) => return true,
+            None => return false,
+        };
+
+        let relay = self.remove_worker(&addr);
+        let task = self.tasks.remove(&addr);
+        let shutdown = self.shutdown.as_mut().unwrap();
+        match relay {
+            Some(relay) => {
+                debug!("Stopping worker {}", addr);
+                shutdown.stop_worker(addr, relay, task, self.sender.clone());
+                false
+            }
+            // Already stopped
+            None => {
+                shutdown.record(addr, true);
+                self.stop_next()
+            }
+        }
+    }
+
+    /// Remove all addresses of a worker, returning its relay sender
+    fn remove_worker(&mut self, addr: &Address) -> Option<Sender<RelayMessage>> {
+        self.started.retain(|a| a != addr);
+        let addrs = self.addr_map.remove(addr)?;
+        addrs
+            .iter()
+            .fold(None, |relay, addr| self.internal.remove(addr).or(relay))
+    }
+
     async fn start_worker(
         &mut self,
         addrs: AddressSet,
         sender: Sender<RelayMessage>,
+        task: Option<JoinHandle<()>>,
+        reply: &mut Sender<NodeReplyResult>,
     ) -> Result<()> {
-        trace!("Starting new worker '{}'", addrs.first());
-        addrs.iter().for_each(|addr| {
-            self.internal.insert(addr.clone(), sender.clone());
-        });
-        self.addr_map.insert(addrs.first(), addrs);
+        let taken = addrs.iter().find(|addr| self.internal.contains_key(addr));
+        let result = match taken {
+            _ if self.shutdown.is_some() => {
+                warn!("Not starting worker '{}', node is stopping", addrs.first());
+                NodeReply::node_stopping()
+            }
+            Some(addr) => {
+                warn!(
+                    "Not starting worker '{}', address {} is taken",
+                    addrs.first(),
+                    addr
+                );
+                NodeReply::address_taken(addr.clone())
+            }
+            None => {
+                trace!("Starting new worker '{}'", addrs.first());
+                self.started.push(addrs.first());
+                addrs.iter().for_each(|addr| {
+                    self.internal.insert(addr.clone(), sender.clone());
+                });
+                self.addr_map.insert(addrs.first(), addrs.clone());
+                if let Some(task) = task {
+                    self.tasks.insert(addrs.first(), task);
+                }
+                NodeReply::ok()
+            }
+        };
+
+        // The relay of a rejected worker was already started
+        if result.is_err() {
+            tokio::spawn(shutdown::stop_relay(addrs.first(), sender));
+        }
+
+        // Workers started before the router runs don't wait for a reply
+        let _ = reply.send(result).await;
         Ok(())
     }
 
+    async fn add_address(
+        &mut self,
+        primary: &Address,
+        addr: Address,
+        reply: &mut Sender<NodeReplyResult>,
+    ) -> Result<()> {
+        let sender = self.internal.get(primary).cloned();
+        let result = match (sender, self.addr_map.get_mut(primary)) {
+            _ if self.internal.contains_key(&addr) => NodeReply::address_taken(addr),
+            (Some(sender), Some(addrs)) => {
+                trace!("Adding address '{}' to worker '{}'", addr, primary);
+                addrs.push(addr.clone());
+                self.internal.insert(addr, sender);
+                NodeReply::ok()
+            }
+            _ => NodeReply::no_such_worker(primary.clone()),
+        };
+
+        reply
+            .send(result)
+            .await
+            .map_err(|_| Error::InternalIOFailure.into())
+    }
+
+    async fn remove_address(
+        &mut self,
+        primary: &Address,
+        addr: &Address,
+        reply: &mut Sender<NodeReplyResult>,
+    ) -> Result<()> {
+        if addr == primary {
+            return reply
+                .send(NodeReply::primary_address(addr.clone()))
+                .await
+                .map_err(|_| Error::InternalIOFailure.into());
+        }
+
+        let removed = self
+            .addr_map
+            .get_mut(primary)
+            .map(|addrs| addrs.remove(addr));
+        let result = match removed {
+            Some(true) => {
+                trace!("Removing address '{}' from worker '{}'", addr, primary);
+                self.internal.remove(addr);
+                NodeReply::ok()
+            }
+            Some(false) => NodeReply::no_such_address(addr.clone()),
+            None => NodeReply::no_such_worker(primary.clone()),
+        };
+
+        reply
+            .send(result)
+            .await
+            .map_err(|_| Error::InternalIOFailure.into())
+    }
+
     async fn stop_worker(
         &mut self,
         addr: &Address,
@@ -131,16 +313,13 @@ impl Router {
     ) -> Result<()> {
         trace!("Stopping worker '{}'", addr);
 
-        let addrs = self.addr_map.remove(addr).unwrap();
-
-        match addrs.iter().fold(Some(()), |opt, addr| {
-            match (opt, self.internal.remove(addr)) {
-                (Some(_), Some(_)) => Some(()),
-                (Some(_), None) => None,
-                (None, _) => None,
+        self.tasks.remove(addr);
+        match self.remove_worker(addr) {
+            // The worker handles its queued messages before stopping
+            Some(relay) => {
+                tokio::spawn(shutdown::stop_relay(addr.clone(), relay));
+                reply.send(NodeReply::ok())
             }
-        }) {
-            Some(_) => reply.send(NodeReply::ok()),
             None => reply.send(NodeReply::no_such_worker(addr.clone())),
         }
         .await
@@ -0,0 +1,141 @@
+//! Graceful node shutdown
+//!
+//! When a node stops, the router stops accepting new workers,
+//! messages for external routers and messages arriving from
+//! transports, then stops workers one by one in reverse start order.
+//! Each worker first handles the messages already in its mailbox,
+//! then runs its shutdown hook.  Workers which have not stopped by
+//! the shutdown deadline are aborted and reported as failed, and so
+//! are all workers after them.
+
+use crate::{relay::RelayMessage, NodeMessage, NodeReply, NodeReplyResult};
+use ockam_core::Address;
+use std::{fmt, time::Duration};
+use tokio::{
+    sync::{mpsc::Sender, oneshot},
+    task::JoinHandle,
+    time::{timeout_at, Instant},
+};
+
+/// How long [`Context::stop`](crate::Context::stop) waits for
+/// workers to stop
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of stopping a node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Workers which stopped, in the order they stopped
+    pub stopped: Vec<Address>,
+    /// Workers which did not stop before the deadline
+    pub failed: Vec<Address>,
+}
+
+impl ShutdownReport {
+    /// Check if all workers stopped in time
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} workers stopped", self.stopped.len())?;
+        if !self.failed.is_empty() {
+            write!(f, ", failed to stop:")?;
+            for addr in &self.failed {
+                write!(f, " {}", addr)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The state of a node shutdown in progress
+pub(crate) struct Shutdown {
+    deadline: Instant,
+    /// Workers left to stop, the next one last
+    pending: Vec<Address>,
+    report: ShutdownReport,
+    replies: Vec<Sender<NodeReplyResult>>,
+}
+
+impl Shutdown {
+    /// Start stopping the given workers, in reverse order
+    pub(crate) fn new(
+        timeout: Duration,
+        started: Vec<Address>,
+        reply: Sender<NodeReplyResult>,
+    ) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            pending: started,
+            report: ShutdownReport::default(),
+            replies: vec![reply],
+        }
+    }
+
+    /// Also send the report to another stop request
+    pub(crate) fn add_reply(&mut self, reply: Sender<NodeReplyResult>) {
+        self.replies.push(reply);
+    }
+
+    /// The next worker to stop, if any
+    pub(crate) fn next(&mut self) -> Option<Address> {
+        self.pending.pop()
+    }
+
+    /// Record whether a worker stopped in time
+    pub(crate) fn record(&mut self, addr: Address, stopped: bool) {
+        if stopped {
+            self.report.stopped.push(addr);
+        } else {
+            warn!("Worker {} did not stop before the shutdown deadline", addr);
+            self.report.failed.push(addr);
+        }
+    }
+
+    /// Send the report to everyone waiting for the node to stop
+    pub(crate) async fn finish(self) {
+        info!("Node shutdown complete: {}", self.report);
+        for reply in self.replies {
+            // Callers of `Context::stop` don't wait for the report
+            let _ = reply.send(NodeReply::shutdown(self.report.clone())).await;
+        }
+    }
+
+    /// Ask a worker to stop, reporting back to the router
+    ///
+    /// This runs in its own task, so the router can keep serving
+    /// the workers which are still draining their mailboxes.  The
+    /// worker task is aborted if it misses the deadline.
+    pub(crate) fn stop_worker(
+        &self,
+        addr: Address,
+        relay: Sender<RelayMessage>,
+        task: Option<JoinHandle<()>>,
+        router: Sender<NodeMessage>,
+    ) {
+        let deadline = self.deadline;
+        tokio::spawn(async move {
+            let stopped = timeout_at(deadline, stop_relay(addr.clone(), relay))
+                .await
+                .is_ok();
+            if let (false, Some(task)) = (stopped, task) {
+                task.abort();
+            }
+            let _ = router.send(NodeMessage::StopAck(addr, stopped)).await;
+        });
+    }
+}
+
+/// Ask a worker relay to stop and wait until it did
+///
+/// The stop signal is queued behind the messages already sent to
+/// the worker, which are handled first.
+pub(crate) async fn stop_relay(addr: Address, relay: Sender<RelayMessage>) {
+    let (tx, rx) = oneshot::channel();
+    // A closed relay has already stopped
+    if relay.send(RelayMessage::stop(addr, tx)).await.is_ok() {
+        let _ = rx.await;
+    }
+}
@@ -4,6 +4,24 @@
 //! output main function that sets up an ockam node and executes the body of
 //! the input function inside the node.
 //!
+//! The `#[test]` macro does the same for async test functions, running each
+//! test in a fresh node that is stopped once the test completes.
+//!
+//! The `#[worker]` macro turns an impl block with `#[handler]` methods into a
+//! worker that can receive several message types, generating the message enum
+//! and the dispatching `Worker` implementation.
+//!
+//! The `#[node]` and `#[test]` macros accept arguments to configure the node, which are passed to
+//! `ockam::NodeBuilder`:
+//!
+//! - `worker_threads = 4`: the number of runtime worker threads
+//! - `address = "app"`: the address of the root application context
+//! - `log_filter = "ockam_node=debug"`: log filter used if `OCKAM_LOG` is not set
+//! - `tracing = false`: do not install the global tracing subscriber
+//! - `echo_service = true`: start an echo service for route diagnostics
+//! - `registry_service = true`: start a registry service for service discovery
+//! - `config = "node.toml"`: load node settings from a configuration file
+//!
 //! The main Ockam crate re-exports this macro.
 
 #![deny(
@@ -19,12 +37,73 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{self, parse_macro_input, Error, Ident, ItemFn};
+use syn::{
+    self, parse_macro_input, AttributeArgs, Error, Ident, ItemFn, Lit, Meta, MetaNameValue,
+    NestedMeta,
+};
+
+mod worker;
+
+/// Default timeout of a `#[ockam::test]` function, in milliseconds.
+const DEFAULT_TEST_TIMEOUT_MS: u64 = 30_000;
+
+/// Turn a node argument into the matching `ockam::NodeBuilder` call.
+///
+/// Returns `None` if the argument is not a node argument.
+fn node_builder_call(arg: &NestedMeta) -> Option<syn::Result<TokenStream2>> {
+    let (path, lit) = match arg {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => (path, lit),
+        _ => return None,
+    };
+    let name = path.get_ident()?.to_string();
+
+    let call = match (name.as_str(), lit) {
+        ("worker_threads", Lit::Int(_)) => quote! { .worker_threads(#lit) },
+        ("address", Lit::Str(_)) => quote! { .root_address(#lit) },
+        ("log_filter", Lit::Str(_)) => quote! { .log_filter(#lit) },
+        ("tracing", Lit::Bool(_)) => quote! { .tracing(#lit) },
+        ("echo_service", Lit::Bool(_)) => quote! { .echo_service(#lit) },
+        ("registry_service", Lit::Bool(_)) => quote! { .registry_service(#lit) },
+        ("config", Lit::Str(_)) => quote! { .config_file(#lit) },
+        ("worker_threads", _) => {
+            let message = format!("Expected an integer for `{}`", name);
+            return Some(Err(Error::new_spanned(lit, message)));
+        }
+        ("address", _) | ("log_filter", _) | ("config", _) => {
+            let message = format!("Expected a string for `{}`", name);
+            return Some(Err(Error::new_spanned(lit, message)));
+        }
+        ("tracing", _) | ("echo_service", _) | ("registry_service", _) => {
+            let message = format!("Expected a bool for `{}`", name);
+            return Some(Err(Error::new_spanned(lit, message)));
+        }
+        _ => return None,
+    };
+
+    Some(Ok(call))
+}
 
 /// Marks an async function to be run in an ockam node.
+///
+/// See the crate documentation for the accepted node arguments.
 #[proc_macro_attribute]
-pub fn node(_args: TokenStream, item: TokenStream) -> TokenStream {
+pub fn node(args: TokenStream, item: TokenStream) -> TokenStream {
+    // Parse the node arguments into node builder calls
+    let args = parse_macro_input!(args as AttributeArgs);
+    let mut builder_calls = vec![];
+    for arg in &args {
+        match node_builder_call(arg) {
+            Some(Ok(call)) => builder_calls.push(call),
+            Some(Err(e)) => return e.to_compile_error().into(),
+            None => {
+                let message = "Unknown argument, expected a node argument";
+                return Error::new_spanned(arg, message).to_compile_error().into();
+            }
+        }
+    }
+
     // Parse the item that #[ockam::node] is defined on.
     // Expect that this item is a function and fail if it isn't a function
     let mut input_function = parse_macro_input!(item as ItemFn);
@@ -137,10 +216,174 @@ pub fn node(_args: TokenStream, item: TokenStream) -> TokenStream {
         #input_function
 
         fn main() -> ockam::Result<()> {
-            let (#ctx_ident, mut executor) = ockam::start_node();
+            let (#ctx_ident, mut executor) = ockam::NodeBuilder::new()
+                #(#builder_calls)*
+                .build()?;
             executor.execute(async move { #output_fn_ident(#ctx_ident).await })
         }
     };
     // Create a token stream of the transformed output_function and return it.
     TokenStream::from(output_function)
 }
+
+/// Marks an async test function to be run in an ockam node.
+///
+/// The function must take a single `&mut ockam::Context` argument and
+/// return an `ockam::Result<()>`.  The test fails if the function
+/// returns an error, panics, or does not complete before its timeout.
+/// The node is always stopped once the test has finished.
+///
+/// The timeout defaults to 30 seconds and can be set in milliseconds:
+/// `#[ockam::test(timeout = 1000)]`.  The node arguments accepted by
+/// `#[ockam::node]` can be used as well.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let mut input_function = parse_macro_input!(item as ItemFn);
+
+    // Parse the optional `timeout = <ms>` argument and node arguments
+    let mut timeout_ms = DEFAULT_TEST_TIMEOUT_MS;
+    let mut builder_calls = vec![];
+    for arg in &args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Int(lit),
+                ..
+            })) if path.is_ident("timeout") => match lit.base10_parse::<u64>() {
+                Ok(ms) => timeout_ms = ms,
+                Err(e) => return e.to_compile_error().into(),
+            },
+            _ => match node_builder_call(arg) {
+                Some(Ok(call)) => builder_calls.push(call),
+                Some(Err(e)) => return e.to_compile_error().into(),
+                None => {
+                    let message =
+                        "Unknown argument, expected `timeout = <milliseconds>` or a node argument";
+                    return Error::new_spanned(arg, message).to_compile_error().into();
+                }
+            },
+        }
+    }
+
+    // Fail if the function is not declared async
+    if input_function.sig.asyncness.is_none() {
+        let message = "a function with attribute '#[ockam::test]' must be declared as 'async'";
+        let token = input_function.sig.fn_token;
+        return Error::new_spanned(token, message).to_compile_error().into();
+    }
+
+    // Fail if the function does not have exactly one argument
+    if input_function.sig.inputs.len() != 1 {
+        let message = "a function with '#[ockam::test]' must have exactly one argument";
+        let token = input_function.sig.fn_token;
+        return Error::new_spanned(token, message).to_compile_error().into();
+    }
+
+    // Verify that the passed argument is `&mut Context`
+    let function_arg = input_function.sig.inputs.first().unwrap();
+    if let syn::FnArg::Typed(syn::PatType { ty, .. }) = function_arg {
+        let is_context = match &**ty {
+            syn::Type::Reference(syn::TypeReference {
+                mutability: Some(_),
+                elem,
+                ..
+            }) => match &**elem {
+                syn::Type::Path(syn::TypePath { path, .. }) => path
+                    .segments
+                    .last()
+                    .map(|seg| seg.ident == "Context")
+                    .unwrap_or(false),
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if !is_context {
+            let path_ident = quote! {#ty}.to_string().replace(' ', "");
+            let message = format!("Expected `&mut ockam::Context` found `{}`", path_ident);
+            return Error::new_spanned(ty, message).to_compile_error().into();
+        }
+    } else {
+        // Passed parameter is a `self`.
+        let message = "Input argument should be of type `&mut ockam::Context`";
+        return Error::new_spanned(function_arg, message)
+            .to_compile_error()
+            .into();
+    }
+
+    // Fail if the function does not return a result
+    if let syn::ReturnType::Default = input_function.sig.output {
+        let message = "a function with '#[ockam::test]' must return `ockam::Result<()>`";
+        let fn_ident = &input_function.sig.ident;
+        return Error::new_spanned(fn_ident, message)
+            .to_compile_error()
+            .into();
+    }
+
+    // Transform the input_function to the output_function:
+    // - Keep the user function under a new name, nested in the test
+    // - Generate a new test function with the original name
+    // - Run the user function in a fresh node, then stop the node
+
+    let test_fn_ident = input_function.sig.ident.clone();
+    let test_fn_attrs = std::mem::take(&mut input_function.attrs);
+    let output_fn_ident = Ident::new("trampoline", input_function.sig.ident.span());
+    input_function.sig.ident = output_fn_ident.clone();
+
+    let output_function = quote! {
+        #[test]
+        #(#test_fn_attrs)*
+        fn #test_fn_ident() {
+            #[inline(always)]
+            #input_function
+
+            let (mut ctx, mut executor) = ockam::NodeBuilder::new()
+                #(#builder_calls)*
+                .build()
+                .unwrap();
+            executor
+                .execute_test(
+                    async move {
+                        let res = #output_fn_ident(&mut ctx).await;
+                        ctx.stop().await?;
+                        res
+                    },
+                    std::time::Duration::from_millis(#timeout_ms),
+                )
+                .unwrap();
+        }
+    };
+    TokenStream::from(output_function)
+}
+
+/// Generates a worker handling several message types.
+///
+/// Each method marked with `#[handler]` handles one message type:
+///
+/// ```ignore
+/// #[ockam::worker]
+/// impl PingPong {
+///     #[handler]
+///     async fn on_ping(&mut self, ctx: &mut Context, msg: Routed<Ping>) -> Result<()> {
+///         ctx.send_message(msg.reply(), Pong).await
+///     }
+/// }
+/// ```
+///
+/// This generates a `pub enum PingPongMessage` with one variant per
+/// handled type (named after the type), `From` conversions into it,
+/// and a `Worker` implementation dispatching each variant to its
+/// handler.  Senders can send `PingPongMessage::from(Ping)`.
+///
+/// Methods marked `#[initialize]` and `#[shutdown]` are used as the
+/// corresponding `Worker` methods.  An `async` `#[shutdown]` method
+/// is used as `Worker::stop` instead.
+#[proc_macro_attribute]
+pub fn worker(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemImpl);
+    match worker::expand(input) {
+        Ok(output) => TokenStream::from(output),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
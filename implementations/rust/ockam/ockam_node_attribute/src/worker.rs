@@ -0,0 +1,225 @@
+//! Implementation of the `#[worker]` attribute macro
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    self, spanned::Spanned, Error, FnArg, GenericArgument, Ident, ImplItem, ImplItemMethod,
+    ItemImpl, PathArguments, Type,
+};
+
+/// A `#[handler]` method and the message type it accepts
+struct Handler {
+    method: Ident,
+    variant: Ident,
+    ty: Type,
+}
+
+/// Check if a method has an attribute, and remove it if so
+fn take_attr(method: &mut ImplItemMethod, name: &str) -> bool {
+    let len = method.attrs.len();
+    method.attrs.retain(|attr| !attr.path.is_ident(name));
+    method.attrs.len() != len
+}
+
+/// Extract `T` from a `Routed<T>` type
+fn routed_inner(ty: &Type) -> Option<&Type> {
+    let path = match ty {
+        Type::Path(syn::TypePath { path, .. }) => path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Routed" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+            Some(GenericArgument::Type(inner)) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Validate a `#[handler]` method and extract its message type
+fn parse_handler(method: &ImplItemMethod) -> syn::Result<Handler> {
+    let sig = &method.sig;
+
+    if sig.asyncness.is_none() {
+        let message = "a '#[handler]' method must be declared as 'async'";
+        return Err(Error::new_spanned(sig.fn_token, message));
+    }
+
+    // Expect `(&mut self, ctx: &mut Context, msg: Routed<M>)`
+    if sig.inputs.len() != 3 {
+        let message = "a '#[handler]' method must take `&mut self`, a context and a `Routed<M>`";
+        return Err(Error::new_spanned(&sig.ident, message));
+    }
+
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(recv)) if recv.reference.is_some() && recv.mutability.is_some() => {}
+        Some(arg) => {
+            let message = "a '#[handler]' method must take `&mut self`";
+            return Err(Error::new_spanned(arg, message));
+        }
+        None => unreachable!(),
+    }
+
+    let ty = match sig.inputs.last() {
+        Some(FnArg::Typed(pat_type)) => match routed_inner(&pat_type.ty) {
+            Some(ty) => ty.clone(),
+            None => {
+                let message = "Expected the message argument to be of type `Routed<M>`";
+                return Err(Error::new_spanned(&pat_type.ty, message));
+            }
+        },
+        _ => unreachable!(),
+    };
+
+    // The enum variant is named after the message type
+    let variant = match &ty {
+        Type::Path(syn::TypePath { path, .. }) => path.segments.last().unwrap().ident.clone(),
+        _ => {
+            let message = "a '#[handler]' message type must be a named type";
+            return Err(Error::new_spanned(&ty, message));
+        }
+    };
+
+    Ok(Handler {
+        method: sig.ident.clone(),
+        variant,
+        ty,
+    })
+}
+
+/// Expand an `impl` block with `#[handler]` methods into a worker
+pub(crate) fn expand(mut input: ItemImpl) -> syn::Result<TokenStream2> {
+    if let Some((_, path, _)) = &input.trait_ {
+        let message = "'#[ockam::worker]' must be used on an inherent impl block";
+        return Err(Error::new_spanned(path, message));
+    }
+
+    if !input.generics.params.is_empty() {
+        let message = "'#[ockam::worker]' does not support generic workers";
+        return Err(Error::new_spanned(&input.generics, message));
+    }
+
+    let worker_ident = match &*input.self_ty {
+        Type::Path(syn::TypePath { path, .. }) => path.segments.last().unwrap().ident.clone(),
+        ty => {
+            let message = "'#[ockam::worker]' must be used on a named type";
+            return Err(Error::new_spanned(ty, message));
+        }
+    };
+
+    // Collect the handlers and the optional lifecycle methods,
+    // removing our marker attributes from the impl block
+    let mut handlers: Vec<Handler> = vec![];
+    let mut initialize = None;
+    let mut shutdown = None;
+    for item in input.items.iter_mut() {
+        if let ImplItem::Method(method) = item {
+            if take_attr(method, "handler") {
+                let handler = parse_handler(method)?;
+                if handlers.iter().any(|h| h.variant == handler.variant) {
+                    let message =
+                        format!("duplicate '#[handler]' for message `{}`", handler.variant);
+                    return Err(Error::new_spanned(&handler.ty, message));
+                }
+                handlers.push(handler);
+            } else if take_attr(method, "initialize") {
+                initialize = Some(method.sig.ident.clone());
+            } else if take_attr(method, "shutdown") {
+                shutdown = Some((method.sig.ident.clone(), method.sig.asyncness.is_some()));
+            }
+        }
+    }
+
+    if handlers.is_empty() {
+        let message = "'#[ockam::worker]' requires at least one '#[handler]' method";
+        return Err(Error::new(input.self_ty.span(), message));
+    }
+
+    let self_ty = &input.self_ty;
+    let enum_ident = Ident::new(&format!("{}Message", worker_ident), Span::call_site());
+    let enum_doc = format!("Messages handled by the [`{}`] worker", worker_ident);
+
+    let variants = handlers.iter().map(|Handler { variant, ty, .. }| {
+        quote! { #variant(#ty) }
+    });
+    let conversions = handlers.iter().map(|Handler { variant, ty, .. }| {
+        quote! {
+            impl From<#ty> for #enum_ident {
+                fn from(msg: #ty) -> Self {
+                    #enum_ident::#variant(msg)
+                }
+            }
+        }
+    });
+    let arms = handlers.iter().map(|handler| {
+        let (method, variant) = (&handler.method, &handler.variant);
+        quote! {
+            #enum_ident::#variant(msg) => {
+                self.#method(ctx, ockam::Routed::new(msg, route).with_local(local)).await
+            }
+        }
+    });
+
+    let initialize = initialize.map(|method| {
+        quote! {
+            async fn initialize(&mut self, ctx: &mut ockam::Context) -> ockam::Result<()> {
+                self.#method(ctx).await
+            }
+        }
+    });
+    let shutdown = shutdown.map(|(method, is_async)| {
+        if is_async {
+            quote! {
+                async fn stop(&mut self, ctx: &mut ockam::Context) -> ockam::Result<()> {
+                    self.#method(ctx).await
+                }
+            }
+        } else {
+            quote! {
+                fn shutdown(&mut self, ctx: &mut ockam::Context) -> ockam::Result<()> {
+                    self.#method(ctx)
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #input
+
+        #[doc = #enum_doc]
+        #[derive(ockam::serde::Serialize, ockam::serde::Deserialize)]
+        #[serde(crate = "ockam::serde")]
+        pub enum #enum_ident {
+            #(#variants),*
+        }
+
+        #(#conversions)*
+
+        #[ockam::async_worker]
+        impl ockam::Worker for #self_ty {
+            type Message = #enum_ident;
+            type Context = ockam::Context;
+
+            #initialize
+
+            #shutdown
+
+            async fn handle_message(
+                &mut self,
+                ctx: &mut ockam::Context,
+                msg: ockam::Routed<#enum_ident>,
+            ) -> ockam::Result<()> {
+                let route = msg.reply();
+                let local = msg.local().clone();
+                match msg.take() {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
@@ -20,6 +20,11 @@ pub(crate) fn stop(b: &ArcBool) {
     b.fetch_and(false, Ordering::Relaxed);
 }
 
+/// Set the ArcBool
+pub(crate) fn set(b: &ArcBool) {
+    b.store(true, Ordering::Relaxed);
+}
+
 /// Perform a relaxed ordering check
 pub(crate) fn check(b: &ArcBool) -> bool {
     b.load(Ordering::Relaxed)
@@ -11,6 +11,7 @@ pub struct WorkerPair {
     pub(crate) tx_addr: Address,
     pub(crate) rx_addr: Address,
     run: ArcBool,
+    peer_v2: ArcBool,
 }
 
 impl WorkerPair {
@@ -22,12 +23,23 @@ impl WorkerPair {
         Ok(())
     }
 
+    /// Declare that the peer decodes version 2 transport messages
+    ///
+    /// Until then, messages are sent to the peer in the version 1
+    /// layout, and reports and probes are dropped.  Peers running this
+    /// implementation are also detected from the first version 2
+    /// message they send.
+    pub fn declare_v2_peer(&self) {
+        atomic::set(&self.peer_v2);
+    }
+
     fn from_peer(addr: &SocketAddr) -> Self {
         Self {
             peer: addr.clone(),
             tx_addr: format!("{}_tx", addr).into(),
             rx_addr: format!("{}_rx", addr).into(),
             run: atomic::new(true),
+            peer_v2: atomic::new(false),
         }
     }
 }
@@ -43,17 +55,22 @@ impl WorkerPair {
             rx_addr,
             tx_addr,
             run,
+            peer_v2,
         } = WorkerPair::from_peer(&peer);
 
         trace!("Creating new worker pair from stream");
 
         // Create two workers based on the split TCP I/O streams
         let (rx, tx) = stream.into_split();
-        let sender = TcpSendWorker { tx };
+        let sender = TcpSendWorker {
+            tx,
+            peer_v2: peer_v2.clone(),
+        };
         let receiver = TcpRecvWorker {
-            rx,
+            rx: Some(rx),
             run: run.clone(),
             peer_addr: format!("1#{}", peer).into(),
+            peer_v2: peer_v2.clone(),
         };
 
         // Derive local worker addresses, and start them
@@ -66,6 +83,7 @@ impl WorkerPair {
             rx_addr,
             tx_addr,
             run,
+            peer_v2,
         })
     }
 
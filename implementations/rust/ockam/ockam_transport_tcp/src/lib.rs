@@ -28,9 +28,11 @@ mod listener;
 mod receiver;
 mod router;
 mod sender;
+mod setup;
 
 pub use error::TcpError;
 pub use init::{start_tcp_worker, WorkerPair};
 pub use receiver::TcpRecvWorker;
 pub use router::{TcpRouter, TcpRouterHandle};
 pub use sender::TcpSendWorker;
+pub use setup::setup_tcp;
@@ -2,7 +2,7 @@ use crate::{
     atomic::{self, ArcBool},
     TcpError,
 };
-use ockam::{async_worker, Address, Context, Result, TransportMessage, Worker};
+use ockam::{async_worker, Address, Context, Result, RoutingError, TransportMessage, Worker};
 use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
 
 /// A TCP receiving message worker
@@ -14,73 +14,122 @@ use tokio::{io::AsyncReadExt, net::tcp::OwnedReadHalf};
 /// worker pair, and listens for incoming TCP packets, to relay into
 /// the node message system.
 pub struct TcpRecvWorker {
-    pub(crate) rx: OwnedReadHalf,
+    pub(crate) rx: Option<OwnedReadHalf>,
     pub(crate) run: ArcBool,
     pub(crate) peer_addr: Address,
+    pub(crate) peer_v2: ArcBool,
 }
 
-#[async_worker]
-impl Worker for TcpRecvWorker {
-    type Context = Context;
+/// Read messages from the TCP stream and forward them to the node
+///
+/// This runs as a task of the [`TcpRecvWorker`], and is cancelled
+/// when the worker stops.
+async fn receive_loop(
+    ctx: Context,
+    mut rx: OwnedReadHalf,
+    run: ArcBool,
+    peer_addr: Address,
+    peer_v2: ArcBool,
+) -> Result<()> {
+    let self_addr = ctx.address();
 
-    // Do not actually listen for messages
-    type Message = ();
+    // Run in a loop until TcpWorkerPair::stop() is called
+    // FIXME: see ArcBool future note
+    while atomic::check(&run) {
+        // First read a message length header...
+        let len = match rx.read_u16().await {
+            Ok(len) => len,
+            Err(_) => {
+                debug!("Connection to {} was closed", peer_addr);
+                break;
+            }
+        };
 
-    // We are using the initialize function here to run a custom loop,
-    // while never listening for messages sent to our address
-    //
-    // Note: when the loop exits, we _must_ call stop_worker(..) on
-    // Context not to spawn a zombie task.
-    //
-    // Also: we must stop the TcpReceive loop when the worker gets
-    // killed by the user or node.
-    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
-        let self_addr = ctx.address();
-
-        // Run in a loop until TcpWorkerPair::stop() is called
-        // FIXME: see ArcBool future note
-        while atomic::check(&self.run) {
-            // First read a message length header...
-            let len = self.rx.read_u16().await.unwrap();
-
-            trace!("Received message header for {} bytes", len);
-
-            // Allocate a buffer of that size
-            let mut buf = vec![0; len as usize];
-
-            // Then Read into the buffer
-            match self.rx.read_exact(&mut buf).await {
-                Ok(_) => {}
-                _ => {
-                    error!("Failed to receive message of length: {}", len);
-                    continue;
-                }
+        trace!("Received message header for {} bytes", len);
+
+        // Allocate a buffer of that size
+        let mut buf = vec![0; len as usize];
+
+        // Then Read into the buffer
+        match rx.read_exact(&mut buf).await {
+            Ok(_) => {}
+            _ => {
+                error!("Failed to receive message of length: {}", len);
+                continue;
             }
+        }
 
-            // Deserialize the message now
-            let mut msg: TransportMessage =
-                serde_bare::from_slice(buf.as_slice()).map_err(|_| TcpError::RecvBadMessage)?;
+        // Deserialize the message now, closing connections which
+        // send malformed frames
+        let mut msg: TransportMessage = match serde_bare::from_slice(buf.as_slice()) {
+            Ok(msg) => msg,
+            Err(_) => {
+                error!("Failed to decode message from {}", peer_addr);
+                break;
+            }
+        };
 
-            // Insert the peer address into the return route so that
-            // reply routing can be properly resolved
-            msg.return_.modify().prepend(self.peer_addr.clone());
+        // Reject routes that are too long before handling them
+        if let Err(e) = msg.validate() {
+            warn!("Dropping message from {}: {}", peer_addr, e);
+            continue;
+        }
+
+        // A peer which sends version 2 messages also decodes them
+        if msg.version == 2 {
+            atomic::set(&peer_v2);
+        }
 
-            // Some verbose logging we may want to remove
-            trace!("Message onward route: {}", msg.onward);
-            trace!("Message return route: {}", msg.return_);
+        // Insert the peer address into the return route so that
+        // reply routing can be properly resolved
+        msg.return_.modify().prepend(peer_addr.clone());
 
-            // FIXME: if we need to re-route (i.e. send it to another
-            // domain specific router) the message here, use
-            // send_message, instead of forward_message.
+        // Let the receiving worker know which connection the
+        // message arrived on
+        msg.local.transport = Some(self_addr.clone());
 
-            // Forward the message to the final destination worker,
-            // which consumes the TransportMessage and yields the
-            // final message type
-            ctx.forward_message(msg).await?;
+        // Some verbose logging we may want to remove
+        trace!("Message onward route: {}", msg.onward);
+        trace!("Message return route: {}", msg.return_);
+
+        // FIXME: if we need to re-route (i.e. send it to another
+        // domain specific router) the message here, use
+        // send_message, instead of forward_message.
+
+        // Forward the message to the final destination worker,
+        // which consumes the TransportMessage and yields the
+        // final message type
+        match ctx.forward_message(msg).await {
+            Ok(()) => {}
+            // Routing errors were already reported by the node
+            Err(e) if e.domain() == RoutingError::DOMAIN_NAME => {}
+            Err(e) => {
+                error!("Failed to forward message from {}: {}", peer_addr, e);
+                break;
+            }
         }
+    }
+
+    // Stop the worker once the connection is closed, which also
+    // cancels this task
+    ctx.stop_worker(self_addr).await
+}
+
+#[async_worker]
+impl Worker for TcpRecvWorker {
+    type Context = Context;
 
-        // Stop the worker to not fall into the next read loop
-        ctx.stop_worker(self_addr).await?;
+    // Do not actually listen for messages
+    type Message = ();
+
+    // The receive loop runs as a task of this worker, so the worker
+    // relay isn't blocked, and the loop is cancelled when the worker
+    // gets stopped by the user or node.
+    async fn initialize(&mut self, ctx: &mut Context) -> Result<()> {
+        let rx = self.rx.take().ok_or(TcpError::ConnectionDrop)?;
+        let (run, peer_addr) = (self.run.clone(), self.peer_addr.clone());
+        let peer_v2 = self.peer_v2.clone();
+        ctx.spawn(move |ctx| receive_loop(ctx, rx, run, peer_addr, peer_v2));
         Ok(())
     }
 }
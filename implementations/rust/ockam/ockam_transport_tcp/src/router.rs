@@ -1,9 +1,9 @@
 use crate::{
     atomic::{self, ArcBool},
     listener::TcpListenWorker,
-    WorkerPair,
+    start_tcp_worker, WorkerPair,
 };
-use ockam::{async_worker, Address, Context, Result, Routed, RouterMessage, Worker};
+use ockam::{async_worker, Address, Context, Result, Routed, RouterMessage, TcpConfig, Worker};
 use std::{collections::BTreeMap, net::SocketAddr};
 
 const DEFAULT_ADDRESS: &'static str = "io.ockam.router.tcp";
@@ -66,6 +66,9 @@ impl Worker for TcpRouter {
             Route(mut msg) => {
                 trace!("TCP route request: {:?}", msg.onward.next());
 
+                // The hop limit was already decremented when the node
+                // forwarded the message, if it didn't originate here
+
                 // Get the next hop
                 let onward = msg.onward.step().unwrap();
 
@@ -135,4 +138,24 @@ impl TcpRouter {
         Self::start(ctx, &addr, Some(run)).await?;
         Ok(TcpRouterHandle { ctx, addr })
     }
+
+    /// Register a new TCP router with the listeners and outbound
+    /// connections of a node configuration
+    pub(crate) async fn configure(ctx: &Context, config: &TcpConfig) -> Result<()> {
+        let run = atomic::new(true);
+        let addr = Address::from(DEFAULT_ADDRESS);
+
+        for socket_addr in &config.listen {
+            TcpListenWorker::start(ctx, addr.clone(), *socket_addr, run.clone()).await?;
+        }
+
+        Self::start(ctx, &addr, Some(run)).await?;
+        let router = TcpRouterHandle { ctx, addr };
+
+        for peer in &config.connect {
+            let pair = start_tcp_worker(ctx, *peer).await?;
+            router.register(&pair).await?;
+        }
+        Ok(())
+    }
 }
@@ -1,5 +1,8 @@
-use crate::TcpError;
-use ockam::{async_worker, Context, Result, Routed, TransportMessage, Worker};
+use crate::{
+    atomic::{self, ArcBool},
+    TcpError,
+};
+use ockam::{async_worker, Context, PayloadKind, Result, Routed, TransportMessage, Worker};
 use tokio::{io::AsyncWriteExt, net::tcp::OwnedWriteHalf};
 
 /// A TCP sending message worker
@@ -10,8 +13,12 @@ use tokio::{io::AsyncWriteExt, net::tcp::OwnedWriteHalf};
 /// This half of the worker is created when spawning a new connection
 /// worker pair, and listens for messages from the node message system
 /// to dispatch to a remote peer.
+///
+/// Messages are sent in the version 1 layout, without their hop
+/// limit, until the peer is known to decode version 2 messages.
 pub struct TcpSendWorker {
     pub(crate) tx: OwnedWriteHalf,
+    pub(crate) peer_v2: ArcBool,
 }
 
 fn prepare_message(msg: TransportMessage) -> Result<Vec<u8>> {
@@ -43,14 +50,26 @@ impl Worker for TcpSendWorker {
     async fn handle_message(
         &mut self,
         _: &mut Context,
-        mut msg: Routed<TransportMessage>,
+        msg: Routed<TransportMessage>,
     ) -> Result<()> {
+        let mut msg = msg.take();
+
         // Remove our own address from the route so the other end
         // knows what to do with the incoming message
         msg.onward.step();
 
+        // Peers which only decode version 1 messages can't tell
+        // reports and probes from data
+        if msg.version != 1 && !atomic::check(&self.peer_v2) {
+            if msg.kind != PayloadKind::Data {
+                debug!("Dropping {:?} message for a version 1 peer", msg.kind);
+                return Ok(());
+            }
+            msg.version = 1;
+        }
+
         // Create a message buffer with pre-pended length
-        let msg = prepare_message(msg.take())?;
+        let msg = prepare_message(msg)?;
 
         match self.tx.write(msg.as_slice()).await {
             Ok(_) => Ok(()),
@@ -58,4 +77,12 @@ impl Worker for TcpSendWorker {
             Err(_) => Err(TcpError::SendBadMessage.into()),
         }
     }
+
+    // Flush pending data and close our half of the connection
+    async fn stop(&mut self, _: &mut Context) -> Result<()> {
+        self.tx
+            .shutdown()
+            .await
+            .map_err(|_| TcpError::ConnectionDrop.into())
+    }
 }
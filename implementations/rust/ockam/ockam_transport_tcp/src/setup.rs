@@ -0,0 +1,21 @@
+use crate::TcpRouter;
+use ockam::{Context, NodeConfig, Result};
+
+/// Start the TCP router of a node, with the listeners and outbound
+/// connections of the `tcp` section of its configuration file
+///
+/// The router is started once the node is executed, and only if the
+/// configuration file has a `tcp` section.
+///
+/// ```ignore
+/// let (ctx, mut executor) = NodeBuilder::new()
+///     .config_file("node.toml")
+///     .setup(ockam_transport_tcp::setup_tcp)
+///     .build()?;
+/// ```
+pub fn setup_tcp(ctx: &Context, config: &NodeConfig) -> Result<()> {
+    if let Some(tcp) = config.tcp.clone() {
+        ctx.spawn(move |ctx| async move { TcpRouter::configure(&ctx, &tcp).await });
+    }
+    Ok(())
+}
@@ -2,8 +2,8 @@ use crate::{SoftwareVault, VaultEntry, VaultError};
 use arrayref::array_ref;
 use ockam_vault_core::Buffer;
 use ockam_vault_core::{
-    AsymmetricVault, Secret, SecretAttributes, SecretPersistence, SecretType, SecretVault,
-    CURVE25519_PUBLIC_LENGTH, CURVE25519_SECRET_LENGTH,
+    AsymmetricVault, Secret, SecretAttributes, SecretPersistence, SecretType, SecretUsage,
+    SecretVault, CURVE25519_PUBLIC_LENGTH, CURVE25519_SECRET_LENGTH, P256_PUBLIC_LENGTH,
 };
 
 impl SoftwareVault {
@@ -33,7 +33,18 @@ impl SoftwareVault {
                 let secret = sk.diffie_hellman(&pk_t);
                 Ok(secret.as_bytes().to_vec())
             }
-            SecretType::P256 | SecretType::Buffer | SecretType::Aes => {
+            SecretType::P256 => {
+                if peer_public_key.len() != P256_PUBLIC_LENGTH {
+                    return Err(VaultError::UnknownEcdhKeyType.into());
+                }
+
+                let sk = Self::p256_secret_key(key.as_ref())?;
+                let pk_t = p256::PublicKey::from_sec1_bytes(peer_public_key)
+                    .map_err(|_| VaultError::InvalidPublicKey)?;
+                let secret = p256::ecdh::diffie_hellman(sk.to_nonzero_scalar(), pk_t.as_affine());
+                Ok(secret.raw_secret_bytes().to_vec())
+            }
+            SecretType::Buffer | SecretType::Aes | SecretType::Ed25519 | SecretType::ChaCha20 => {
                 Err(VaultError::UnknownEcdhKeyType.into())
             }
         }
@@ -46,7 +57,7 @@ impl AsymmetricVault for SoftwareVault {
         context: &Secret,
         peer_public_key: &[u8],
     ) -> ockam_core::Result<Secret> {
-        let entry = self.get_entry(context)?;
+        let entry = self.get_entry_for(context, SecretUsage::Derive)?;
 
         let dh = Self::ecdh_internal(entry, peer_public_key)?;
 
@@ -61,9 +72,30 @@ mod tests {
     use crate::SoftwareVault;
     use ockam_vault_core::{
         AsymmetricVault, SecretAttributes, SecretPersistence, SecretType, SecretVault,
-        CURVE25519_SECRET_LENGTH,
+        CURVE25519_SECRET_LENGTH, P256_SECRET_LENGTH,
     };
 
+    #[test]
+    fn ec_diffie_hellman_p256() {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::P256,
+            SecretPersistence::Ephemeral,
+            P256_SECRET_LENGTH,
+        );
+        let sk_ctx_1 = vault.secret_generate(attributes).unwrap();
+        let sk_ctx_2 = vault.secret_generate(attributes).unwrap();
+        let pk_1 = vault.secret_public_key_get(&sk_ctx_1).unwrap();
+        let pk_2 = vault.secret_public_key_get(&sk_ctx_2).unwrap();
+
+        let ss1 = vault.ec_diffie_hellman(&sk_ctx_1, pk_2.as_ref()).unwrap();
+        let ss2 = vault.ec_diffie_hellman(&sk_ctx_2, pk_1.as_ref()).unwrap();
+        assert_eq!(
+            vault.secret_export(&ss1).unwrap(),
+            vault.secret_export(&ss2).unwrap()
+        );
+    }
+
     #[test]
     fn ec_diffie_hellman_curve25519() {
         let mut vault = SoftwareVault::default();
@@ -0,0 +1,328 @@
+use crate::encoding::{put_attributes, put_bytes, Reader};
+use crate::recipient::AES_GCM_NONCE_LENGTH;
+use crate::recipient::{aes_attributes, open_for_recipient, seal_for_recipient};
+use crate::software_vault::{SoftwareVault, VaultEntry};
+use crate::VaultError;
+use hmac::Hmac;
+use ockam_vault_core::{
+    AsymmetricVault, Buffer, Hasher, PublicKey, Secret, SecretKey, SecretVault, SymmetricVault,
+    AES256_SECRET_LENGTH,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+/// Identifies vault backups
+const BACKUP_MAGIC: &[u8; 4] = b"OCKV";
+/// Version of the backup format
+const BACKUP_VERSION: u8 = 1;
+/// The backup key is derived from a password with PBKDF2-HMAC-SHA256
+const METHOD_PASSWORD: u8 = 0;
+/// The backup key is agreed with a recipient Curve25519 key
+const METHOD_RECIPIENT: u8 = 1;
+
+const PASSWORD_SALT_LENGTH: usize = 16;
+const PASSWORD_ITERATIONS: u32 = 100_000;
+
+/// Derive the backup key from a password into `vault`
+fn password_key(
+    vault: &mut SoftwareVault,
+    password: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> ockam_core::Result<Secret> {
+    let mut key = [0u8; AES256_SECRET_LENGTH];
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, iterations, &mut key);
+    let secret = vault.secret_import(&key, aes_attributes());
+    key.zeroize();
+    secret
+}
+
+fn backup_header(method: u8) -> Vec<u8> {
+    let mut header = BACKUP_MAGIC.to_vec();
+    header.push(BACKUP_VERSION);
+    header.push(method);
+    header
+}
+
+/// Check the header of a backup made with `method`, returning a
+/// reader of what follows it
+fn backup_reader(backup: &[u8], method: u8) -> ockam_core::Result<Reader<'_>> {
+    let header = backup_header(method);
+    let mut reader = Reader::new(backup);
+    match reader.take(header.len()) {
+        Ok(prefix) if prefix == &header[..] => Ok(reader),
+        _ => Err(VaultError::InvalidBackup.into()),
+    }
+}
+
+impl SoftwareVault {
+    /// Serialize the exportable entries, with their attributes and
+    /// key ids
+    fn backup_payload(&self) -> ockam_core::Result<Vec<u8>> {
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.key_attributes().exportable())
+            .collect();
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&(self.next_id as u64).to_be_bytes());
+        payload.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for (index, entry) in entries {
+            payload.extend_from_slice(&(*index as u64).to_be_bytes());
+            put_bytes(&mut payload, entry.key().as_ref())?;
+            put_attributes(&mut payload, entry.key_attributes());
+            match entry.key_id() {
+                Some(key_id) => {
+                    payload.push(1);
+                    put_bytes(&mut payload, key_id.as_bytes())?;
+                }
+                None => payload.push(0),
+            }
+        }
+        Ok(payload)
+    }
+
+    /// Rebuild a vault from serialized entries, keeping their indices
+    fn from_backup_payload(payload: &[u8]) -> ockam_core::Result<Self> {
+        let mut reader = Reader::new(payload);
+        let mut vault = Self::new();
+        vault.next_id = reader.u64()? as usize;
+        for _ in 0..reader.u32()? {
+            let index = reader.u64()? as usize;
+            let key = SecretKey::new(reader.bytes()?.to_vec());
+            let attributes = reader.attributes(key.as_ref().len())?;
+            let key_id = match reader.u8()? {
+                0 => None,
+                _ => Some(
+                    String::from_utf8(reader.bytes()?.to_vec())
+                        .map_err(|_| VaultError::InvalidBackup)?,
+                ),
+            };
+            if index > vault.next_id || vault.entries.contains_key(&index) {
+                return Err(VaultError::InvalidBackup.into());
+            }
+            vault
+                .entries
+                .insert(index, VaultEntry::new(key_id, attributes, key));
+        }
+        reader.finish()?;
+        Ok(vault)
+    }
+
+    /// Back up the exportable secrets of the vault, encrypted with a
+    /// key derived from `password`.
+    ///
+    /// Secrets which can't be exported are left out of the backup, so
+    /// [`SoftwareVault::restore_with_password`] rebuilds the vault
+    /// without them.
+    pub fn backup_with_password(&self, password: &str) -> ockam_core::Result<Buffer<u8>> {
+        let mut salt = [0u8; PASSWORD_SALT_LENGTH];
+        let mut nonce = [0u8; AES_GCM_NONCE_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let mut backup = backup_header(METHOD_PASSWORD);
+        backup.extend_from_slice(&salt);
+        backup.extend_from_slice(&PASSWORD_ITERATIONS.to_be_bytes());
+        backup.extend_from_slice(&nonce);
+
+        let mut vault = SoftwareVault::new();
+        let key = password_key(&mut vault, password, &salt, PASSWORD_ITERATIONS)?;
+        let mut payload = self.backup_payload()?;
+        let ciphertext = vault.aead_aes_gcm_encrypt(&key, &payload, &nonce, &backup);
+        payload.zeroize();
+        backup.extend_from_slice(&ciphertext?);
+        Ok(backup)
+    }
+
+    /// Restore a vault from a backup made with
+    /// [`SoftwareVault::backup_with_password`]. Secrets keep their
+    /// handles and key ids.
+    pub fn restore_with_password(backup: &[u8], password: &str) -> ockam_core::Result<Self> {
+        let mut reader = backup_reader(backup, METHOD_PASSWORD)?;
+        let salt = reader.take(PASSWORD_SALT_LENGTH)?;
+        // The iterations come from an untrusted backup, and only the
+        // ones of this version are accepted
+        let iterations = reader.u32()?;
+        if iterations != PASSWORD_ITERATIONS {
+            return Err(VaultError::InvalidBackup.into());
+        }
+        let nonce = reader.take(AES_GCM_NONCE_LENGTH)?;
+        let header = &backup[..backup.len() - reader.input.len()];
+
+        let mut vault = SoftwareVault::new();
+        let key = password_key(&mut vault, password, salt, iterations)?;
+        let mut payload = vault.aead_aes_gcm_decrypt(&key, reader.input, nonce, header)?;
+        let restored = Self::from_backup_payload(&payload);
+        payload.zeroize();
+        restored
+    }
+
+    /// Back up the exportable secrets of the vault, encrypted to the
+    /// Curve25519 public key of a recipient.
+    ///
+    /// Secrets which can't be exported are left out of the backup, so
+    /// [`SoftwareVault::restore_for_recipient`] rebuilds the vault
+    /// without them.
+    pub fn backup_for_recipient(
+        &self,
+        recipient_public_key: &PublicKey,
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let mut payload = self.backup_payload()?;
+        let backup = seal_for_recipient(
+            backup_header(METHOD_RECIPIENT),
+            recipient_public_key,
+            &payload,
+        );
+        payload.zeroize();
+        backup
+    }
+
+    /// Restore a vault from a backup made with
+    /// [`SoftwareVault::backup_for_recipient`], using the recipient
+    /// Curve25519 secret held by `vault`. Secrets keep their handles
+    /// and key ids.
+    pub fn restore_for_recipient<V>(
+        backup: &[u8],
+        vault: &mut V,
+        recipient: &Secret,
+    ) -> ockam_core::Result<Self>
+    where
+        V: AsymmetricVault + Hasher + SecretVault + SymmetricVault,
+    {
+        let header_length = backup.len() - backup_reader(backup, METHOD_RECIPIENT)?.input.len();
+        let mut payload = open_for_recipient(vault, recipient, backup, header_length)?;
+        let restored = Self::from_backup_payload(&payload);
+        payload.zeroize();
+        restored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SoftwareVault, VaultError};
+    use ockam_vault_core::{
+        KeyIdVault, Secret, SecretAttributes, SecretPersistence, SecretType, SecretUsage,
+        SecretVault, Signer, Verifier, CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+    };
+
+    /// A vault with a signing key, a non-exportable key, a destroyed
+    /// and a buffer secret
+    fn populated_vault() -> (SoftwareVault, Secret, Secret) {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::Ed25519,
+            SecretPersistence::Persistent,
+            ED25519_SECRET_LENGTH,
+        )
+        .with_usage(SecretUsage::Sign);
+        let key = vault.secret_generate(attributes).unwrap();
+        let hidden = vault
+            .secret_generate(attributes.with_exportable(false))
+            .unwrap();
+        let buffer = vault
+            .secret_generate(SecretAttributes::new(
+                SecretType::Buffer,
+                SecretPersistence::Ephemeral,
+                24,
+            ))
+            .unwrap();
+        vault.secret_destroy(buffer).unwrap();
+        vault
+            .secret_import(
+                b"hello world!",
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 12),
+            )
+            .unwrap();
+        (vault, key, hidden)
+    }
+
+    fn assert_restored(
+        vault: &mut SoftwareVault,
+        key: &Secret,
+        hidden: &Secret,
+        restored: &mut SoftwareVault,
+    ) {
+        // Non-exportable secrets are left out
+        let hidden_public = vault.secret_public_key_get(hidden).unwrap();
+        let hidden_id = vault.compute_key_id_for_public_key(&hidden_public).unwrap();
+        assert!(restored.get_secret_by_key_id(&hidden_id).is_err());
+        assert!(restored.secret_attributes_get(hidden).is_err());
+        assert_eq!(restored.entries.len(), vault.entries.len() - 1);
+        for (index, entry) in &restored.entries {
+            assert_ne!(*index, hidden.index());
+            assert_eq!(vault.entries.get(index), Some(entry));
+        }
+        assert_eq!(restored.next_id, vault.next_id);
+
+        // Key ids find the same keys, which sign the same way
+        let public = vault.secret_public_key_get(key).unwrap();
+        let key_id = vault.compute_key_id_for_public_key(&public).unwrap();
+        let secret = restored.get_secret_by_key_id(&key_id).unwrap();
+        let signature = restored.sign(&secret, b"data").unwrap();
+        vault
+            .verify(&signature, public.as_ref(), SecretType::Ed25519, b"data")
+            .unwrap();
+
+        // Fresh secrets don't reuse the handles of restored ones
+        let secret = restored
+            .secret_import(
+                b"fresh",
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 5),
+            )
+            .unwrap();
+        assert!(vault.secret_attributes_get(&secret).is_err());
+    }
+
+    #[test]
+    fn backup_with_password() {
+        let (mut vault, key, hidden) = populated_vault();
+        let backup = vault.backup_with_password("password").unwrap();
+
+        let mut restored = SoftwareVault::restore_with_password(&backup, "password").unwrap();
+        assert_restored(&mut vault, &key, &hidden, &mut restored);
+
+        assert!(SoftwareVault::restore_with_password(&backup, "wrong").is_err());
+
+        // The header is authenticated
+        let mut tampered = backup.to_vec();
+        tampered[10] ^= 1;
+        assert!(SoftwareVault::restore_with_password(&tampered, "password").is_err());
+
+        // Other iteration counts are refused before deriving the key
+        let mut tampered = backup.to_vec();
+        tampered[22..26].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = SoftwareVault::restore_with_password(&tampered, "password").unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidBackup as u32
+        );
+    }
+
+    #[test]
+    fn backup_for_recipient() {
+        let (mut vault, key, hidden) = populated_vault();
+        let mut recipient_vault = SoftwareVault::default();
+        let recipient = recipient_vault
+            .secret_generate(SecretAttributes::new(
+                SecretType::Curve25519,
+                SecretPersistence::Persistent,
+                CURVE25519_SECRET_LENGTH,
+            ))
+            .unwrap();
+        let recipient_public = recipient_vault.secret_public_key_get(&recipient).unwrap();
+
+        let backup = vault.backup_for_recipient(&recipient_public).unwrap();
+        let mut restored =
+            SoftwareVault::restore_for_recipient(&backup, &mut recipient_vault, &recipient)
+                .unwrap();
+        assert_restored(&mut vault, &key, &hidden, &mut restored);
+
+        let err = SoftwareVault::restore_with_password(&backup, "password").unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidBackup as u32
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::VaultError;
+use ockam_vault_core::{SecretAttributes, SecretPersistence, SecretType, SecretUsage};
+use std::convert::TryInto;
+
+fn stype_to_u8(stype: SecretType) -> u8 {
+    match stype {
+        SecretType::Buffer => 0,
+        SecretType::Aes => 1,
+        SecretType::Curve25519 => 2,
+        SecretType::P256 => 3,
+        SecretType::Ed25519 => 4,
+        SecretType::ChaCha20 => 5,
+    }
+}
+
+fn stype_from_u8(stype: u8) -> ockam_core::Result<SecretType> {
+    match stype {
+        0 => Ok(SecretType::Buffer),
+        1 => Ok(SecretType::Aes),
+        2 => Ok(SecretType::Curve25519),
+        3 => Ok(SecretType::P256),
+        4 => Ok(SecretType::Ed25519),
+        5 => Ok(SecretType::ChaCha20),
+        _ => Err(VaultError::InvalidEncoding.into()),
+    }
+}
+
+fn usage_to_u8(usage: SecretUsage) -> u8 {
+    match usage {
+        SecretUsage::Any => 0,
+        SecretUsage::Sign => 1,
+        SecretUsage::Derive => 2,
+    }
+}
+
+fn usage_from_u8(usage: u8) -> ockam_core::Result<SecretUsage> {
+    match usage {
+        0 => Ok(SecretUsage::Any),
+        1 => Ok(SecretUsage::Sign),
+        2 => Ok(SecretUsage::Derive),
+        _ => Err(VaultError::InvalidEncoding.into()),
+    }
+}
+
+/// Append bytes prefixed with their length
+pub(crate) fn put_bytes(output: &mut Vec<u8>, bytes: &[u8]) -> ockam_core::Result<()> {
+    let length: u16 = bytes
+        .len()
+        .try_into()
+        .map_err(|_| VaultError::InvalidEncoding)?;
+    output.extend_from_slice(&length.to_be_bytes());
+    output.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Append attributes, but their length which is the one of the secret
+pub(crate) fn put_attributes(output: &mut Vec<u8>, attributes: SecretAttributes) {
+    output.push(stype_to_u8(attributes.stype()));
+    output.push(match attributes.persistence() {
+        SecretPersistence::Ephemeral => 0,
+        SecretPersistence::Persistent => 1,
+    });
+    output.push(usage_to_u8(attributes.usage()));
+    output.push(attributes.exportable() as u8);
+}
+
+/// Reads encoded fields, failing on truncated input
+pub(crate) struct Reader<'a> {
+    pub(crate) input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    pub(crate) fn take(&mut self, length: usize) -> ockam_core::Result<&'a [u8]> {
+        if self.input.len() < length {
+            return Err(VaultError::InvalidEncoding.into());
+        }
+        let (bytes, rest) = self.input.split_at(length);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> ockam_core::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> ockam_core::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> ockam_core::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> ockam_core::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn bytes(&mut self) -> ockam_core::Result<&'a [u8]> {
+        let length = self.u16()? as usize;
+        self.take(length)
+    }
+
+    /// Read attributes written by [`put_attributes`]
+    pub(crate) fn attributes(&mut self, length: usize) -> ockam_core::Result<SecretAttributes> {
+        let stype = stype_from_u8(self.u8()?)?;
+        let persistence = match self.u8()? {
+            0 => SecretPersistence::Ephemeral,
+            1 => SecretPersistence::Persistent,
+            _ => return Err(VaultError::InvalidEncoding.into()),
+        };
+        let usage = usage_from_u8(self.u8()?)?;
+        let exportable = self.u8()? != 0;
+        Ok(SecretAttributes::new(stype, persistence, length)
+            .with_usage(usage)
+            .with_exportable(exportable))
+    }
+
+    /// Fail unless all the input was read
+    pub(crate) fn finish(&self) -> ockam_core::Result<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(VaultError::InvalidEncoding.into())
+        }
+    }
+}
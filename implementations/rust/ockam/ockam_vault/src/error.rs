@@ -19,6 +19,15 @@ pub enum VaultError {
     InvalidSignature,
     HkdfExpandError,
     SecretNotFound,
+    InvalidChaCha20KeyLength,
+    AeadChaCha20Poly1305Encrypt,
+    AeadChaCha20Poly1305Decrypt,
+    SecretNotExportable,
+    InvalidSecretUsage,
+    InvalidBackup,
+    InvalidEncoding,
+    InvalidSecretShares,
+    InvalidDerivationPath,
 }
 
 impl VaultError {
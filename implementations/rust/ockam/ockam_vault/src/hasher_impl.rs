@@ -2,8 +2,8 @@ use crate::software_vault::SoftwareVault;
 use crate::VaultError;
 use arrayref::array_ref;
 use ockam_vault_core::{
-    Hasher, Secret, SecretAttributes, SecretType, SecretVault, AES128_SECRET_LENGTH,
-    AES256_SECRET_LENGTH,
+    Hasher, Secret, SecretAttributes, SecretType, SecretUsage, SecretVault, AES128_SECRET_LENGTH,
+    AES256_SECRET_LENGTH, CHACHA20_SECRET_LENGTH,
 };
 use sha2::{Digest, Sha256};
 
@@ -25,7 +25,7 @@ impl Hasher for SoftwareVault {
     ) -> ockam_core::Result<Vec<Secret>> {
         let ikm: ockam_core::Result<&[u8]> = match ikm {
             Some(ikm) => {
-                let ikm = self.get_entry(ikm)?;
+                let ikm = self.get_entry_for(ikm, SecretUsage::Derive)?;
                 if ikm.key_attributes().stype() == SecretType::Buffer {
                     Ok(ikm.key().as_ref())
                 } else {
@@ -37,7 +37,7 @@ impl Hasher for SoftwareVault {
 
         let ikm = ikm?;
 
-        let salt = self.get_entry(salt)?;
+        let salt = self.get_entry_for(salt, SecretUsage::Derive)?;
 
         if salt.key_attributes().stype() != SecretType::Buffer {
             return Err(VaultError::InvalidKeyType.into());
@@ -64,6 +64,10 @@ impl Hasher for SoftwareVault {
                 if length != AES256_SECRET_LENGTH && length != AES128_SECRET_LENGTH {
                     return Err(VaultError::InvalidAesKeyLength.into());
                 }
+            } else if attributes.stype() == SecretType::ChaCha20 {
+                if length != CHACHA20_SECRET_LENGTH {
+                    return Err(VaultError::InvalidChaCha20KeyLength.into());
+                }
             } else if attributes.stype() != SecretType::Buffer {
                 return Err(VaultError::InvalidHkdfOutputType.into());
             }
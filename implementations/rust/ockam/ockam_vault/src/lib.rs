@@ -16,10 +16,16 @@
 pub extern crate ockam_vault_core;
 
 mod asymmetric_impl;
+mod backup;
+mod encoding;
 mod error;
 mod hasher_impl;
 mod key_id_impl;
+mod recipient;
+mod secret_derivation_impl;
 mod secret_impl;
+mod secret_sharing_impl;
+mod shamir;
 mod signer_impl;
 mod software_vault;
 mod symmetric_impl;
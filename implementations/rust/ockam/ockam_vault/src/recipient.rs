@@ -0,0 +1,110 @@
+use crate::encoding::Reader;
+use crate::{SoftwareVault, VaultError};
+use ockam_vault_core::{
+    AsymmetricVault, Buffer, Hasher, PublicKey, Secret, SecretAttributes, SecretPersistence,
+    SecretType, SecretVault, SymmetricVault, AES256_SECRET_LENGTH, CURVE25519_PUBLIC_LENGTH,
+    CURVE25519_SECRET_LENGTH,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+pub(crate) const AES_GCM_NONCE_LENGTH: usize = 12;
+/// HKDF salt of the key agreed with a recipient
+const RECIPIENT_SALT: &[u8] = b"OCKAM_VAULT_BACKUP";
+
+pub(crate) fn aes_attributes() -> SecretAttributes {
+    SecretAttributes::new(
+        SecretType::Aes,
+        SecretPersistence::Ephemeral,
+        AES256_SECRET_LENGTH,
+    )
+}
+
+/// Agree on a key with ECDH and HKDF-SHA256 in `vault`, binding it
+/// to the ephemeral public key of the sender
+fn recipient_key<V: AsymmetricVault + Hasher + SecretVault>(
+    vault: &mut V,
+    secret: &Secret,
+    peer_public_key: &[u8],
+    ephemeral_public_key: &[u8],
+) -> ockam_core::Result<Secret> {
+    let shared = vault.ec_diffie_hellman(secret, peer_public_key)?;
+    let salt = vault.secret_import(
+        RECIPIENT_SALT,
+        SecretAttributes::new(
+            SecretType::Buffer,
+            SecretPersistence::Ephemeral,
+            RECIPIENT_SALT.len(),
+        ),
+    )?;
+    let keys = vault.hkdf_sha256(
+        &salt,
+        ephemeral_public_key,
+        Some(&shared),
+        vec![aes_attributes()],
+    );
+    vault.secret_destroy(salt)?;
+    vault.secret_destroy(shared)?;
+    keys?
+        .pop()
+        .ok_or_else(|| VaultError::HkdfExpandError.into())
+}
+
+/// Encrypt `plaintext` to a Curve25519 public key with AES-256-GCM,
+/// under a key agreed with an ephemeral key.
+///
+/// The output is `header`, the ephemeral public key, the nonce and
+/// the ciphertext, which authenticates what precedes it.
+pub(crate) fn seal_for_recipient(
+    mut header: Vec<u8>,
+    recipient_public_key: &PublicKey,
+    plaintext: &[u8],
+) -> ockam_core::Result<Buffer<u8>> {
+    if recipient_public_key.as_ref().len() != CURVE25519_PUBLIC_LENGTH {
+        return Err(VaultError::InvalidPublicKey.into());
+    }
+    let mut vault = SoftwareVault::new();
+    let ephemeral = vault.secret_generate(SecretAttributes::new(
+        SecretType::Curve25519,
+        SecretPersistence::Ephemeral,
+        CURVE25519_SECRET_LENGTH,
+    ))?;
+    let ephemeral_public_key = vault.secret_public_key_get(&ephemeral)?;
+    let key = recipient_key(
+        &mut vault,
+        &ephemeral,
+        recipient_public_key.as_ref(),
+        ephemeral_public_key.as_ref(),
+    )?;
+
+    let mut nonce = [0u8; AES_GCM_NONCE_LENGTH];
+    OsRng.fill_bytes(&mut nonce);
+    header.extend_from_slice(ephemeral_public_key.as_ref());
+    header.extend_from_slice(&nonce);
+    let ciphertext = vault.aead_aes_gcm_encrypt(&key, plaintext, &nonce, &header)?;
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+/// Decrypt the output of [`seal_for_recipient`] with the recipient
+/// Curve25519 secret held by `vault`, given the length of the header
+pub(crate) fn open_for_recipient<V>(
+    vault: &mut V,
+    recipient: &Secret,
+    sealed: &[u8],
+    header_length: usize,
+) -> ockam_core::Result<Buffer<u8>>
+where
+    V: AsymmetricVault + Hasher + SecretVault + SymmetricVault,
+{
+    let mut reader = Reader::new(sealed);
+    reader.take(header_length)?;
+    let ephemeral_public_key = reader.take(CURVE25519_PUBLIC_LENGTH)?;
+    let nonce = reader.take(AES_GCM_NONCE_LENGTH)?;
+    let aad = &sealed[..sealed.len() - reader.input.len()];
+
+    let key = recipient_key(vault, recipient, ephemeral_public_key, ephemeral_public_key)?;
+    let plaintext = vault.aead_aes_gcm_decrypt(&key, reader.input, nonce, aad);
+    vault.secret_destroy(key)?;
+    plaintext
+}
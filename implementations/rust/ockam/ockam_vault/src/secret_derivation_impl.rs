@@ -0,0 +1,273 @@
+use crate::software_vault::SoftwareVault;
+use crate::VaultError;
+use hmac::{Hmac, Mac, NewMac};
+use ockam_vault_core::{
+    Secret, SecretAttributes, SecretDerivationVault, SecretKey, SecretType, SecretUsage,
+    CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+};
+use sha2::Sha512;
+use zeroize::Zeroize;
+
+/// Indices at or above this are hardened
+const HARDENED: u32 = 0x8000_0000;
+/// Seeds are between 128 and 512 bits long, as in BIP-0032
+const SEED_LENGTHS: core::ops::RangeInclusive<usize> = 16..=64;
+
+/// Parse a path of hardened indices such as `m/0'/1'`, where `H` may
+/// be used instead of `'`
+fn parse_path(path: &str) -> ockam_core::Result<Vec<u32>> {
+    let mut components = path.split('/');
+    if components.next() != Some("m") {
+        return Err(VaultError::InvalidDerivationPath.into());
+    }
+    components
+        .map(|component| {
+            let index = component
+                .strip_suffix('\'')
+                .or_else(|| component.strip_suffix('H'))
+                .filter(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+                .ok_or(VaultError::InvalidDerivationPath)?;
+            match index.parse::<u32>() {
+                Ok(index) if index < HARDENED => Ok(index | HARDENED),
+                _ => Err(VaultError::InvalidDerivationPath.into()),
+            }
+        })
+        .collect()
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    // HMAC takes keys of any length
+    let mut mac = Hmac::<Sha512>::new_varkey(key).unwrap();
+    for data in data {
+        mac.update(data);
+    }
+    let mut output = mac.finalize().into_bytes();
+    let mut node = [0u8; 64];
+    node.copy_from_slice(&output);
+    output.as_mut_slice().zeroize();
+    node
+}
+
+/// SLIP-0010 derivation of the private key at `path`, which only has
+/// hardened indices, for `curve` given by its master key label
+fn slip10_derive(curve: &[u8], seed: &[u8], path: &[u32]) -> SecretKey {
+    // The first half of a node is its key, the second half its chain code
+    let mut node = hmac_sha512(curve, &[seed]);
+    for index in path {
+        let (key, chain_code) = node.split_at(32);
+        let child = hmac_sha512(chain_code, &[&[0u8], key, &index.to_be_bytes()]);
+        node.zeroize();
+        node = child;
+    }
+    let key = SecretKey::new(node[..32].to_vec());
+    node.zeroize();
+    key
+}
+
+impl SecretDerivationVault for SoftwareVault {
+    /// Derive a Curve25519 or Ed25519 secret from a Buffer seed of 16
+    /// to 64 bytes. Derived secrets are found by key id like generated
+    /// ones, and can't be exported if the seed can't
+    fn secret_derive(
+        &mut self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> ockam_core::Result<Secret> {
+        let (curve, length): (&[u8], _) = match attributes.stype() {
+            SecretType::Curve25519 => (b"curve25519 seed", CURVE25519_SECRET_LENGTH),
+            SecretType::Ed25519 => (b"ed25519 seed", ED25519_SECRET_LENGTH),
+            SecretType::Buffer | SecretType::Aes | SecretType::ChaCha20 | SecretType::P256 => {
+                return Err(VaultError::InvalidKeyType.into())
+            }
+        };
+        if attributes.length() != length {
+            return Err(VaultError::InvalidPrivateKeyLen.into());
+        }
+        let path = parse_path(path)?;
+
+        let entry = self.get_entry_for(seed, SecretUsage::Derive)?;
+        if entry.key_attributes().stype() != SecretType::Buffer {
+            return Err(VaultError::InvalidKeyType.into());
+        }
+        if !SEED_LENGTHS.contains(&entry.key().as_ref().len()) {
+            return Err(VaultError::InvalidPrivateKeyLen.into());
+        }
+        let key = slip10_derive(curve, entry.key().as_ref(), &path);
+        let exportable = attributes.exportable() && entry.key_attributes().exportable();
+
+        self.secret_import_with_key_id(key, attributes.with_exportable(exportable))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_path;
+    use crate::{SoftwareVault, VaultError};
+    use ockam_core::hex::{decode, encode};
+    use ockam_vault_core::{
+        KeyIdVault, Secret, SecretAttributes, SecretDerivationVault, SecretPersistence, SecretType,
+        SecretUsage, SecretVault, CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+    };
+
+    fn seed(vault: &mut SoftwareVault, seed: &str) -> Secret {
+        let seed = decode(seed).unwrap();
+        vault
+            .secret_import(
+                &seed,
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, seed.len()),
+            )
+            .unwrap()
+    }
+
+    fn derive(vault: &mut SoftwareVault, seed: &Secret, path: &str, stype: SecretType) -> String {
+        let attributes = SecretAttributes::new(stype, SecretPersistence::Persistent, 32);
+        let secret = vault.secret_derive(seed, path, attributes).unwrap();
+        encode(vault.secret_export(&secret).unwrap().as_ref())
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(parse_path("m").unwrap(), Vec::<u32>::new());
+        assert_eq!(
+            parse_path("m/0'/1H/2147483647'").unwrap(),
+            vec![0x8000_0000, 0x8000_0001, 0xffff_ffff]
+        );
+        for path in &[
+            "",
+            "0'",
+            "m/",
+            "m/0",
+            "m/'",
+            "m/+1'",
+            "m/2147483648'",
+            "M/0'",
+        ] {
+            assert!(parse_path(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn slip10_test_vector_1() {
+        let mut vault = SoftwareVault::default();
+        let seed = seed(&mut vault, "000102030405060708090a0b0c0d0e0f");
+
+        // SLIP-0010, test vector 1 for ed25519
+        let vectors = [
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+            ),
+            (
+                "m/0'/1'/2'/2'/1000000000'",
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+            ),
+        ];
+        for (path, key) in &vectors {
+            assert_eq!(derive(&mut vault, &seed, path, SecretType::Ed25519), *key);
+        }
+
+        // SLIP-0010, test vector 1 for curve25519
+        assert_eq!(
+            derive(&mut vault, &seed, "m/0'/1'", SecretType::Curve25519),
+            "a95f97cfc1a61dd833b882c89d36a78a030ea6b2fbe3ae2a70e4f1fc9008d6b1"
+        );
+    }
+
+    #[test]
+    fn secret_derive() {
+        let mut vault = SoftwareVault::default();
+        let seed = seed(&mut vault, "fffcf9f6f3f0edeae7e4e1dedbd8d5d2");
+        let attributes = SecretAttributes::new(
+            SecretType::Curve25519,
+            SecretPersistence::Persistent,
+            CURVE25519_SECRET_LENGTH,
+        );
+
+        // Derived keys are found by key id
+        let secret = vault.secret_derive(&seed, "m/1'", attributes).unwrap();
+        let public_key = vault.secret_public_key_get(&secret).unwrap();
+        let key_id = vault.compute_key_id_for_public_key(&public_key).unwrap();
+        assert_eq!(
+            vault.get_secret_by_key_id(&key_id).unwrap().index(),
+            secret.index()
+        );
+        assert_eq!(vault.secret_attributes_get(&secret).unwrap(), attributes);
+
+        let err = vault.secret_derive(&seed, "m/1", attributes).unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidDerivationPath as u32
+        );
+        let p256 = SecretAttributes::new(SecretType::P256, SecretPersistence::Persistent, 32);
+        assert!(vault.secret_derive(&seed, "m/1'", p256).is_err());
+        // Only seeds can be derived from
+        assert!(vault.secret_derive(&secret, "m/1'", attributes).is_err());
+
+        let short_seed = vault
+            .secret_import(
+                &[0u8; 8],
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 8),
+            )
+            .unwrap();
+        assert!(vault
+            .secret_derive(&short_seed, "m/1'", attributes)
+            .is_err());
+
+        let signing_seed = vault
+            .secret_import(
+                &[0u8; 32],
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 32)
+                    .with_usage(SecretUsage::Sign),
+            )
+            .unwrap();
+        let err = vault
+            .secret_derive(
+                &signing_seed,
+                "m/1'",
+                SecretAttributes::new(
+                    SecretType::Ed25519,
+                    SecretPersistence::Persistent,
+                    ED25519_SECRET_LENGTH,
+                ),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidSecretUsage as u32
+        );
+    }
+
+    #[test]
+    fn secret_derive_not_exportable() {
+        let mut vault = SoftwareVault::default();
+        let seed = vault
+            .secret_import(
+                &[0u8; 32],
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 32)
+                    .with_exportable(false),
+            )
+            .unwrap();
+        let attributes = SecretAttributes::new(
+            SecretType::Ed25519,
+            SecretPersistence::Persistent,
+            ED25519_SECRET_LENGTH,
+        );
+
+        // Keys derived from a seed which can't be exported can't be either
+        let secret = vault.secret_derive(&seed, "m/1'", attributes).unwrap();
+        assert_eq!(
+            vault.secret_attributes_get(&secret).unwrap(),
+            attributes.with_exportable(false)
+        );
+        let err = vault.secret_export(&secret).unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::SecretNotExportable as u32
+        );
+    }
+}
@@ -3,14 +3,16 @@ use crate::VaultError;
 use arrayref::array_ref;
 use ockam_vault_core::{
     KeyIdVault, PublicKey, Secret, SecretAttributes, SecretKey, SecretPersistence, SecretType,
-    SecretVault, AES128_SECRET_LENGTH, AES256_SECRET_LENGTH, CURVE25519_SECRET_LENGTH,
+    SecretVault, AES128_SECRET_LENGTH, AES256_SECRET_LENGTH, CHACHA20_SECRET_LENGTH,
+    CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH, P256_SECRET_LENGTH,
 };
+use p256::elliptic_curve::sec1::ToEncodedPoint;
 use rand::rngs::OsRng;
 use rand::RngCore;
 use zeroize::Zeroize;
 
 impl SecretVault for SoftwareVault {
-    /// Generate fresh secret. Only Curve25519 and Buffer types are supported
+    /// Generate fresh secret. Only Curve25519, P256, Ed25519, Aes, ChaCha20 and Buffer types are supported
     fn secret_generate(&mut self, attributes: SecretAttributes) -> ockam_core::Result<Secret> {
         let mut rng = OsRng {};
         let (key, key_id) = match attributes.stype() {
@@ -44,8 +46,41 @@ impl SecretVault for SoftwareVault {
                 rng.fill_bytes(&mut key);
                 (SecretKey::new(key), None)
             }
+            SecretType::ChaCha20 => {
+                if attributes.length() != CHACHA20_SECRET_LENGTH {
+                    return Err(VaultError::InvalidChaCha20KeyLength.into());
+                };
+                if attributes.persistence() != SecretPersistence::Ephemeral {
+                    return Err(VaultError::InvalidKeyType.into());
+                };
+                let mut key = vec![0u8; attributes.length()];
+                rng.fill_bytes(&mut key);
+                (SecretKey::new(key), None)
+            }
             SecretType::P256 => {
-                return Err(VaultError::InvalidKeyType.into());
+                let sk = loop {
+                    // Retry the rare byte strings which are not valid scalars
+                    let mut bytes = [0u8; P256_SECRET_LENGTH];
+                    rng.fill_bytes(&mut bytes);
+                    if let Ok(sk) = p256::SecretKey::from_be_bytes(&bytes) {
+                        bytes.zeroize();
+                        break sk;
+                    }
+                };
+                let public = Self::p256_public_key(&sk);
+                let private = SecretKey::new(sk.to_be_bytes().to_vec());
+                let key_id = self.compute_key_id_for_public_key(&public)?;
+
+                (private, Some(key_id))
+            }
+            SecretType::Ed25519 => {
+                let sk = ed25519_dalek::SecretKey::generate(&mut rng);
+                let public = ed25519_dalek::PublicKey::from(&sk);
+                let private = SecretKey::new(sk.as_bytes().to_vec());
+                let key_id = self
+                    .compute_key_id_for_public_key(&PublicKey::new(public.as_bytes().to_vec()))?;
+
+                (private, Some(key_id))
             }
         };
         self.next_id += 1;
@@ -74,23 +109,27 @@ impl SecretVault for SoftwareVault {
     }
 
     fn secret_export(&mut self, context: &Secret) -> ockam_core::Result<SecretKey> {
-        self.get_entry(context).map(|i| i.key().clone())
+        let entry = self.get_entry(context)?;
+        if !entry.key_attributes().exportable() {
+            return Err(VaultError::SecretNotExportable.into());
+        }
+        Ok(entry.key().clone())
     }
 
     fn secret_attributes_get(&mut self, context: &Secret) -> ockam_core::Result<SecretAttributes> {
         self.get_entry(context).map(|i| i.key_attributes())
     }
 
-    /// Extract public key from secret. Only Curve25519 type is supported
+    /// Extract public key from secret. Only Curve25519, P256 and Ed25519 types are supported
     fn secret_public_key_get(&mut self, context: &Secret) -> ockam_core::Result<PublicKey> {
         let entry = self.get_entry(context)?;
 
-        if entry.key().as_ref().len() != CURVE25519_SECRET_LENGTH {
-            return Err(VaultError::InvalidPrivateKeyLen.into());
-        }
-
         match entry.key_attributes().stype() {
             SecretType::Curve25519 => {
+                if entry.key().as_ref().len() != CURVE25519_SECRET_LENGTH {
+                    return Err(VaultError::InvalidPrivateKeyLen.into());
+                }
+
                 let sk = x25519_dalek::StaticSecret::from(*array_ref![
                     entry.key().as_ref(),
                     0,
@@ -99,6 +138,15 @@ impl SecretVault for SoftwareVault {
                 let pk = x25519_dalek::PublicKey::from(&sk);
                 Ok(PublicKey::new(pk.to_bytes().to_vec()))
             }
+            SecretType::P256 => {
+                let sk = Self::p256_secret_key(entry.key().as_ref())?;
+                Ok(Self::p256_public_key(&sk))
+            }
+            SecretType::Ed25519 => {
+                let sk = Self::ed25519_secret_key(entry.key().as_ref())?;
+                let pk = ed25519_dalek::PublicKey::from(&sk);
+                Ok(PublicKey::new(pk.as_bytes().to_vec()))
+            }
             _ => Err(VaultError::InvalidKeyType.into()),
         }
     }
@@ -112,13 +160,58 @@ impl SecretVault for SoftwareVault {
     }
 }
 
+impl SoftwareVault {
+    pub(crate) fn p256_secret_key(key: &[u8]) -> ockam_core::Result<p256::SecretKey> {
+        if key.len() != P256_SECRET_LENGTH {
+            return Err(VaultError::InvalidPrivateKeyLen.into());
+        }
+        p256::SecretKey::from_be_bytes(key).map_err(|_| VaultError::InvalidKeyType.into())
+    }
+
+    pub(crate) fn ed25519_secret_key(key: &[u8]) -> ockam_core::Result<ed25519_dalek::SecretKey> {
+        if key.len() != ED25519_SECRET_LENGTH {
+            return Err(VaultError::InvalidPrivateKeyLen.into());
+        }
+        ed25519_dalek::SecretKey::from_bytes(key).map_err(|_| VaultError::InvalidKeyType.into())
+    }
+
+    /// Import a secret, which is found by key id like a generated one
+    /// if it has a public key
+    pub(crate) fn secret_import_with_key_id(
+        &mut self,
+        key: SecretKey,
+        attributes: SecretAttributes,
+    ) -> ockam_core::Result<Secret> {
+        let secret = self.secret_import(key.as_ref(), attributes)?;
+        match attributes.stype() {
+            SecretType::Curve25519 | SecretType::P256 | SecretType::Ed25519 => {
+                let public_key = self.secret_public_key_get(&secret)?;
+                let key_id = self.compute_key_id_for_public_key(&public_key)?;
+                self.entries.insert(
+                    secret.index(),
+                    VaultEntry::new(Some(key_id), attributes, key),
+                );
+            }
+            SecretType::Buffer | SecretType::Aes | SecretType::ChaCha20 => {}
+        }
+        Ok(secret)
+    }
+
+    /// Uncompressed SEC1 encoding of the public key
+    fn p256_public_key(sk: &p256::SecretKey) -> PublicKey {
+        let point = sk.public_key().to_encoded_point(false);
+        PublicKey::new(point.as_bytes().to_vec())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::SoftwareVault;
     use ockam_core::hex::{decode, encode};
     use ockam_vault_core::{
-        SecretAttributes, SecretPersistence, SecretType, SecretVault, CURVE25519_PUBLIC_LENGTH,
-        CURVE25519_SECRET_LENGTH,
+        AsymmetricVault, SecretAttributes, SecretPersistence, SecretType, SecretUsage, SecretVault,
+        Signer, SymmetricVault, AES256_SECRET_LENGTH, CURVE25519_PUBLIC_LENGTH,
+        CURVE25519_SECRET_LENGTH, P256_PUBLIC_LENGTH, P256_SECRET_LENGTH,
     };
 
     #[test]
@@ -152,11 +245,31 @@ mod tests {
         assert_eq!(vault.next_id, 2);
     }
 
+    #[test]
+    fn new_p256_public_key() {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::P256,
+            SecretPersistence::Ephemeral,
+            P256_SECRET_LENGTH,
+        );
+
+        let secret = vault.secret_generate(attributes).unwrap();
+        let public = vault.secret_public_key_get(&secret).unwrap();
+        assert_eq!(public.as_ref().len(), P256_PUBLIC_LENGTH);
+        assert_eq!(public.as_ref()[0], 0x04);
+    }
+
     #[test]
     fn new_secret_keys() {
         let mut vault = SoftwareVault::default();
 
-        let types = [(SecretType::Curve25519, 32), (SecretType::Buffer, 24)];
+        let types = [
+            (SecretType::Curve25519, 32),
+            (SecretType::P256, 32),
+            (SecretType::Ed25519, 32),
+            (SecretType::Buffer, 24),
+        ];
         for (t, s) in &types {
             let attributes = SecretAttributes::new(*t, SecretPersistence::Ephemeral, *s);
             let res = vault.secret_generate(attributes);
@@ -224,4 +337,63 @@ mod tests {
         let secret = vault.secret_generate(attributes).unwrap();
         assert_eq!(vault.secret_attributes_get(&secret).unwrap(), attributes);
     }
+
+    #[test]
+    fn export_policy() {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::Curve25519,
+            SecretPersistence::Persistent,
+            CURVE25519_SECRET_LENGTH,
+        );
+
+        let secret = vault.secret_generate(attributes).unwrap();
+        assert!(vault.secret_export(&secret).is_ok());
+
+        let attributes = attributes.with_exportable(false);
+        let secret = vault.secret_generate(attributes).unwrap();
+        assert!(vault.secret_export(&secret).is_err());
+        assert_eq!(vault.secret_attributes_get(&secret).unwrap(), attributes);
+        // Non-exportable secrets are still usable
+        assert!(vault.secret_public_key_get(&secret).is_ok());
+        assert!(vault.sign(&secret, b"hello").is_ok());
+    }
+
+    #[test]
+    fn usage_policy() {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::Curve25519,
+            SecretPersistence::Ephemeral,
+            CURVE25519_SECRET_LENGTH,
+        );
+        let peer = vault.secret_generate(attributes).unwrap();
+        let peer = vault.secret_public_key_get(&peer).unwrap();
+
+        let sign_only = vault
+            .secret_generate(attributes.with_usage(SecretUsage::Sign))
+            .unwrap();
+        assert!(vault.sign(&sign_only, b"hello").is_ok());
+        assert!(vault.ec_diffie_hellman(&sign_only, peer.as_ref()).is_err());
+
+        let derive_only = vault
+            .secret_generate(attributes.with_usage(SecretUsage::Derive))
+            .unwrap();
+        assert!(vault.sign(&derive_only, b"hello").is_err());
+        assert!(vault.ec_diffie_hellman(&derive_only, peer.as_ref()).is_ok());
+
+        let key = vault
+            .secret_generate(
+                SecretAttributes::new(
+                    SecretType::Aes,
+                    SecretPersistence::Ephemeral,
+                    AES256_SECRET_LENGTH,
+                )
+                .with_usage(SecretUsage::Derive),
+            )
+            .unwrap();
+        assert!(vault
+            .aead_aes_gcm_encrypt(&key, b"hello", &[0u8; 12], b"")
+            .is_err());
+    }
 }
@@ -0,0 +1,295 @@
+use crate::encoding::{put_attributes, put_bytes, Reader};
+use crate::recipient::{open_for_recipient, seal_for_recipient};
+use crate::shamir;
+use crate::software_vault::SoftwareVault;
+use crate::VaultError;
+use ockam_vault_core::{
+    Buffer, PublicKey, Secret, SecretAttributes, SecretKey, SecretSharingVault, SmallBuffer,
+};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroize;
+
+/// Identifies encrypted shares, and their version
+const SHARE_HEADER: &[u8; 5] = b"OCKS\x01";
+/// Random identifier of the shares of one split
+const SPLIT_ID_LENGTH: usize = 16;
+
+/// A decrypted share
+struct Share<'a> {
+    split_id: &'a [u8],
+    threshold: u8,
+    point: u8,
+    value: &'a [u8],
+    attributes: SecretAttributes,
+}
+
+impl<'a> Share<'a> {
+    fn parse(plaintext: &'a [u8]) -> ockam_core::Result<Self> {
+        let mut reader = Reader::new(plaintext);
+        let split_id = reader.take(SPLIT_ID_LENGTH)?;
+        let threshold = reader.u8()?;
+        let point = reader.u8()?;
+        let value = reader.bytes()?;
+        let attributes = reader.attributes(value.len())?;
+        reader.finish()?;
+        Ok(Self {
+            split_id,
+            threshold,
+            point,
+            value,
+            attributes,
+        })
+    }
+}
+
+impl SoftwareVault {
+    /// Decrypt shares encrypted to the `recipient` secret and combine
+    /// them. The key never leaves the vault, since shares of secrets
+    /// which can't be exported may be combined as well.
+    fn secret_shares_combine(
+        &mut self,
+        recipient: &Secret,
+        shares: &[Buffer<u8>],
+    ) -> ockam_core::Result<(SecretKey, SecretAttributes)> {
+        let mut plaintexts = Vec::with_capacity(shares.len());
+        let combined = self.combine_shares(recipient, shares, &mut plaintexts);
+        for plaintext in plaintexts.iter_mut() {
+            plaintext.zeroize();
+        }
+        combined
+    }
+
+    fn combine_shares(
+        &mut self,
+        recipient: &Secret,
+        shares: &[Buffer<u8>],
+        plaintexts: &mut Vec<Buffer<u8>>,
+    ) -> ockam_core::Result<(SecretKey, SecretAttributes)> {
+        for share in shares {
+            plaintexts.push(open_for_recipient(
+                self,
+                recipient,
+                share,
+                SHARE_HEADER.len(),
+            )?);
+        }
+        let shares = plaintexts
+            .iter()
+            .map(|plaintext| Share::parse(plaintext))
+            .collect::<ockam_core::Result<Vec<_>>>()?;
+
+        let first = shares.first().ok_or(VaultError::InvalidSecretShares)?;
+        let mut points: Vec<(u8, &[u8])> = Vec::new();
+        for share in &shares {
+            if share.split_id != first.split_id
+                || share.threshold != first.threshold
+                || share.attributes != first.attributes
+                || share.point == 0
+                || points.iter().any(|(point, _)| *point == share.point)
+            {
+                return Err(VaultError::InvalidSecretShares.into());
+            }
+            points.push((share.point, share.value));
+        }
+        if points.len() < first.threshold as usize {
+            return Err(VaultError::InvalidSecretShares.into());
+        }
+        points.truncate(first.threshold as usize);
+
+        Ok((SecretKey::new(shamir::combine(&points)), first.attributes))
+    }
+}
+
+impl SecretSharingVault for SoftwareVault {
+    /// Split a secret into shares encrypted to Curve25519 public keys.
+    ///
+    /// Only secrets which can be exported may be split, since the
+    /// holders of the shares learn the secret.
+    fn secret_split(
+        &mut self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> ockam_core::Result<SmallBuffer<Buffer<u8>>> {
+        if threshold == 0 || guardians.len() < threshold as usize || guardians.len() > 255 {
+            return Err(VaultError::InvalidSecretShares.into());
+        }
+        let entry = self.get_entry(context)?;
+        if !entry.key_attributes().exportable() {
+            return Err(VaultError::SecretNotExportable.into());
+        }
+        let mut values = shamir::split(entry.key().as_ref(), threshold, guardians.len() as u8);
+
+        let mut split_id = [0u8; SPLIT_ID_LENGTH];
+        OsRng.fill_bytes(&mut split_id);
+        let mut shares = SmallBuffer::new();
+        for (i, (value, guardian)) in values.iter().zip(guardians).enumerate() {
+            let mut plaintext = split_id.to_vec();
+            plaintext.push(threshold);
+            plaintext.push(i as u8 + 1);
+            put_bytes(&mut plaintext, value)?;
+            put_attributes(&mut plaintext, entry.key_attributes());
+            let share = seal_for_recipient(SHARE_HEADER.to_vec(), guardian, &plaintext);
+            plaintext.zeroize();
+            shares.push(share?);
+        }
+        for value in values.iter_mut() {
+            value.zeroize();
+        }
+        Ok(shares)
+    }
+
+    fn secret_share_reencrypt(
+        &mut self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let mut plaintext = open_for_recipient(self, guardian, share, SHARE_HEADER.len())?;
+        let share = seal_for_recipient(SHARE_HEADER.to_vec(), recipient, &plaintext);
+        plaintext.zeroize();
+        share
+    }
+
+    /// Recover a secret, which is found again by key id like a
+    /// generated one
+    fn secret_recover(
+        &mut self,
+        recipient: &Secret,
+        shares: &[Buffer<u8>],
+    ) -> ockam_core::Result<Secret> {
+        let (key, attributes) = self.secret_shares_combine(recipient, shares)?;
+        self.secret_import_with_key_id(key, attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SoftwareVault, VaultError};
+    use ockam_vault_core::{
+        KeyIdVault, PublicKey, Secret, SecretAttributes, SecretPersistence, SecretSharingVault,
+        SecretType, SecretUsage, SecretVault, Signer, Verifier, CURVE25519_SECRET_LENGTH,
+        P256_SECRET_LENGTH,
+    };
+
+    fn recovery_key(vault: &mut SoftwareVault) -> (Secret, PublicKey) {
+        let secret = vault
+            .secret_generate(SecretAttributes::new(
+                SecretType::Curve25519,
+                SecretPersistence::Persistent,
+                CURVE25519_SECRET_LENGTH,
+            ))
+            .unwrap();
+        let public_key = vault.secret_public_key_get(&secret).unwrap();
+        (secret, public_key)
+    }
+
+    #[test]
+    fn secret_split_recover() {
+        let mut vault = SoftwareVault::default();
+        let attributes = SecretAttributes::new(
+            SecretType::P256,
+            SecretPersistence::Persistent,
+            P256_SECRET_LENGTH,
+        )
+        .with_usage(SecretUsage::Sign);
+        let secret = vault.secret_generate(attributes).unwrap();
+        let public_key = vault.secret_public_key_get(&secret).unwrap();
+
+        let mut guardians: Vec<SoftwareVault> = (0..3).map(|_| SoftwareVault::default()).collect();
+        let keys: Vec<_> = guardians.iter_mut().map(recovery_key).collect();
+        let guardian_keys: Vec<_> = keys.iter().map(|(_, public)| public.clone()).collect();
+        let shares = vault.secret_split(&secret, 2, &guardian_keys).unwrap();
+        assert_eq!(shares.len(), 3);
+
+        // Guardians re-encrypt their shares to a new vault
+        let mut recovered_vault = SoftwareVault::default();
+        let (recipient, recipient_public) = recovery_key(&mut recovered_vault);
+        let reencrypt = |i: usize, guardians: &mut Vec<SoftwareVault>| {
+            guardians[i]
+                .secret_share_reencrypt(&keys[i].0, &shares[i], &recipient_public)
+                .unwrap()
+        };
+        let first = reencrypt(2, &mut guardians);
+        let second = reencrypt(0, &mut guardians);
+
+        let err = recovered_vault
+            .secret_recover(&recipient, std::slice::from_ref(&first))
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidSecretShares as u32
+        );
+        let err = recovered_vault
+            .secret_recover(&recipient, &[first.clone(), first.clone()])
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::InvalidSecretShares as u32
+        );
+        // Shares which weren't re-encrypted can't be read
+        assert!(recovered_vault
+            .secret_recover(&recipient, &[first.clone(), shares[1].clone()])
+            .is_err());
+
+        let recovered = recovered_vault
+            .secret_recover(&recipient, &[first, second])
+            .unwrap();
+        assert_eq!(
+            recovered_vault.secret_attributes_get(&recovered).unwrap(),
+            attributes
+        );
+        let key_id = vault.compute_key_id_for_public_key(&public_key).unwrap();
+        assert_eq!(
+            recovered_vault
+                .get_secret_by_key_id(&key_id)
+                .unwrap()
+                .index(),
+            recovered.index()
+        );
+        let signature = recovered_vault.sign(&recovered, b"data").unwrap();
+        vault
+            .verify(&signature, public_key.as_ref(), attributes.stype(), b"data")
+            .unwrap();
+    }
+
+    #[test]
+    fn secret_split_not_exportable() {
+        let mut vault = SoftwareVault::default();
+        let secret = vault
+            .secret_generate(
+                SecretAttributes::new(
+                    SecretType::Curve25519,
+                    SecretPersistence::Persistent,
+                    CURVE25519_SECRET_LENGTH,
+                )
+                .with_exportable(false),
+            )
+            .unwrap();
+
+        // Shares would reveal the secret to the holder of the guardian key
+        let (_, guardian) = recovery_key(&mut vault);
+        let err = vault.secret_split(&secret, 1, &[guardian]).unwrap_err();
+        assert_eq!(
+            err.code(),
+            VaultError::DOMAIN_CODE + VaultError::SecretNotExportable as u32
+        );
+    }
+
+    #[test]
+    fn secret_split_threshold() {
+        let mut vault = SoftwareVault::default();
+        let secret = vault
+            .secret_import(
+                b"hello world!",
+                SecretAttributes::new(SecretType::Buffer, SecretPersistence::Ephemeral, 12),
+            )
+            .unwrap();
+        let (_, guardian) = recovery_key(&mut vault);
+        assert!(vault
+            .secret_split(&secret, 0, std::slice::from_ref(&guardian))
+            .is_err());
+        assert!(vault.secret_split(&secret, 2, &[guardian]).is_err());
+    }
+}
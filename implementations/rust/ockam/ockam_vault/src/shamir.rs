@@ -0,0 +1,107 @@
+//! Shamir's secret sharing over GF(256), byte by byte.
+//!
+//! Arithmetic uses the AES field polynomial and avoids branches and
+//! table lookups on secret values.
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroize;
+
+/// Multiply in GF(256) modulo x^8 + x^4 + x^3 + x + 1
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = a >> 7;
+        a = (a << 1) ^ (0x1b & 0u8.wrapping_sub(carry));
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverse in GF(256), as a^254
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut power = a;
+    for _ in 0..7 {
+        power = gf_mul(power, power);
+        result = gf_mul(result, power);
+    }
+    result
+}
+
+/// Split `secret` into `count` shares, any `threshold` of which
+/// recover it. Share `i` is the polynomial evaluated at `i + 1`.
+pub(crate) fn split(secret: &[u8], threshold: u8, count: u8) -> Vec<Vec<u8>> {
+    let mut shares = vec![Vec::with_capacity(secret.len()); count as usize];
+    let mut coefficients = vec![0u8; threshold as usize];
+    for byte in secret {
+        coefficients[0] = *byte;
+        OsRng.fill_bytes(&mut coefficients[1..]);
+        for (i, share) in shares.iter_mut().enumerate() {
+            let x = i as u8 + 1;
+            // Horner's method, from the highest degree
+            let y = coefficients
+                .iter()
+                .rev()
+                .fold(0u8, |y, coefficient| gf_mul(y, x) ^ coefficient);
+            share.push(y);
+        }
+    }
+    coefficients.zeroize();
+    shares
+}
+
+/// Recover the secret from shares given with their point, by
+/// Lagrange interpolation at zero. Points must be distinct and not 0.
+pub(crate) fn combine(shares: &[(u8, &[u8])]) -> Vec<u8> {
+    let length = shares.first().map_or(0, |(_, value)| value.len());
+    let mut secret = vec![0u8; length];
+    for (i, (xi, yi)) in shares.iter().enumerate() {
+        let basis = shares
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1u8, |basis, (_, (xj, _))| {
+                gf_mul(basis, gf_mul(*xj, gf_inv(xj ^ xi)))
+            });
+        for (byte, y) in secret.iter_mut().zip(yi.iter()) {
+            *byte ^= gf_mul(*y, basis);
+        }
+    }
+    secret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine, gf_inv, gf_mul, split};
+
+    #[test]
+    fn field() {
+        // FIPS-197, section 4.2
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn split_combine() {
+        let secret = b"the secret of a root key, 32 b!!";
+        let shares = split(secret, 3, 5);
+        assert_eq!(shares.len(), 5);
+
+        for subset in &[[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let subset: Vec<_> = subset
+                .iter()
+                .map(|i| (*i as u8 + 1, shares[*i].as_slice()))
+                .collect();
+            assert_eq!(combine(&subset), secret);
+        }
+        let two: Vec<_> = (0..2)
+            .map(|i| (i as u8 + 1, shares[i].as_slice()))
+            .collect();
+        assert_ne!(combine(&two), secret);
+    }
+}
@@ -2,13 +2,16 @@ use crate::software_vault::SoftwareVault;
 use crate::xeddsa::XEddsaSigner;
 use crate::VaultError;
 use arrayref::array_ref;
-use ockam_vault_core::{Secret, SecretType, Signer, CURVE25519_SECRET_LENGTH};
+use ockam_vault_core::{Secret, SecretType, SecretUsage, Signer, CURVE25519_SECRET_LENGTH};
+use p256::ecdsa::{signature::Signer as _, Signature, SigningKey};
 use rand::{thread_rng, RngCore};
 
 impl Signer for SoftwareVault {
-    /// Sign data with xeddsa algorithm. Only curve25519 is supported.
+    /// Sign data with xeddsa algorithm for curve25519 keys, with
+    /// ECDSA (SHA-256) for P256 keys, and with Ed25519 (RFC 8032) for
+    /// Ed25519 keys.
     fn sign(&mut self, secret_key: &Secret, data: &[u8]) -> ockam_core::Result<[u8; 64]> {
-        let entry = self.get_entry(secret_key)?;
+        let entry = self.get_entry_for(secret_key, SecretUsage::Sign)?;
         let key = entry.key().as_ref();
         match entry.key_attributes().stype() {
             SecretType::Curve25519 if key.len() == CURVE25519_SECRET_LENGTH => {
@@ -20,6 +23,17 @@ impl Signer for SoftwareVault {
                         .sign(data.as_ref(), &nonce);
                 Ok(sig)
             }
+            SecretType::P256 => {
+                let sk = Self::p256_secret_key(key)?;
+                let sig: Signature = SigningKey::from(sk).sign(data);
+                Ok(*array_ref!(sig.as_ref(), 0, 64))
+            }
+            SecretType::Ed25519 => {
+                let sk = Self::ed25519_secret_key(key)?;
+                let pk = ed25519_dalek::PublicKey::from(&sk);
+                let sig = ed25519_dalek::ExpandedSecretKey::from(&sk).sign(data, &pk);
+                Ok(sig.to_bytes())
+            }
             _ => Err(VaultError::InvalidKeyType.into()),
         }
     }
@@ -28,26 +42,61 @@ impl Signer for SoftwareVault {
 #[cfg(test)]
 mod tests {
     use crate::SoftwareVault;
+    use ockam_core::hex::{decode, encode};
     use ockam_vault_core::{
         SecretAttributes, SecretPersistence, SecretType, SecretVault, Signer, Verifier,
-        CURVE25519_SECRET_LENGTH,
+        CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH, P256_SECRET_LENGTH,
     };
 
     #[test]
     fn sign() {
+        let mut vault = SoftwareVault::default();
+        let types = [
+            (SecretType::Curve25519, CURVE25519_SECRET_LENGTH),
+            (SecretType::P256, P256_SECRET_LENGTH),
+            (SecretType::Ed25519, ED25519_SECRET_LENGTH),
+        ];
+        for (t, s) in &types {
+            let secret = vault
+                .secret_generate(SecretAttributes::new(*t, SecretPersistence::Ephemeral, *s))
+                .unwrap();
+            let res = vault.sign(&secret, b"hello world!");
+            assert!(res.is_ok());
+            let pubkey = vault.secret_public_key_get(&secret).unwrap();
+            let signature = res.unwrap();
+            let res = vault.verify(&signature, pubkey.as_ref(), *t, b"hello world!");
+            assert!(res.is_ok());
+            let res = vault.verify(&signature, pubkey.as_ref(), *t, b"hello world?");
+            assert!(res.is_err());
+        }
+    }
+
+    #[test]
+    fn sign_ed25519_rfc8032() {
+        // RFC 8032, section 7.1, TEST 2
         let mut vault = SoftwareVault::default();
         let secret = vault
-            .secret_generate(SecretAttributes::new(
-                SecretType::Curve25519,
-                SecretPersistence::Ephemeral,
-                CURVE25519_SECRET_LENGTH,
-            ))
+            .secret_import(
+                &decode("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb")
+                    .unwrap(),
+                SecretAttributes::new(
+                    SecretType::Ed25519,
+                    SecretPersistence::Ephemeral,
+                    ED25519_SECRET_LENGTH,
+                ),
+            )
             .unwrap();
-        let res = vault.sign(&secret, b"hello world!");
-        assert!(res.is_ok());
-        let pubkey = vault.secret_public_key_get(&secret).unwrap();
-        let signature = res.unwrap();
-        let res = vault.verify(&signature, pubkey.as_ref(), b"hello world!");
-        assert!(res.is_ok());
+        let public_key = vault.secret_public_key_get(&secret).unwrap();
+        assert_eq!(
+            encode(public_key.as_ref()),
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"
+        );
+
+        let signature = vault.sign(&secret, &[0x72]).unwrap();
+        assert_eq!(
+            encode(&signature[..]),
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+        );
     }
 }
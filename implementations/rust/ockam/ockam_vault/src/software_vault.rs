@@ -1,6 +1,6 @@
 use crate::VaultError;
 use ockam_vault_core::zdrop_impl;
-use ockam_vault_core::{Secret, SecretAttributes, SecretKey};
+use ockam_vault_core::{Secret, SecretAttributes, SecretKey, SecretUsage};
 use std::collections::BTreeMap;
 use zeroize::Zeroize;
 
@@ -26,7 +26,7 @@ use zeroize::Zeroize;
 ///     let data = "Very important stuff".as_bytes();
 ///
 ///     let signature = vault.sign(&secret, data)?;
-///     vault.verify(&signature, public.as_ref(), data)
+///     vault.verify(&signature, public.as_ref(), SecretType::Curve25519, data)
 /// }
 /// ```
 #[derive(Debug)]
@@ -56,6 +56,20 @@ impl SoftwareVault {
             .get(&context.index())
             .ok_or_else(|| VaultError::EntryNotFound.into())
     }
+
+    /// Get the entry of a secret that is going to be used for `usage`
+    pub(crate) fn get_entry_for(
+        &self,
+        context: &Secret,
+        usage: SecretUsage,
+    ) -> ockam_core::Result<&VaultEntry> {
+        let entry = self.get_entry(context)?;
+        if entry.key_attributes().usage().permits(usage) {
+            Ok(entry)
+        } else {
+            Err(VaultError::InvalidSecretUsage.into())
+        }
+    }
 }
 
 impl Zeroize for SoftwareVault {
@@ -1,10 +1,17 @@
 use crate::{SoftwareVault, VaultError};
 use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead, Payload};
 use aes_gcm::{Aes128Gcm, Aes256Gcm};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
 use ockam_vault_core::{
-    Buffer, Secret, SecretType, SymmetricVault, AES128_SECRET_LENGTH, AES256_SECRET_LENGTH,
+    Buffer, Secret, SecretType, SecretUsage, SymmetricVault, AES128_SECRET_LENGTH,
+    AES256_SECRET_LENGTH, CHACHA20_SECRET_LENGTH,
 };
 
+/// ChaCha20-Poly1305 nonce length
+const CHACHA20_POLY1305_NONCE_LENGTH: usize = 12;
+/// XChaCha20-Poly1305 nonce length
+const XCHACHA20_POLY1305_NONCE_LENGTH: usize = 24;
+
 macro_rules! encrypt_op_impl {
     ($a:expr,$aad:expr,$nonce:expr,$text:expr,$type:ident,$op:ident) => {{
         let key = GenericArray::from_slice($a.as_ref());
@@ -40,6 +47,29 @@ macro_rules! encrypt_impl {
     }};
 }
 
+macro_rules! chacha_impl {
+    ($entry:expr, $aad:expr, $nonce:expr, $text:expr, $type:ident, $nonce_len:expr, $op:ident, $err:expr) => {{
+        if $entry.key_attributes().stype() != SecretType::ChaCha20 {
+            return Err($err.into());
+        }
+        if $entry.key_attributes().length() != CHACHA20_SECRET_LENGTH {
+            return Err(VaultError::InvalidChaCha20KeyLength.into());
+        }
+        if $nonce.len() != $nonce_len {
+            return Err($err.into());
+        }
+        let cipher = $type::new(GenericArray::from_slice($entry.key().as_ref()));
+        let nonce = GenericArray::from_slice($nonce.as_ref());
+        let payload = Payload {
+            aad: $aad.as_ref(),
+            msg: $text.as_ref(),
+        };
+        cipher
+            .$op(nonce, payload)
+            .map_err(|_| Into::<ockam_core::Error>::into($err))
+    }};
+}
+
 impl SymmetricVault for SoftwareVault {
     fn aead_aes_gcm_encrypt(
         &mut self,
@@ -48,7 +78,7 @@ impl SymmetricVault for SoftwareVault {
         nonce: &[u8],
         aad: &[u8],
     ) -> ockam_core::Result<Buffer<u8>> {
-        let entry = self.get_entry(context)?;
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
 
         encrypt_impl!(
             entry,
@@ -67,7 +97,7 @@ impl SymmetricVault for SoftwareVault {
         nonce: &[u8],
         aad: &[u8],
     ) -> ockam_core::Result<Buffer<u8>> {
-        let entry = self.get_entry(context)?;
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
 
         encrypt_impl!(
             entry,
@@ -78,14 +108,99 @@ impl SymmetricVault for SoftwareVault {
             VaultError::AeadAesGcmDecrypt
         )
     }
+
+    fn aead_chacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
+
+        chacha_impl!(
+            entry,
+            aad,
+            nonce,
+            plaintext,
+            ChaCha20Poly1305,
+            CHACHA20_POLY1305_NONCE_LENGTH,
+            encrypt,
+            VaultError::AeadChaCha20Poly1305Encrypt
+        )
+    }
+
+    fn aead_chacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
+
+        chacha_impl!(
+            entry,
+            aad,
+            nonce,
+            cipher_text,
+            ChaCha20Poly1305,
+            CHACHA20_POLY1305_NONCE_LENGTH,
+            decrypt,
+            VaultError::AeadChaCha20Poly1305Decrypt
+        )
+    }
+
+    fn aead_xchacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
+
+        chacha_impl!(
+            entry,
+            aad,
+            nonce,
+            plaintext,
+            XChaCha20Poly1305,
+            XCHACHA20_POLY1305_NONCE_LENGTH,
+            encrypt,
+            VaultError::AeadChaCha20Poly1305Encrypt
+        )
+    }
+
+    fn aead_xchacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>> {
+        let entry = self.get_entry_for(context, SecretUsage::Any)?;
+
+        chacha_impl!(
+            entry,
+            aad,
+            nonce,
+            cipher_text,
+            XChaCha20Poly1305,
+            XCHACHA20_POLY1305_NONCE_LENGTH,
+            decrypt,
+            VaultError::AeadChaCha20Poly1305Decrypt
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::SoftwareVault;
+    use ockam_core::hex::{decode, encode};
     use ockam_vault_core::{
         SecretAttributes, SecretPersistence, SecretType, SecretVault, SymmetricVault,
-        AES128_SECRET_LENGTH,
+        AES128_SECRET_LENGTH, CHACHA20_SECRET_LENGTH,
     };
 
     #[test]
@@ -114,4 +229,67 @@ mod tests {
             vault.aead_aes_gcm_decrypt(ctx, ciphertext.as_slice(), nonce.as_ref(), aad.as_ref());
         assert!(res.is_err());
     }
+
+    #[test]
+    fn chacha20_poly1305_rfc8439() {
+        // RFC 8439, section 2.8.2
+        let mut vault = SoftwareVault::default();
+        let key =
+            decode("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f").unwrap();
+        let nonce = decode("070000004041424344454647").unwrap();
+        let aad = decode("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let message = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let attributes = SecretAttributes::new(
+            SecretType::ChaCha20,
+            SecretPersistence::Ephemeral,
+            CHACHA20_SECRET_LENGTH,
+        );
+
+        let ctx = &vault.secret_import(&key, attributes).unwrap();
+        let ciphertext = vault
+            .aead_chacha20_poly1305_encrypt(ctx, message.as_ref(), &nonce, &aad)
+            .unwrap();
+        assert_eq!(
+            encode(&ciphertext[ciphertext.len() - 16..]),
+            "1ae10b594f09e26a7e902ecbd0600691"
+        );
+        assert_eq!(
+            encode(&ciphertext[..16]),
+            "d31a8d34648e60db7b86afbc53ef7ec2"
+        );
+        let plaintext = vault
+            .aead_chacha20_poly1305_decrypt(ctx, &ciphertext, &nonce, &aad)
+            .unwrap();
+        assert_eq!(plaintext, message.to_vec());
+    }
+
+    #[test]
+    fn xchacha20_poly1305_encryption() {
+        let mut vault = SoftwareVault::default();
+        let message = b"Ockam Test Message";
+        let nonce = b"TestingNonceTestingNonce";
+        let aad = b"Extra payload data";
+        let attributes = SecretAttributes::new(
+            SecretType::ChaCha20,
+            SecretPersistence::Ephemeral,
+            CHACHA20_SECRET_LENGTH,
+        );
+
+        let ctx = &vault.secret_generate(attributes).unwrap();
+        let mut ciphertext = vault
+            .aead_xchacha20_poly1305_encrypt(ctx, message.as_ref(), nonce.as_ref(), aad.as_ref())
+            .unwrap();
+        let plaintext = vault
+            .aead_xchacha20_poly1305_decrypt(ctx, &ciphertext, nonce.as_ref(), aad.as_ref())
+            .unwrap();
+        assert_eq!(plaintext, message.to_vec());
+        ciphertext[0] ^= ciphertext[1];
+        assert!(vault
+            .aead_xchacha20_poly1305_decrypt(ctx, &ciphertext, nonce.as_ref(), aad.as_ref())
+            .is_err());
+        // A ChaCha20-Poly1305 nonce is too short
+        assert!(vault
+            .aead_xchacha20_poly1305_encrypt(ctx, message.as_ref(), &nonce[..12], aad.as_ref())
+            .is_err());
+    }
 }
@@ -2,27 +2,123 @@ use crate::software_vault::SoftwareVault;
 use crate::xeddsa::XEddsaVerifier;
 use crate::VaultError;
 use arrayref::array_ref;
-use ockam_vault_core::{Verifier, CURVE25519_PUBLIC_LENGTH};
+use ockam_vault_core::{
+    SecretType, Verifier, CURVE25519_PUBLIC_LENGTH, ED25519_PUBLIC_LENGTH, P256_PUBLIC_LENGTH,
+};
+use p256::ecdsa::{signature::Verifier as _, Signature, VerifyingKey};
+use std::convert::TryFrom;
 
 impl Verifier for SoftwareVault {
-    /// Verify signature with xeddsa algorithm. Only curve25519 is supported.
+    /// Verify signature with xeddsa algorithm for curve25519 keys,
+    /// with ECDSA (SHA-256) for P256 keys, and with Ed25519 (RFC 8032)
+    /// for Ed25519 keys.
     fn verify(
         &mut self,
         signature: &[u8; 64],
         public_key: &[u8],
+        key_type: SecretType,
         data: &[u8],
     ) -> ockam_core::Result<()> {
-        // FIXME
-        if public_key.len() == CURVE25519_PUBLIC_LENGTH {
-            if x25519_dalek::PublicKey::from(*array_ref!(public_key, 0, CURVE25519_PUBLIC_LENGTH))
-                .verify(data.as_ref(), &signature)
-            {
-                Ok(())
-            } else {
-                Err(VaultError::InvalidSignature.into())
+        let valid = match key_type {
+            SecretType::Curve25519 => {
+                if public_key.len() != CURVE25519_PUBLIC_LENGTH {
+                    return Err(VaultError::InvalidPublicKey.into());
+                }
+                x25519_dalek::PublicKey::from(*array_ref!(public_key, 0, CURVE25519_PUBLIC_LENGTH))
+                    .verify(data.as_ref(), &signature)
             }
+            SecretType::P256 => {
+                if public_key.len() != P256_PUBLIC_LENGTH {
+                    return Err(VaultError::InvalidPublicKey.into());
+                }
+                let key = VerifyingKey::from_sec1_bytes(public_key)
+                    .map_err(|_| VaultError::InvalidPublicKey)?;
+                let signature = Signature::try_from(&signature[..])
+                    .map_err(|_| VaultError::InvalidSignature)?;
+                key.verify(data, &signature).is_ok()
+            }
+            SecretType::Ed25519 => {
+                if public_key.len() != ED25519_PUBLIC_LENGTH {
+                    return Err(VaultError::InvalidPublicKey.into());
+                }
+                let key = ed25519_dalek::PublicKey::from_bytes(public_key)
+                    .map_err(|_| VaultError::InvalidPublicKey)?;
+                let signature = ed25519_dalek::Signature::try_from(&signature[..])
+                    .map_err(|_| VaultError::InvalidSignature)?;
+                key.verify_strict(data, &signature).is_ok()
+            }
+            _ => return Err(VaultError::InvalidKeyType.into()),
+        };
+        if valid {
+            Ok(())
         } else {
-            Err(VaultError::InvalidPublicKey.into())
+            Err(VaultError::InvalidSignature.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SoftwareVault;
+    use ockam_core::hex::decode;
+    use ockam_vault_core::{
+        SecretAttributes, SecretPersistence, SecretType, SecretVault, Signer, Verifier,
+        CURVE25519_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+    };
+
+    #[test]
+    fn verify_ed25519_rfc8032() {
+        // RFC 8032, section 7.1, TEST 2
+        let public_key =
+            decode("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c").unwrap();
+        let signature = decode(
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        )
+        .unwrap();
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&signature);
+
+        let mut vault = SoftwareVault::default();
+        assert!(vault
+            .verify(&sig, &public_key, SecretType::Ed25519, &[0x72])
+            .is_ok());
+        assert!(vault
+            .verify(&sig, &public_key, SecretType::Ed25519, &[0x73])
+            .is_err());
+    }
+
+    #[test]
+    fn verify_with_the_scheme_of_the_key_type_only() {
+        let mut vault = SoftwareVault::default();
+        let types = [
+            (
+                SecretType::Curve25519,
+                CURVE25519_SECRET_LENGTH,
+                SecretType::Ed25519,
+            ),
+            (
+                SecretType::Ed25519,
+                ED25519_SECRET_LENGTH,
+                SecretType::Curve25519,
+            ),
+        ];
+        for (t, s, other) in &types {
+            let secret = vault
+                .secret_generate(SecretAttributes::new(*t, SecretPersistence::Ephemeral, *s))
+                .unwrap();
+            let public_key = vault.secret_public_key_get(&secret).unwrap();
+            let signature = vault.sign(&secret, b"data").unwrap();
+
+            assert!(vault
+                .verify(&signature, public_key.as_ref(), *t, b"data")
+                .is_ok());
+            assert!(vault
+                .verify(&signature, public_key.as_ref(), *other, b"data")
+                .is_err());
+            assert!(vault
+                .verify(&signature, public_key.as_ref(), SecretType::Buffer, b"data")
+                .is_err());
         }
     }
 }
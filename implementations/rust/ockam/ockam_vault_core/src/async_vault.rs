@@ -0,0 +1,380 @@
+//! Asynchronous variants of the vault traits
+//!
+//! Functions take `&self`, so that a vault can be shared between
+//! workers and keep its own locking.  Vaults which wait on other
+//! nodes, hardware or files implement these traits to be used from
+//! workers without blocking the runtime.
+//!
+//! Synchronous vaults get these traits through a [`Mutex`], e.g.
+//! `Arc<Mutex<SoftwareVault>>`.  The lock is only held for the
+//! duration of one call.
+
+use crate::{
+    AsymmetricVault, Buffer, Hasher, KeyId, KeyIdVault, PublicKey, Secret, SecretAttributes,
+    SecretDerivationVault, SecretKey, SecretSharingVault, SecretType, SecretVault, Signer,
+    SmallBuffer, SymmetricVault, Verifier,
+};
+use ockam_core::async_trait::async_trait;
+use ockam_core::Result;
+use std::boxed::Box;
+use std::sync::Mutex;
+
+/// Asynchronous [`SecretVault`]
+#[async_trait]
+pub trait AsyncSecretVault: Send + Sync {
+    /// See [`SecretVault::secret_generate`]
+    async fn secret_generate(&self, attributes: SecretAttributes) -> Result<Secret>;
+    /// See [`SecretVault::secret_import`]
+    async fn secret_import(&self, secret: &[u8], attributes: SecretAttributes) -> Result<Secret>;
+    /// See [`SecretVault::secret_export`]
+    async fn secret_export(&self, context: &Secret) -> Result<SecretKey>;
+    /// See [`SecretVault::secret_attributes_get`]
+    async fn secret_attributes_get(&self, context: &Secret) -> Result<SecretAttributes>;
+    /// See [`SecretVault::secret_public_key_get`]
+    async fn secret_public_key_get(&self, context: &Secret) -> Result<PublicKey>;
+    /// See [`SecretVault::secret_destroy`]
+    async fn secret_destroy(&self, context: Secret) -> Result<()>;
+}
+
+/// Asynchronous [`Hasher`]
+#[async_trait]
+pub trait AsyncHasher: Send + Sync {
+    /// See [`Hasher::sha256`]
+    async fn sha256(&self, data: &[u8]) -> Result<[u8; 32]>;
+    /// See [`Hasher::hkdf_sha256`]
+    async fn hkdf_sha256(
+        &self,
+        salt: &Secret,
+        info: &[u8],
+        ikm: Option<&Secret>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<Secret>>;
+}
+
+/// Asynchronous [`KeyIdVault`]
+#[async_trait]
+pub trait AsyncKeyIdVault: Send + Sync {
+    /// See [`KeyIdVault::get_secret_by_key_id`]
+    async fn get_secret_by_key_id(&self, key_id: &str) -> Result<Secret>;
+    /// See [`KeyIdVault::compute_key_id_for_public_key`]
+    async fn compute_key_id_for_public_key(&self, public_key: &PublicKey) -> Result<KeyId>;
+}
+
+/// Asynchronous [`Signer`]
+#[async_trait]
+pub trait AsyncSigner: Send + Sync {
+    /// See [`Signer::sign`]
+    async fn sign(&self, secret_key: &Secret, data: &[u8]) -> Result<[u8; 64]>;
+}
+
+/// Asynchronous [`Verifier`]
+#[async_trait]
+pub trait AsyncVerifier: Send + Sync {
+    /// See [`Verifier::verify`]
+    async fn verify(
+        &self,
+        signature: &[u8; 64],
+        public_key: &[u8],
+        key_type: SecretType,
+        data: &[u8],
+    ) -> Result<()>;
+}
+
+/// Asynchronous [`AsymmetricVault`]
+#[async_trait]
+pub trait AsyncAsymmetricVault: Send + Sync {
+    /// See [`AsymmetricVault::ec_diffie_hellman`]
+    async fn ec_diffie_hellman(&self, context: &Secret, peer_public_key: &[u8]) -> Result<Secret>;
+}
+
+/// Asynchronous [`SymmetricVault`]
+#[async_trait]
+pub trait AsyncSymmetricVault: Send + Sync {
+    /// See [`SymmetricVault::aead_aes_gcm_encrypt`]
+    async fn aead_aes_gcm_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+    /// See [`SymmetricVault::aead_aes_gcm_decrypt`]
+    async fn aead_aes_gcm_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+    /// See [`SymmetricVault::aead_chacha20_poly1305_encrypt`]
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+    /// See [`SymmetricVault::aead_chacha20_poly1305_decrypt`]
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+    /// See [`SymmetricVault::aead_xchacha20_poly1305_encrypt`]
+    async fn aead_xchacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+    /// See [`SymmetricVault::aead_xchacha20_poly1305_decrypt`]
+    async fn aead_xchacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>>;
+}
+
+/// Asynchronous [`SecretSharingVault`]
+#[async_trait]
+pub trait AsyncSecretSharingVault: Send + Sync {
+    /// See [`SecretSharingVault::secret_split`]
+    async fn secret_split(
+        &self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> Result<SmallBuffer<Buffer<u8>>>;
+    /// See [`SecretSharingVault::secret_share_reencrypt`]
+    async fn secret_share_reencrypt(
+        &self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> Result<Buffer<u8>>;
+    /// See [`SecretSharingVault::secret_recover`]
+    async fn secret_recover(&self, recipient: &Secret, shares: &[Buffer<u8>]) -> Result<Secret>;
+}
+
+/// Asynchronous [`SecretDerivationVault`]
+#[async_trait]
+pub trait AsyncSecretDerivationVault: Send + Sync {
+    /// See [`SecretDerivationVault::secret_derive`]
+    async fn secret_derive(
+        &self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> Result<Secret>;
+}
+
+#[async_trait]
+impl<V: SecretVault + Send + ?Sized> AsyncSecretVault for Mutex<V> {
+    async fn secret_generate(&self, attributes: SecretAttributes) -> Result<Secret> {
+        self.lock().unwrap().secret_generate(attributes)
+    }
+
+    async fn secret_import(&self, secret: &[u8], attributes: SecretAttributes) -> Result<Secret> {
+        self.lock().unwrap().secret_import(secret, attributes)
+    }
+
+    async fn secret_export(&self, context: &Secret) -> Result<SecretKey> {
+        self.lock().unwrap().secret_export(context)
+    }
+
+    async fn secret_attributes_get(&self, context: &Secret) -> Result<SecretAttributes> {
+        self.lock().unwrap().secret_attributes_get(context)
+    }
+
+    async fn secret_public_key_get(&self, context: &Secret) -> Result<PublicKey> {
+        self.lock().unwrap().secret_public_key_get(context)
+    }
+
+    async fn secret_destroy(&self, context: Secret) -> Result<()> {
+        self.lock().unwrap().secret_destroy(context)
+    }
+}
+
+#[async_trait]
+impl<V: Hasher + Send + ?Sized> AsyncHasher for Mutex<V> {
+    async fn sha256(&self, data: &[u8]) -> Result<[u8; 32]> {
+        self.lock().unwrap().sha256(data)
+    }
+
+    async fn hkdf_sha256(
+        &self,
+        salt: &Secret,
+        info: &[u8],
+        ikm: Option<&Secret>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<Secret>> {
+        self.lock()
+            .unwrap()
+            .hkdf_sha256(salt, info, ikm, output_attributes)
+    }
+}
+
+#[async_trait]
+impl<V: KeyIdVault + Send + ?Sized> AsyncKeyIdVault for Mutex<V> {
+    async fn get_secret_by_key_id(&self, key_id: &str) -> Result<Secret> {
+        self.lock().unwrap().get_secret_by_key_id(key_id)
+    }
+
+    async fn compute_key_id_for_public_key(&self, public_key: &PublicKey) -> Result<KeyId> {
+        self.lock()
+            .unwrap()
+            .compute_key_id_for_public_key(public_key)
+    }
+}
+
+#[async_trait]
+impl<V: Signer + Send + ?Sized> AsyncSigner for Mutex<V> {
+    async fn sign(&self, secret_key: &Secret, data: &[u8]) -> Result<[u8; 64]> {
+        self.lock().unwrap().sign(secret_key, data)
+    }
+}
+
+#[async_trait]
+impl<V: Verifier + Send + ?Sized> AsyncVerifier for Mutex<V> {
+    async fn verify(
+        &self,
+        signature: &[u8; 64],
+        public_key: &[u8],
+        key_type: SecretType,
+        data: &[u8],
+    ) -> Result<()> {
+        self.lock()
+            .unwrap()
+            .verify(signature, public_key, key_type, data)
+    }
+}
+
+#[async_trait]
+impl<V: AsymmetricVault + Send + ?Sized> AsyncAsymmetricVault for Mutex<V> {
+    async fn ec_diffie_hellman(&self, context: &Secret, peer_public_key: &[u8]) -> Result<Secret> {
+        self.lock()
+            .unwrap()
+            .ec_diffie_hellman(context, peer_public_key)
+    }
+}
+
+#[async_trait]
+impl<V: SymmetricVault + Send + ?Sized> AsyncSymmetricVault for Mutex<V> {
+    async fn aead_aes_gcm_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_aes_gcm_encrypt(context, plaintext, nonce, aad)
+    }
+
+    async fn aead_aes_gcm_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_aes_gcm_decrypt(context, cipher_text, nonce, aad)
+    }
+
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_chacha20_poly1305_encrypt(context, plaintext, nonce, aad)
+    }
+
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_chacha20_poly1305_decrypt(context, cipher_text, nonce, aad)
+    }
+
+    async fn aead_xchacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_xchacha20_poly1305_encrypt(context, plaintext, nonce, aad)
+    }
+
+    async fn aead_xchacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .aead_xchacha20_poly1305_decrypt(context, cipher_text, nonce, aad)
+    }
+}
+
+#[async_trait]
+impl<V: SecretSharingVault + Send + ?Sized> AsyncSecretSharingVault for Mutex<V> {
+    async fn secret_split(
+        &self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> Result<SmallBuffer<Buffer<u8>>> {
+        self.lock()
+            .unwrap()
+            .secret_split(context, threshold, guardians)
+    }
+
+    async fn secret_share_reencrypt(
+        &self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> Result<Buffer<u8>> {
+        self.lock()
+            .unwrap()
+            .secret_share_reencrypt(guardian, share, recipient)
+    }
+
+    async fn secret_recover(&self, recipient: &Secret, shares: &[Buffer<u8>]) -> Result<Secret> {
+        self.lock().unwrap().secret_recover(recipient, shares)
+    }
+}
+
+#[async_trait]
+impl<V: SecretDerivationVault + Send + ?Sized> AsyncSecretDerivationVault for Mutex<V> {
+    async fn secret_derive(
+        &self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> Result<Secret> {
+        self.lock().unwrap().secret_derive(seed, path, attributes)
+    }
+}
@@ -15,11 +15,18 @@
     warnings
 )]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 mod asymmetric_vault;
+#[cfg(feature = "std")]
+mod async_vault;
 mod hasher;
 mod key_id_vault;
 mod macros;
 mod secret;
+mod secret_derivation_vault;
+mod secret_sharing_vault;
 mod secret_vault;
 mod signer;
 mod symmetric_vault;
@@ -27,10 +34,14 @@ mod types;
 mod verifier;
 
 pub use asymmetric_vault::*;
+#[cfg(feature = "std")]
+pub use async_vault::*;
 pub use hasher::*;
 pub use key_id_vault::*;
 pub use macros::*;
 pub use secret::*;
+pub use secret_derivation_vault::*;
+pub use secret_sharing_vault::*;
 pub use secret_vault::*;
 pub use signer::*;
 pub use symmetric_vault::*;
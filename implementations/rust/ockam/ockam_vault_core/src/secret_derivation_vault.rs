@@ -0,0 +1,20 @@
+use crate::secret::Secret;
+use crate::SecretAttributes;
+use zeroize::Zeroize;
+
+/// Hierarchical deterministic derivation of vault secrets
+///
+/// Child secrets are derived from a seed secret along a path of
+/// hardened indices such as `m/0'/1'`, following SLIP-0010 for
+/// Curve25519 and Ed25519 keys. The same seed and path always give
+/// the same secret, so many keys can be regenerated from one seed.
+pub trait SecretDerivationVault: Zeroize {
+    /// Derive the secret at `path` from the `seed` secret. The type
+    /// of the derived secret is given by `attributes`
+    fn secret_derive(
+        &mut self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> ockam_core::Result<Secret>;
+}
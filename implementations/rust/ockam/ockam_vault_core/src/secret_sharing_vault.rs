@@ -0,0 +1,35 @@
+use crate::secret::Secret;
+use crate::{Buffer, PublicKey, SmallBuffer};
+use zeroize::Zeroize;
+
+/// Shamir's secret sharing of vault secrets
+///
+/// A secret is split into one share per guardian, such that any
+/// `threshold` shares recover it.  Shares are encrypted to the
+/// Curve25519 public keys of guardians, who re-encrypt their share
+/// to the recovering vault, which combines them.
+pub trait SecretSharingVault: Zeroize {
+    /// Split a secret into shares encrypted to each of `guardians`,
+    /// any `threshold` of which recover the secret with its attributes.
+    /// Secrets which can't be exported can't be split either.
+    fn secret_split(
+        &mut self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> ockam_core::Result<SmallBuffer<Buffer<u8>>>;
+    /// Re-encrypt a share encrypted to the `guardian` secret to
+    /// `recipient`, e.g. a key of the vault recovering the secret
+    fn secret_share_reencrypt(
+        &mut self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> ockam_core::Result<Buffer<u8>>;
+    /// Recover a secret from shares encrypted to the `recipient` secret
+    fn secret_recover(
+        &mut self,
+        recipient: &Secret,
+        shares: &[Buffer<u8>],
+    ) -> ockam_core::Result<Secret>;
+}
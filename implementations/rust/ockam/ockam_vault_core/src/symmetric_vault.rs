@@ -19,4 +19,36 @@ pub trait SymmetricVault: Zeroize {
         nonce: &[u8],
         aad: &[u8],
     ) -> ockam_core::Result<Buffer<u8>>;
+    /// Encrypt a payload using ChaCha20-Poly1305 with a 12 byte nonce
+    fn aead_chacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>>;
+    /// Decrypt a payload using ChaCha20-Poly1305 with a 12 byte nonce
+    fn aead_chacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>>;
+    /// Encrypt a payload using XChaCha20-Poly1305 with a 24 byte nonce
+    fn aead_xchacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>>;
+    /// Decrypt a payload using XChaCha20-Poly1305 with a 24 byte nonce
+    fn aead_xchacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> ockam_core::Result<Buffer<u8>>;
 }
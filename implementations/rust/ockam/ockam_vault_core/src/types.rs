@@ -10,10 +10,16 @@ pub const CURVE25519_PUBLIC_LENGTH: usize = 32;
 pub const P256_SECRET_LENGTH: usize = 32;
 /// P256 public key length
 pub const P256_PUBLIC_LENGTH: usize = 65;
+/// Ed25519 private key length
+pub const ED25519_SECRET_LENGTH: usize = 32;
+/// Ed25519 public key length
+pub const ED25519_PUBLIC_LENGTH: usize = 32;
 /// AES256 private key length
 pub const AES256_SECRET_LENGTH: usize = 32;
 /// AES128 private key length
 pub const AES128_SECRET_LENGTH: usize = 16;
+/// ChaCha20 private key length
+pub const CHACHA20_SECRET_LENGTH: usize = 32;
 
 cfg_if! {
     if #[cfg(feature = "no_std")] {
@@ -77,26 +83,56 @@ impl AsRef<[u8]> for PublicKey {
 
 /// All possible [`SecretType`]s
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Zeroize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecretType {
     Buffer,
     Aes,
     Curve25519,
     P256,
+    Ed25519,
+    ChaCha20,
 }
 
 /// Possible [`SecretKey`]'s persistence
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Zeroize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecretPersistence {
     Ephemeral,
     Persistent,
 }
 
+/// Operations a [`SecretKey`] may be used for
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Zeroize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SecretUsage {
+    /// Any operation supported by the secret type
+    Any,
+    /// Signing only
+    Sign,
+    /// Key agreement and key derivation only
+    Derive,
+}
+
+impl SecretUsage {
+    /// Whether a secret with this usage may be used for `operation`
+    pub fn permits(&self, operation: SecretUsage) -> bool {
+        *self == SecretUsage::Any || *self == operation
+    }
+}
+
 /// Attributes for a specific vault [`SecretKey`]
+///
+/// Secrets may be used for any operation and exported unless
+/// restricted with [`SecretAttributes::with_usage`] and
+/// [`SecretAttributes::with_exportable`].
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Zeroize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecretAttributes {
     stype: SecretType,
     persistence: SecretPersistence,
     length: usize,
+    usage: SecretUsage,
+    exportable: bool,
 }
 
 impl SecretAttributes {
@@ -109,6 +145,12 @@ impl SecretAttributes {
     pub fn length(&self) -> usize {
         self.length
     }
+    pub fn usage(&self) -> SecretUsage {
+        self.usage
+    }
+    pub fn exportable(&self) -> bool {
+        self.exportable
+    }
 }
 
 impl SecretAttributes {
@@ -117,8 +159,23 @@ impl SecretAttributes {
             stype,
             persistence,
             length,
+            usage: SecretUsage::Any,
+            exportable: true,
         }
     }
+
+    /// Restrict the operations the secret may be used for
+    pub fn with_usage(mut self, usage: SecretUsage) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    /// Set whether [`SecretVault::secret_export`](crate::SecretVault::secret_export)
+    /// may return the secret
+    pub fn with_exportable(mut self, exportable: bool) -> Self {
+        self.exportable = exportable;
+        self
+    }
 }
 
 zdrop_impl!(SecretKey);
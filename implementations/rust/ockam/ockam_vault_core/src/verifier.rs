@@ -1,12 +1,16 @@
+use crate::SecretType;
 use zeroize::Zeroize;
 
 /// Signature verification functionality
 pub trait Verifier: Zeroize {
-    /// Verify a signature for given data using given public key
+    /// Verify a signature for given data using given public key.
+    /// The key type selects the signature scheme, so a key is only
+    /// ever checked against the scheme of its own type.
     fn verify(
         &mut self,
         signature: &[u8; 64],
         public_key: &[u8],
+        key_type: SecretType,
         data: &[u8],
     ) -> ockam_core::Result<()>;
 }
@@ -0,0 +1,58 @@
+use crate::cryptoki::*;
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use crate::token::{Mechanism, Template};
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{
+    AsymmetricVault, Secret, SecretAttributes, SecretPersistence, SecretType, SecretUsage,
+    SecretVault, P256_PUBLIC_LENGTH, P256_SECRET_LENGTH,
+};
+
+impl AsymmetricVault for Pkcs11Vault {
+    /// Compute Elliptic-Curve Diffie-Hellman. P256 keys held by the
+    /// token derive the shared secret there, which is then kept in
+    /// memory as an ephemeral Buffer secret
+    fn ec_diffie_hellman(&mut self, context: &Secret, peer_public_key: &[u8]) -> Result<Secret> {
+        let key = match self.get_entry_for(context, SecretUsage::Derive)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                let shared = self.software.ec_diffie_hellman(&secret, peer_public_key)?;
+                return Ok(self.add_software(shared));
+            }
+            VaultEntry::Token(key) => key,
+        };
+        if key.attributes.stype() != SecretType::P256 || peer_public_key.len() != P256_PUBLIC_LENGTH
+        {
+            return Err(Pkcs11Error::InvalidKeyType.into());
+        }
+
+        // The derived secret is a session object that may be read
+        let template = Template::new()
+            .ulong(CKA_CLASS, CKO_SECRET_KEY)
+            .ulong(CKA_KEY_TYPE, CKK_GENERIC_SECRET)
+            .ulong(CKA_VALUE_LEN, P256_SECRET_LENGTH as CK_ULONG)
+            .bool(CKA_TOKEN, false)
+            .bool(CKA_SENSITIVE, false)
+            .bool(CKA_EXTRACTABLE, true);
+        let derived = self.token.derive_key(
+            Mechanism::Ecdh1Derive {
+                public_key: peer_public_key,
+            },
+            key.private,
+            template,
+        )?;
+        let value = self.token.attribute(derived, CKA_VALUE);
+        self.token.destroy_object(derived)?;
+        let mut value = value?;
+
+        let attributes = SecretAttributes::new(
+            SecretType::Buffer,
+            SecretPersistence::Ephemeral,
+            value.len(),
+        );
+        let shared = self.software.secret_import(&value, attributes);
+        zeroize::Zeroize::zeroize(&mut value);
+        let shared = shared?;
+        Ok(self.add_software(shared))
+    }
+}
@@ -0,0 +1,737 @@
+//! The subset of the PKCS#11 v2.40 C interface used by this crate
+//!
+//! Layouts follow the Unix conventions of the specification, where
+//! `CK_ULONG` is an `unsigned long` and structures are not packed, so
+//! other targets are refused.
+//!
+//! This is the only module calling into the PKCS#11 module.  The
+//! functions used by the crate are wrapped in safe methods of
+//! [`Module`], which only pass pointers to memory they borrow for the
+//! duration of the call.
+
+#![allow(non_camel_case_types, non_snake_case, unsafe_code)]
+
+#[cfg(not(unix))]
+compile_error!("ockam_vault_pkcs11 only supports the PKCS#11 layouts of Unix targets");
+
+use libloading::Library;
+use std::ffi::OsStr;
+use std::mem::{self, size_of};
+use std::os::raw::{c_ulong, c_void};
+use std::ptr;
+
+pub type CK_ULONG = c_ulong;
+pub type CK_BBOOL = u8;
+pub type CK_RV = CK_ULONG;
+pub type CK_FLAGS = CK_ULONG;
+pub type CK_SLOT_ID = CK_ULONG;
+pub type CK_SESSION_HANDLE = CK_ULONG;
+pub type CK_OBJECT_HANDLE = CK_ULONG;
+pub type CK_ATTRIBUTE_TYPE = CK_ULONG;
+pub type CK_MECHANISM_TYPE = CK_ULONG;
+
+pub const CK_TRUE: CK_BBOOL = 1;
+pub const CK_FALSE: CK_BBOOL = 0;
+pub const CK_UNAVAILABLE_INFORMATION: CK_ULONG = !0;
+
+pub const CKR_OK: CK_RV = 0x0000_0000;
+pub const CKR_USER_ALREADY_LOGGED_IN: CK_RV = 0x0000_0100;
+pub const CKR_CRYPTOKI_ALREADY_INITIALIZED: CK_RV = 0x0000_0191;
+
+pub const CKF_RW_SESSION: CK_FLAGS = 0x0000_0002;
+pub const CKF_SERIAL_SESSION: CK_FLAGS = 0x0000_0004;
+pub const CKF_OS_LOCKING_OK: CK_FLAGS = 0x0000_0002;
+
+pub const CKU_USER: CK_ULONG = 1;
+
+pub const CKO_PUBLIC_KEY: CK_ULONG = 2;
+pub const CKO_PRIVATE_KEY: CK_ULONG = 3;
+pub const CKO_SECRET_KEY: CK_ULONG = 4;
+
+pub const CKK_EC: CK_ULONG = 0x0000_0003;
+pub const CKK_GENERIC_SECRET: CK_ULONG = 0x0000_0010;
+pub const CKK_AES: CK_ULONG = 0x0000_001f;
+pub const CKK_EC_EDWARDS: CK_ULONG = 0x0000_0040;
+
+pub const CKA_CLASS: CK_ATTRIBUTE_TYPE = 0x0000_0000;
+pub const CKA_TOKEN: CK_ATTRIBUTE_TYPE = 0x0000_0001;
+pub const CKA_PRIVATE: CK_ATTRIBUTE_TYPE = 0x0000_0002;
+pub const CKA_VALUE: CK_ATTRIBUTE_TYPE = 0x0000_0011;
+pub const CKA_KEY_TYPE: CK_ATTRIBUTE_TYPE = 0x0000_0100;
+pub const CKA_ID: CK_ATTRIBUTE_TYPE = 0x0000_0102;
+pub const CKA_SENSITIVE: CK_ATTRIBUTE_TYPE = 0x0000_0103;
+pub const CKA_ENCRYPT: CK_ATTRIBUTE_TYPE = 0x0000_0104;
+pub const CKA_DECRYPT: CK_ATTRIBUTE_TYPE = 0x0000_0105;
+pub const CKA_SIGN: CK_ATTRIBUTE_TYPE = 0x0000_0108;
+pub const CKA_VERIFY: CK_ATTRIBUTE_TYPE = 0x0000_010a;
+pub const CKA_DERIVE: CK_ATTRIBUTE_TYPE = 0x0000_010c;
+pub const CKA_VALUE_LEN: CK_ATTRIBUTE_TYPE = 0x0000_0161;
+pub const CKA_EXTRACTABLE: CK_ATTRIBUTE_TYPE = 0x0000_0162;
+pub const CKA_EC_PARAMS: CK_ATTRIBUTE_TYPE = 0x0000_0180;
+pub const CKA_EC_POINT: CK_ATTRIBUTE_TYPE = 0x0000_0181;
+
+pub const CKM_EC_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1040;
+pub const CKM_ECDSA: CK_MECHANISM_TYPE = 0x0000_1041;
+pub const CKM_ECDH1_DERIVE: CK_MECHANISM_TYPE = 0x0000_1050;
+pub const CKM_EC_EDWARDS_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x0000_1055;
+pub const CKM_EDDSA: CK_MECHANISM_TYPE = 0x0000_1057;
+pub const CKM_AES_KEY_GEN: CK_MECHANISM_TYPE = 0x0000_1080;
+pub const CKM_AES_GCM: CK_MECHANISM_TYPE = 0x0000_1087;
+
+pub const CKD_NULL: CK_ULONG = 0x0000_0001;
+
+#[repr(C)]
+pub struct CK_VERSION {
+    pub major: u8,
+    pub minor: u8,
+}
+
+#[repr(C)]
+pub struct CK_C_INITIALIZE_ARGS {
+    pub CreateMutex: *const c_void,
+    pub DestroyMutex: *const c_void,
+    pub LockMutex: *const c_void,
+    pub UnlockMutex: *const c_void,
+    pub flags: CK_FLAGS,
+    pub pReserved: *mut c_void,
+}
+
+#[repr(C)]
+pub struct CK_TOKEN_INFO {
+    pub label: [u8; 32],
+    pub manufacturerID: [u8; 32],
+    pub model: [u8; 16],
+    pub serialNumber: [u8; 16],
+    pub flags: CK_FLAGS,
+    pub ulMaxSessionCount: CK_ULONG,
+    pub ulSessionCount: CK_ULONG,
+    pub ulMaxRwSessionCount: CK_ULONG,
+    pub ulRwSessionCount: CK_ULONG,
+    pub ulMaxPinLen: CK_ULONG,
+    pub ulMinPinLen: CK_ULONG,
+    pub ulTotalPublicMemory: CK_ULONG,
+    pub ulFreePublicMemory: CK_ULONG,
+    pub ulTotalPrivateMemory: CK_ULONG,
+    pub ulFreePrivateMemory: CK_ULONG,
+    pub hardwareVersion: CK_VERSION,
+    pub firmwareVersion: CK_VERSION,
+    pub utcTime: [u8; 16],
+}
+
+#[repr(C)]
+pub struct CK_ATTRIBUTE {
+    pub type_: CK_ATTRIBUTE_TYPE,
+    pub pValue: *mut c_void,
+    pub ulValueLen: CK_ULONG,
+}
+
+#[repr(C)]
+pub struct CK_MECHANISM {
+    pub mechanism: CK_MECHANISM_TYPE,
+    pub pParameter: *mut c_void,
+    pub ulParameterLen: CK_ULONG,
+}
+
+#[repr(C)]
+pub struct CK_ECDH1_DERIVE_PARAMS {
+    pub kdf: CK_ULONG,
+    pub ulSharedDataLen: CK_ULONG,
+    pub pSharedData: *mut u8,
+    pub ulPublicDataLen: CK_ULONG,
+    pub pPublicData: *mut u8,
+}
+
+#[repr(C)]
+pub struct CK_GCM_PARAMS {
+    pub pIv: *mut u8,
+    pub ulIvLen: CK_ULONG,
+    pub ulIvBits: CK_ULONG,
+    pub pAAD: *mut u8,
+    pub ulAADLen: CK_ULONG,
+    pub ulTagBits: CK_ULONG,
+}
+
+/// Entry point looked up in the module
+pub type C_GetFunctionList = unsafe extern "C" fn(*mut *const CK_FUNCTION_LIST) -> CK_RV;
+
+/// Slot of a function this crate doesn't call
+type Unused = *const c_void;
+
+#[repr(C)]
+pub struct CK_FUNCTION_LIST {
+    pub version: CK_VERSION,
+    pub C_Initialize: unsafe extern "C" fn(*mut c_void) -> CK_RV,
+    pub C_Finalize: Unused,
+    pub C_GetInfo: Unused,
+    pub C_GetFunctionList: Unused,
+    pub C_GetSlotList: unsafe extern "C" fn(CK_BBOOL, *mut CK_SLOT_ID, *mut CK_ULONG) -> CK_RV,
+    pub C_GetSlotInfo: Unused,
+    pub C_GetTokenInfo: unsafe extern "C" fn(CK_SLOT_ID, *mut CK_TOKEN_INFO) -> CK_RV,
+    pub C_GetMechanismList: Unused,
+    pub C_GetMechanismInfo: Unused,
+    pub C_InitToken: Unused,
+    pub C_InitPIN: Unused,
+    pub C_SetPIN: Unused,
+    pub C_OpenSession: unsafe extern "C" fn(
+        CK_SLOT_ID,
+        CK_FLAGS,
+        *mut c_void,
+        *const c_void,
+        *mut CK_SESSION_HANDLE,
+    ) -> CK_RV,
+    pub C_CloseSession: unsafe extern "C" fn(CK_SESSION_HANDLE) -> CK_RV,
+    pub C_CloseAllSessions: Unused,
+    pub C_GetSessionInfo: Unused,
+    pub C_GetOperationState: Unused,
+    pub C_SetOperationState: Unused,
+    pub C_Login: unsafe extern "C" fn(CK_SESSION_HANDLE, CK_ULONG, *const u8, CK_ULONG) -> CK_RV,
+    pub C_Logout: Unused,
+    pub C_CreateObject: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+        *mut CK_OBJECT_HANDLE,
+    ) -> CK_RV,
+    pub C_CopyObject: Unused,
+    pub C_DestroyObject: unsafe extern "C" fn(CK_SESSION_HANDLE, CK_OBJECT_HANDLE) -> CK_RV,
+    pub C_GetObjectSize: Unused,
+    pub C_GetAttributeValue: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        CK_OBJECT_HANDLE,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+    ) -> CK_RV,
+    pub C_SetAttributeValue: Unused,
+    pub C_FindObjectsInit:
+        unsafe extern "C" fn(CK_SESSION_HANDLE, *mut CK_ATTRIBUTE, CK_ULONG) -> CK_RV,
+    pub C_FindObjects: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *mut CK_OBJECT_HANDLE,
+        CK_ULONG,
+        *mut CK_ULONG,
+    ) -> CK_RV,
+    pub C_FindObjectsFinal: unsafe extern "C" fn(CK_SESSION_HANDLE) -> CK_RV,
+    pub C_EncryptInit:
+        unsafe extern "C" fn(CK_SESSION_HANDLE, *mut CK_MECHANISM, CK_OBJECT_HANDLE) -> CK_RV,
+    pub C_Encrypt: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *const u8,
+        CK_ULONG,
+        *mut u8,
+        *mut CK_ULONG,
+    ) -> CK_RV,
+    pub C_EncryptUpdate: Unused,
+    pub C_EncryptFinal: Unused,
+    pub C_DecryptInit:
+        unsafe extern "C" fn(CK_SESSION_HANDLE, *mut CK_MECHANISM, CK_OBJECT_HANDLE) -> CK_RV,
+    pub C_Decrypt: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *const u8,
+        CK_ULONG,
+        *mut u8,
+        *mut CK_ULONG,
+    ) -> CK_RV,
+    pub C_DecryptUpdate: Unused,
+    pub C_DecryptFinal: Unused,
+    pub C_DigestInit: Unused,
+    pub C_Digest: Unused,
+    pub C_DigestUpdate: Unused,
+    pub C_DigestKey: Unused,
+    pub C_DigestFinal: Unused,
+    pub C_SignInit:
+        unsafe extern "C" fn(CK_SESSION_HANDLE, *mut CK_MECHANISM, CK_OBJECT_HANDLE) -> CK_RV,
+    pub C_Sign: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *const u8,
+        CK_ULONG,
+        *mut u8,
+        *mut CK_ULONG,
+    ) -> CK_RV,
+    pub C_SignUpdate: Unused,
+    pub C_SignFinal: Unused,
+    pub C_SignRecoverInit: Unused,
+    pub C_SignRecover: Unused,
+    pub C_VerifyInit: Unused,
+    pub C_Verify: Unused,
+    pub C_VerifyUpdate: Unused,
+    pub C_VerifyFinal: Unused,
+    pub C_VerifyRecoverInit: Unused,
+    pub C_VerifyRecover: Unused,
+    pub C_DigestEncryptUpdate: Unused,
+    pub C_DecryptDigestUpdate: Unused,
+    pub C_SignEncryptUpdate: Unused,
+    pub C_DecryptVerifyUpdate: Unused,
+    pub C_GenerateKey: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *mut CK_MECHANISM,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+        *mut CK_OBJECT_HANDLE,
+    ) -> CK_RV,
+    pub C_GenerateKeyPair: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *mut CK_MECHANISM,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+        *mut CK_OBJECT_HANDLE,
+        *mut CK_OBJECT_HANDLE,
+    ) -> CK_RV,
+    pub C_WrapKey: Unused,
+    pub C_UnwrapKey: Unused,
+    pub C_DeriveKey: unsafe extern "C" fn(
+        CK_SESSION_HANDLE,
+        *mut CK_MECHANISM,
+        CK_OBJECT_HANDLE,
+        *mut CK_ATTRIBUTE,
+        CK_ULONG,
+        *mut CK_OBJECT_HANDLE,
+    ) -> CK_RV,
+    pub C_SeedRandom: Unused,
+    pub C_GenerateRandom: Unused,
+    pub C_GetFunctionStatus: Unused,
+    pub C_CancelFunction: Unused,
+    pub C_WaitForSlotEvent: Unused,
+}
+
+/// Attributes of an object to create or to search for
+#[derive(Default)]
+pub(crate) struct Template {
+    values: Vec<(CK_ATTRIBUTE_TYPE, Vec<u8>)>,
+}
+
+impl Template {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn bool(self, attribute: CK_ATTRIBUTE_TYPE, value: bool) -> Self {
+        let value = if value { CK_TRUE } else { CK_FALSE };
+        self.bytes(attribute, &[value])
+    }
+
+    pub(crate) fn ulong(self, attribute: CK_ATTRIBUTE_TYPE, value: CK_ULONG) -> Self {
+        self.bytes(attribute, &value.to_ne_bytes())
+    }
+
+    pub(crate) fn bytes(mut self, attribute: CK_ATTRIBUTE_TYPE, value: &[u8]) -> Self {
+        self.values.push((attribute, value.to_vec()));
+        self
+    }
+
+    /// The attributes passed to the module, which point into `self`
+    fn attributes(&mut self) -> Vec<CK_ATTRIBUTE> {
+        self.values
+            .iter_mut()
+            .map(|(attribute, value)| CK_ATTRIBUTE {
+                type_: *attribute,
+                pValue: value.as_mut_ptr() as *mut c_void,
+                ulValueLen: value.len() as CK_ULONG,
+            })
+            .collect()
+    }
+}
+
+/// Mechanisms used with the token
+pub(crate) enum Mechanism<'a> {
+    EcKeyPairGen,
+    EcEdwardsKeyPairGen,
+    AesKeyGen,
+    Ecdsa,
+    EdDsa,
+    Ecdh1Derive { public_key: &'a [u8] },
+    AesGcm { nonce: &'a [u8], aad: &'a [u8] },
+}
+
+impl Mechanism<'_> {
+    /// Run `f` with this mechanism and its parameters
+    fn with<T>(&self, f: impl FnOnce(*mut CK_MECHANISM) -> T) -> T {
+        let plain = |mechanism| CK_MECHANISM {
+            mechanism,
+            pParameter: ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+        match self {
+            Mechanism::EcKeyPairGen => f(&mut plain(CKM_EC_KEY_PAIR_GEN)),
+            Mechanism::EcEdwardsKeyPairGen => f(&mut plain(CKM_EC_EDWARDS_KEY_PAIR_GEN)),
+            Mechanism::AesKeyGen => f(&mut plain(CKM_AES_KEY_GEN)),
+            Mechanism::Ecdsa => f(&mut plain(CKM_ECDSA)),
+            Mechanism::EdDsa => f(&mut plain(CKM_EDDSA)),
+            Mechanism::Ecdh1Derive { public_key } => {
+                let mut params = CK_ECDH1_DERIVE_PARAMS {
+                    kdf: CKD_NULL,
+                    ulSharedDataLen: 0,
+                    pSharedData: ptr::null_mut(),
+                    ulPublicDataLen: public_key.len() as CK_ULONG,
+                    pPublicData: public_key.as_ptr() as *mut u8,
+                };
+                f(&mut CK_MECHANISM {
+                    mechanism: CKM_ECDH1_DERIVE,
+                    pParameter: ptr::addr_of_mut!(params).cast(),
+                    ulParameterLen: size_of::<CK_ECDH1_DERIVE_PARAMS>() as CK_ULONG,
+                })
+            }
+            Mechanism::AesGcm { nonce, aad } => {
+                let mut params = CK_GCM_PARAMS {
+                    pIv: nonce.as_ptr() as *mut u8,
+                    ulIvLen: nonce.len() as CK_ULONG,
+                    ulIvBits: (nonce.len() * 8) as CK_ULONG,
+                    pAAD: aad.as_ptr() as *mut u8,
+                    ulAADLen: aad.len() as CK_ULONG,
+                    ulTagBits: 128,
+                };
+                f(&mut CK_MECHANISM {
+                    mechanism: CKM_AES_GCM,
+                    pParameter: ptr::addr_of_mut!(params).cast(),
+                    ulParameterLen: size_of::<CK_GCM_PARAMS>() as CK_ULONG,
+                })
+            }
+        }
+    }
+}
+
+/// Operations taking data and writing their output in one call
+#[derive(Clone, Copy)]
+pub(crate) enum SinglePart {
+    Sign,
+    Encrypt,
+    Decrypt,
+}
+
+/// A loaded PKCS#11 module
+pub(crate) struct Module {
+    functions: *const CK_FUNCTION_LIST,
+    // Keeps `functions` valid
+    _library: Library,
+}
+
+// The function list is only read, and modules are initialized for use
+// from several threads.
+unsafe impl Send for Module {}
+
+impl Module {
+    /// Load the PKCS#11 module at `path` and get its function list
+    pub(crate) fn load(path: &OsStr) -> Option<Self> {
+        // Loading a library runs its initialization code, which PKCS#11
+        // modules are trusted with
+        let library = unsafe { Library::new(path) }.ok()?;
+        let mut functions = ptr::null();
+        unsafe {
+            let get_function_list = library
+                .get::<C_GetFunctionList>(b"C_GetFunctionList\0")
+                .ok()?;
+            if get_function_list(&mut functions) != CKR_OK {
+                return None;
+            }
+        }
+        if functions.is_null() {
+            return None;
+        }
+        Some(Self {
+            functions,
+            _library: library,
+        })
+    }
+
+    fn f(&self) -> &CK_FUNCTION_LIST {
+        unsafe { &*self.functions }
+    }
+
+    /// Initialize the module for use with OS locking
+    pub(crate) fn initialize(&self) -> CK_RV {
+        let mut args = CK_C_INITIALIZE_ARGS {
+            CreateMutex: ptr::null(),
+            DestroyMutex: ptr::null(),
+            LockMutex: ptr::null(),
+            UnlockMutex: ptr::null(),
+            flags: CKF_OS_LOCKING_OK,
+            pReserved: ptr::null_mut(),
+        };
+        unsafe { (self.f().C_Initialize)(ptr::addr_of_mut!(args).cast()) }
+    }
+
+    /// The slots with a token present
+    pub(crate) fn slot_list(&self) -> Result<Vec<CK_SLOT_ID>, CK_RV> {
+        let mut count = 0;
+        let rv = unsafe { (self.f().C_GetSlotList)(CK_TRUE, ptr::null_mut(), &mut count) };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        let mut slots = vec![0; count as usize];
+        let rv = unsafe { (self.f().C_GetSlotList)(CK_TRUE, slots.as_mut_ptr(), &mut count) };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        slots.truncate(count as usize);
+        Ok(slots)
+    }
+
+    pub(crate) fn token_info(&self, slot: CK_SLOT_ID) -> Result<CK_TOKEN_INFO, CK_RV> {
+        // All fields of the structure are plain integers and arrays
+        let mut info = unsafe { mem::zeroed::<CK_TOKEN_INFO>() };
+        match unsafe { (self.f().C_GetTokenInfo)(slot, &mut info) } {
+            CKR_OK => Ok(info),
+            rv => Err(rv),
+        }
+    }
+
+    pub(crate) fn open_session(
+        &self,
+        slot: CK_SLOT_ID,
+        flags: CK_FLAGS,
+    ) -> Result<CK_SESSION_HANDLE, CK_RV> {
+        let mut session = 0;
+        let rv = unsafe {
+            (self.f().C_OpenSession)(slot, flags, ptr::null_mut(), ptr::null(), &mut session)
+        };
+        match rv {
+            CKR_OK => Ok(session),
+            rv => Err(rv),
+        }
+    }
+
+    pub(crate) fn close_session(&self, session: CK_SESSION_HANDLE) -> CK_RV {
+        unsafe { (self.f().C_CloseSession)(session) }
+    }
+
+    pub(crate) fn login(&self, session: CK_SESSION_HANDLE, user: CK_ULONG, pin: &[u8]) -> CK_RV {
+        unsafe { (self.f().C_Login)(session, user, pin.as_ptr(), pin.len() as CK_ULONG) }
+    }
+
+    pub(crate) fn find_objects_init(
+        &self,
+        session: CK_SESSION_HANDLE,
+        template: &mut Template,
+    ) -> CK_RV {
+        let mut attributes = template.attributes();
+        unsafe {
+            (self.f().C_FindObjectsInit)(
+                session,
+                attributes.as_mut_ptr(),
+                attributes.len() as CK_ULONG,
+            )
+        }
+    }
+
+    /// Fill `objects` with found objects, returning how many were found
+    pub(crate) fn find_objects(
+        &self,
+        session: CK_SESSION_HANDLE,
+        objects: &mut [CK_OBJECT_HANDLE],
+    ) -> Result<usize, CK_RV> {
+        let mut count = 0;
+        let rv = unsafe {
+            (self.f().C_FindObjects)(
+                session,
+                objects.as_mut_ptr(),
+                objects.len() as CK_ULONG,
+                &mut count,
+            )
+        };
+        match rv {
+            CKR_OK => Ok((count as usize).min(objects.len())),
+            rv => Err(rv),
+        }
+    }
+
+    pub(crate) fn find_objects_final(&self, session: CK_SESSION_HANDLE) -> CK_RV {
+        unsafe { (self.f().C_FindObjectsFinal)(session) }
+    }
+
+    /// The value of an attribute of `object`
+    pub(crate) fn attribute_value(
+        &self,
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<Option<Vec<u8>>, CK_RV> {
+        let mut query = CK_ATTRIBUTE {
+            type_: attribute,
+            pValue: ptr::null_mut(),
+            ulValueLen: 0,
+        };
+        let rv = unsafe { (self.f().C_GetAttributeValue)(session, object, &mut query, 1) };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        if query.ulValueLen == CK_UNAVAILABLE_INFORMATION {
+            return Ok(None);
+        }
+
+        let mut value = vec![0u8; query.ulValueLen as usize];
+        query.pValue = value.as_mut_ptr() as *mut c_void;
+        query.ulValueLen = value.len() as CK_ULONG;
+        let rv = unsafe { (self.f().C_GetAttributeValue)(session, object, &mut query, 1) };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        value.truncate(query.ulValueLen as usize);
+        Ok(Some(value))
+    }
+
+    pub(crate) fn generate_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        mechanism: &Mechanism,
+        template: &mut Template,
+    ) -> Result<CK_OBJECT_HANDLE, CK_RV> {
+        let mut attributes = template.attributes();
+        let mut key = 0;
+        let rv = mechanism.with(|mechanism| unsafe {
+            (self.f().C_GenerateKey)(
+                session,
+                mechanism,
+                attributes.as_mut_ptr(),
+                attributes.len() as CK_ULONG,
+                &mut key,
+            )
+        });
+        match rv {
+            CKR_OK => Ok(key),
+            rv => Err(rv),
+        }
+    }
+
+    /// Generate a key pair, returning the public and the private key
+    pub(crate) fn generate_key_pair(
+        &self,
+        session: CK_SESSION_HANDLE,
+        mechanism: &Mechanism,
+        public_template: &mut Template,
+        private_template: &mut Template,
+    ) -> Result<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE), CK_RV> {
+        let mut public_attributes = public_template.attributes();
+        let mut private_attributes = private_template.attributes();
+        let (mut public, mut private) = (0, 0);
+        let rv = mechanism.with(|mechanism| unsafe {
+            (self.f().C_GenerateKeyPair)(
+                session,
+                mechanism,
+                public_attributes.as_mut_ptr(),
+                public_attributes.len() as CK_ULONG,
+                private_attributes.as_mut_ptr(),
+                private_attributes.len() as CK_ULONG,
+                &mut public,
+                &mut private,
+            )
+        });
+        match rv {
+            CKR_OK => Ok((public, private)),
+            rv => Err(rv),
+        }
+    }
+
+    pub(crate) fn create_object(
+        &self,
+        session: CK_SESSION_HANDLE,
+        template: &mut Template,
+    ) -> Result<CK_OBJECT_HANDLE, CK_RV> {
+        let mut attributes = template.attributes();
+        let mut object = 0;
+        let rv = unsafe {
+            (self.f().C_CreateObject)(
+                session,
+                attributes.as_mut_ptr(),
+                attributes.len() as CK_ULONG,
+                &mut object,
+            )
+        };
+        match rv {
+            CKR_OK => Ok(object),
+            rv => Err(rv),
+        }
+    }
+
+    pub(crate) fn destroy_object(
+        &self,
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+    ) -> CK_RV {
+        unsafe { (self.f().C_DestroyObject)(session, object) }
+    }
+
+    pub(crate) fn derive_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        mechanism: &Mechanism,
+        base_key: CK_OBJECT_HANDLE,
+        template: &mut Template,
+    ) -> Result<CK_OBJECT_HANDLE, CK_RV> {
+        let mut attributes = template.attributes();
+        let mut key = 0;
+        let rv = mechanism.with(|mechanism| unsafe {
+            (self.f().C_DeriveKey)(
+                session,
+                mechanism,
+                base_key,
+                attributes.as_mut_ptr(),
+                attributes.len() as CK_ULONG,
+                &mut key,
+            )
+        });
+        match rv {
+            CKR_OK => Ok(key),
+            rv => Err(rv),
+        }
+    }
+
+    /// Start a single-part `operation` with `key`
+    pub(crate) fn init(
+        &self,
+        operation: SinglePart,
+        session: CK_SESSION_HANDLE,
+        mechanism: &Mechanism,
+        key: CK_OBJECT_HANDLE,
+    ) -> CK_RV {
+        let init = match operation {
+            SinglePart::Sign => self.f().C_SignInit,
+            SinglePart::Encrypt => self.f().C_EncryptInit,
+            SinglePart::Decrypt => self.f().C_DecryptInit,
+        };
+        mechanism.with(|mechanism| unsafe { init(session, mechanism, key) })
+    }
+
+    /// Run a started single-part `operation`, asking the module for
+    /// the output length first
+    pub(crate) fn run(
+        &self,
+        operation: SinglePart,
+        session: CK_SESSION_HANDLE,
+        data: &[u8],
+    ) -> Result<Vec<u8>, CK_RV> {
+        let run = match operation {
+            SinglePart::Sign => self.f().C_Sign,
+            SinglePart::Encrypt => self.f().C_Encrypt,
+            SinglePart::Decrypt => self.f().C_Decrypt,
+        };
+        let mut length = 0;
+        let rv = unsafe {
+            run(
+                session,
+                data.as_ptr(),
+                data.len() as CK_ULONG,
+                ptr::null_mut(),
+                &mut length,
+            )
+        };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        let mut output = vec![0u8; length as usize];
+        let rv = unsafe {
+            run(
+                session,
+                data.as_ptr(),
+                data.len() as CK_ULONG,
+                output.as_mut_ptr(),
+                &mut length,
+            )
+        };
+        if rv != CKR_OK {
+            return Err(rv);
+        }
+        output.truncate(length as usize);
+        Ok(output)
+    }
+}
@@ -0,0 +1,60 @@
+use ockam_core::Error;
+
+/// Represents the failures that can occur in
+/// an Ockam PKCS#11 vault
+#[derive(Clone, Copy, Debug)]
+pub enum Pkcs11Error {
+    None,
+    /// The PKCS#11 module can't be loaded
+    ModuleLoad,
+    /// The PKCS#11 module failed to initialize
+    Initialize,
+    /// No token with the given label is present
+    TokenNotFound,
+    /// A session with the token can't be opened
+    OpenSession,
+    /// The token rejected the PIN
+    Login,
+    /// Searching objects on the token failed
+    FindObjects,
+    /// An attribute of a token object can't be read
+    GetAttribute,
+    /// The token failed to generate a key
+    GenerateKey,
+    /// The token failed to import a key
+    CreateObject,
+    /// The token failed to destroy a key
+    DestroyObject,
+    /// The token failed to sign
+    Sign,
+    /// The token failed to derive a key
+    DeriveKey,
+    /// The token failed to encrypt
+    Encrypt,
+    /// The token failed to decrypt
+    Decrypt,
+    /// The operation isn't supported for this kind of secret
+    InvalidKeyType,
+    /// Keys held by the token can't be exported
+    SecretNotExtractable,
+    /// The secret's usage doesn't permit the operation
+    InvalidSecretUsage,
+    EntryNotFound,
+    SecretNotFound,
+}
+
+impl Pkcs11Error {
+    /// Integer code associated with the error domain.
+    pub const DOMAIN_CODE: u32 = 20_000;
+    /// Descriptive name for the error domain.
+    pub const DOMAIN_NAME: &'static str = "OCKAM_VAULT_PKCS11";
+}
+
+impl From<Pkcs11Error> for Error {
+    fn from(err: Pkcs11Error) -> Self {
+        Self::new(
+            Pkcs11Error::DOMAIN_CODE + (err as u32),
+            Pkcs11Error::DOMAIN_NAME,
+        )
+    }
+}
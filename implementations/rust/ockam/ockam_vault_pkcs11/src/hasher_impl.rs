@@ -0,0 +1,32 @@
+use crate::pkcs11_vault::Pkcs11Vault;
+use ockam_core::Result;
+use ockam_vault_core::{Hasher, Secret, SecretAttributes, SmallBuffer};
+
+impl Hasher for Pkcs11Vault {
+    fn sha256(&self, data: &[u8]) -> Result<[u8; 32]> {
+        self.software.sha256(data)
+    }
+
+    /// Compute sha256 HKDF in software. Salt and input key material
+    /// can't be keys held by the token, outputs are ephemeral secrets
+    fn hkdf_sha256(
+        &mut self,
+        salt: &Secret,
+        info: &[u8],
+        ikm: Option<&Secret>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<Secret>> {
+        let salt = self.software_secret(salt)?;
+        let ikm = match ikm {
+            Some(ikm) => Some(self.software_secret(ikm)?),
+            None => None,
+        };
+        let outputs = self
+            .software
+            .hkdf_sha256(&salt, info, ikm.as_ref(), output_attributes)?;
+        Ok(outputs
+            .into_iter()
+            .map(|secret| self.add_software(secret))
+            .collect())
+    }
+}
@@ -0,0 +1,30 @@
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{KeyId, KeyIdVault, PublicKey, Secret};
+
+impl KeyIdVault for Pkcs11Vault {
+    fn get_secret_by_key_id(&self, key_id: &str) -> Result<Secret> {
+        let token_key = self.entries.iter().find(|(_, entry)| match entry {
+            VaultEntry::Token(key) => key.key_id.as_deref() == Some(key_id),
+            VaultEntry::Software(_) => false,
+        });
+        if let Some((index, _)) = token_key {
+            return Ok(Secret::new(*index));
+        }
+
+        let secret = self.software.get_secret_by_key_id(key_id)?;
+        self.entries
+            .iter()
+            .find(|(_, entry)| match entry {
+                VaultEntry::Software(s) => s.index() == secret.index(),
+                VaultEntry::Token(_) => false,
+            })
+            .map(|(index, _)| Secret::new(*index))
+            .ok_or_else(|| Pkcs11Error::SecretNotFound.into())
+    }
+
+    fn compute_key_id_for_public_key(&self, public_key: &PublicKey) -> Result<KeyId> {
+        self.software.compute_key_id_for_public_key(public_key)
+    }
+}
@@ -0,0 +1,209 @@
+//! PKCS#11 implementation of ockam_vault_core traits.
+//!
+//! This crate keeps the persistent keys of a vault in a PKCS#11
+//! token, such as a hardware security module, where they are marked
+//! sensitive and non-extractable.  [`Pkcs11Vault`] can be used
+//! wherever a vault is expected, e.g. by profiles and key exchangers.
+
+#![deny(
+    // missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications,
+    warnings
+)]
+
+pub extern crate ockam_vault_core;
+
+mod asymmetric_impl;
+mod cryptoki;
+mod error;
+mod hasher_impl;
+mod key_id_impl;
+mod pkcs11_vault;
+mod secret_derivation_impl;
+mod secret_impl;
+mod secret_sharing_impl;
+mod signer_impl;
+mod symmetric_impl;
+mod token;
+mod verifier_impl;
+
+pub use error::*;
+pub use pkcs11_vault::Pkcs11Vault;
+
+#[cfg(test)]
+mod tests {
+    use crate::cryptoki::CK_FUNCTION_LIST;
+    use crate::{Pkcs11Error, Pkcs11Vault};
+    use ockam_core::hex::{decode, encode};
+    use ockam_vault::SoftwareVault;
+    use ockam_vault_core::{
+        AsymmetricVault, KeyIdVault, SecretAttributes, SecretPersistence, SecretType, SecretUsage,
+        SecretVault, Signer, SymmetricVault, Verifier, AES256_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+        P256_SECRET_LENGTH,
+    };
+    use std::env;
+    use std::mem::size_of;
+
+    /// Open the token named by the environment
+    ///
+    /// Tests using a token are ignored by default.  To run them
+    /// against SoftHSM, initialize a token labeled `ockam` with user
+    /// PIN `1234`, and point `OCKAM_PKCS11_MODULE` to the module:
+    ///
+    /// ```text
+    /// softhsm2-util --init-token --free --label ockam --so-pin 0000 --pin 1234
+    /// OCKAM_PKCS11_MODULE=/usr/lib/softhsm/libsofthsm2.so cargo test -- --ignored
+    /// ```
+    fn test_vault() -> Pkcs11Vault {
+        let module = env::var_os("OCKAM_PKCS11_MODULE")
+            .expect("OCKAM_PKCS11_MODULE must point to a PKCS#11 module, see DEVELOP.md");
+        let label = env::var("OCKAM_PKCS11_TOKEN").unwrap_or_else(|_| "ockam".into());
+        let pin = env::var("OCKAM_PKCS11_PIN").unwrap_or_else(|_| "1234".into());
+        Pkcs11Vault::open(module, &label, &pin).unwrap()
+    }
+
+    #[test]
+    fn function_list_layout() {
+        // The version, padded to pointer alignment, and 68 functions
+        let pointer = size_of::<usize>();
+        assert_eq!(size_of::<CK_FUNCTION_LIST>(), pointer * 69);
+    }
+
+    #[test]
+    #[ignore = "needs a PKCS#11 token, see test_vault"]
+    fn persistent_p256_keys() {
+        let mut vault = test_vault();
+        let attributes = SecretAttributes::new(
+            SecretType::P256,
+            SecretPersistence::Persistent,
+            P256_SECRET_LENGTH,
+        );
+        let secret = vault.secret_generate(attributes).unwrap();
+        let public = vault.secret_public_key_get(&secret).unwrap();
+        assert_eq!(
+            vault.secret_attributes_get(&secret).unwrap(),
+            attributes.with_exportable(false)
+        );
+
+        let err = vault.secret_export(&secret).unwrap_err();
+        assert_eq!(err.domain(), Pkcs11Error::DOMAIN_NAME);
+
+        let signature = vault.sign(&secret, b"hello world!").unwrap();
+        let mut software = SoftwareVault::default();
+        software
+            .verify(
+                &signature,
+                public.as_ref(),
+                SecretType::P256,
+                b"hello world!",
+            )
+            .unwrap();
+
+        // Shared secrets match the software vault
+        let ephemeral = SecretAttributes::new(
+            SecretType::P256,
+            SecretPersistence::Ephemeral,
+            P256_SECRET_LENGTH,
+        );
+        let peer = software.secret_generate(ephemeral).unwrap();
+        let peer_public = software.secret_public_key_get(&peer).unwrap();
+        let shared = vault
+            .ec_diffie_hellman(&secret, peer_public.as_ref())
+            .unwrap();
+        let peer_shared = software.ec_diffie_hellman(&peer, public.as_ref()).unwrap();
+        assert_eq!(
+            vault.secret_export(&shared).unwrap(),
+            software.secret_export(&peer_shared).unwrap()
+        );
+
+        // Keys are found again after reopening the token
+        let key_id = vault.compute_key_id_for_public_key(&public).unwrap();
+        drop(vault);
+        let mut vault = test_vault();
+        let secret = vault.get_secret_by_key_id(&key_id).unwrap();
+        assert_eq!(vault.secret_public_key_get(&secret).unwrap(), public);
+        vault.secret_destroy(secret).unwrap();
+        assert!(vault.get_secret_by_key_id(&key_id).is_err());
+
+        // Sign-only keys keep their usage on the token
+        let secret = vault
+            .secret_generate(attributes.with_usage(SecretUsage::Sign))
+            .unwrap();
+        let public = vault.secret_public_key_get(&secret).unwrap();
+        let key_id = vault.compute_key_id_for_public_key(&public).unwrap();
+        drop(vault);
+        let mut vault = test_vault();
+        let secret = vault.get_secret_by_key_id(&key_id).unwrap();
+        let attributes = vault.secret_attributes_get(&secret).unwrap();
+        assert_eq!(attributes.usage(), SecretUsage::Sign);
+        vault.sign(&secret, b"hello world!").unwrap();
+        let err = vault
+            .ec_diffie_hellman(&secret, peer_public.as_ref())
+            .unwrap_err();
+        assert_eq!(
+            err.code(),
+            Pkcs11Error::DOMAIN_CODE + Pkcs11Error::InvalidSecretUsage as u32
+        );
+        vault.secret_destroy(secret).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a PKCS#11 token, see test_vault"]
+    fn imported_keys() {
+        let mut vault = test_vault();
+
+        // RFC 8032, section 7.1, TEST 2
+        let secret = vault
+            .secret_import(
+                &decode("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb")
+                    .unwrap(),
+                SecretAttributes::new(
+                    SecretType::Ed25519,
+                    SecretPersistence::Persistent,
+                    ED25519_SECRET_LENGTH,
+                ),
+            )
+            .unwrap();
+        assert_eq!(
+            encode(vault.secret_public_key_get(&secret).unwrap().as_ref()),
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"
+        );
+        let signature = vault.sign(&secret, &[0x72]).unwrap();
+        assert_eq!(
+            encode(&signature[..]),
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+        );
+        vault.secret_destroy(secret).unwrap();
+
+        let key = [7u8; AES256_SECRET_LENGTH];
+        let attributes = SecretAttributes::new(
+            SecretType::Aes,
+            SecretPersistence::Persistent,
+            AES256_SECRET_LENGTH,
+        );
+        let secret = vault.secret_import(&key, attributes).unwrap();
+        let mut software = SoftwareVault::default();
+        let software_secret = software.secret_import(&key, attributes).unwrap();
+
+        let nonce = [0u8; 12];
+        let ciphertext = vault
+            .aead_aes_gcm_encrypt(&secret, b"hello", &nonce, b"aad")
+            .unwrap();
+        assert_eq!(
+            ciphertext,
+            software
+                .aead_aes_gcm_encrypt(&software_secret, b"hello", &nonce, b"aad")
+                .unwrap()
+        );
+        let plaintext = vault
+            .aead_aes_gcm_decrypt(&secret, &ciphertext, &nonce, b"aad")
+            .unwrap();
+        assert_eq!(plaintext, b"hello");
+        vault.secret_destroy(secret).unwrap();
+    }
+}
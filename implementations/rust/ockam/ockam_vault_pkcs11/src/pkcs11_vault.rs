@@ -0,0 +1,238 @@
+use crate::cryptoki::*;
+use crate::token::{Template, Token};
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault::SoftwareVault;
+use ockam_vault_core::zdrop_impl;
+use ockam_vault_core::{
+    KeyId, KeyIdVault, PublicKey, Secret, SecretAttributes, SecretPersistence, SecretType,
+    SecretUsage, ED25519_PUBLIC_LENGTH, ED25519_SECRET_LENGTH, P256_PUBLIC_LENGTH,
+    P256_SECRET_LENGTH,
+};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use zeroize::Zeroize;
+
+/// DER encoded OID of the P-256 curve (1.2.840.10045.3.1.7)
+pub(crate) const P256_PARAMS: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoded OID of Ed25519 (1.3.101.112)
+pub(crate) const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Vault implementation that keeps persistent keys in a PKCS#11 token.
+///
+/// Persistent P256, Ed25519 and Aes secrets are generated or imported
+/// into the token as sensitive, non-extractable objects, and are
+/// found again when the token is opened.  Their usage is enforced by
+/// the token, and they can never be exported.  Ephemeral secrets, and the
+/// secrets derived with [`Hasher::hkdf_sha256`] or
+/// [`AsymmetricVault::ec_diffie_hellman`], are kept in memory with a
+/// [`SoftwareVault`], since PKCS#11 lacks the algorithms key
+/// exchanges need on them.
+///
+/// Profiles stored in this vault must use P256 or Ed25519 keys, see
+/// `Profile::create_with_key_type`.
+///
+/// # Examples
+/// ```no_run
+/// use ockam_vault_pkcs11::Pkcs11Vault;
+/// use ockam_vault_core::{SecretAttributes, SecretType, SecretPersistence, P256_SECRET_LENGTH, SecretVault, Signer, Verifier};
+///
+/// fn example() -> ockam_core::Result<()> {
+///     let mut vault = Pkcs11Vault::open("/usr/lib/softhsm/libsofthsm2.so", "ockam", "1234")?;
+///
+///     let attributes = SecretAttributes::new(
+///         SecretType::P256,
+///         SecretPersistence::Persistent,
+///         P256_SECRET_LENGTH,
+///     );
+///
+///     let secret = vault.secret_generate(attributes)?;
+///     let public = vault.secret_public_key_get(&secret)?;
+///
+///     let data = "Very important stuff".as_bytes();
+///
+///     let signature = vault.sign(&secret, data)?;
+///     vault.verify(&signature, public.as_ref(), SecretType::P256, data)
+/// }
+/// ```
+///
+/// [`Hasher::hkdf_sha256`]: ockam_vault_core::Hasher::hkdf_sha256
+/// [`AsymmetricVault::ec_diffie_hellman`]: ockam_vault_core::AsymmetricVault::ec_diffie_hellman
+pub struct Pkcs11Vault {
+    pub(crate) token: Token,
+    pub(crate) software: SoftwareVault,
+    pub(crate) entries: BTreeMap<usize, VaultEntry>,
+    pub(crate) next_id: usize,
+}
+
+impl Pkcs11Vault {
+    /// Open the token labeled `token_label` with the PKCS#11 `module`,
+    /// logging in with the user `pin`
+    pub fn open(module: impl AsRef<OsStr>, token_label: &str, pin: &str) -> Result<Self> {
+        let mut vault = Self {
+            token: Token::open(module.as_ref(), token_label, pin)?,
+            software: SoftwareVault::default(),
+            entries: Default::default(),
+            next_id: 0,
+        };
+        vault.load_token_keys()?;
+        Ok(vault)
+    }
+
+    /// Add the keys stored on the token by previous sessions
+    fn load_token_keys(&mut self) -> Result<()> {
+        let private_keys = Template::new()
+            .ulong(CKA_CLASS, CKO_PRIVATE_KEY)
+            .bool(CKA_TOKEN, true);
+        for private in self.token.find_objects(private_keys)? {
+            let (stype, length) = match self.token.ulong_attribute(private, CKA_KEY_TYPE)? {
+                CKK_EC if self.token.attribute(private, CKA_EC_PARAMS)? == P256_PARAMS => {
+                    (SecretType::P256, P256_SECRET_LENGTH)
+                }
+                CKK_EC_EDWARDS => (SecretType::Ed25519, ED25519_SECRET_LENGTH),
+                _ => continue,
+            };
+            // Key pairs are matched by id
+            let public_keys = Template::new()
+                .ulong(CKA_CLASS, CKO_PUBLIC_KEY)
+                .bytes(CKA_ID, &self.token.attribute(private, CKA_ID)?);
+            let public = match self.token.find_objects(public_keys)?.first() {
+                Some(public) => *public,
+                None => continue,
+            };
+            let sign = self.token.bool_attribute(private, CKA_SIGN)?;
+            let derive = self.token.bool_attribute(private, CKA_DERIVE)?;
+            // Ed25519 keys can only sign, so they never have CKA_DERIVE
+            let usage = match (stype, sign, derive) {
+                (SecretType::P256, true, true) => SecretUsage::Any,
+                (_, true, _) => SecretUsage::Sign,
+                _ => SecretUsage::Derive,
+            };
+            let attributes = SecretAttributes::new(stype, SecretPersistence::Persistent, length)
+                .with_usage(usage)
+                .with_exportable(false);
+            let key = self.token_key_pair(public, private, attributes)?;
+            self.add_entry(VaultEntry::Token(key));
+        }
+
+        let secret_keys = Template::new()
+            .ulong(CKA_CLASS, CKO_SECRET_KEY)
+            .ulong(CKA_KEY_TYPE, CKK_AES)
+            .bool(CKA_TOKEN, true);
+        for key in self.token.find_objects(secret_keys)? {
+            let length = self.token.ulong_attribute(key, CKA_VALUE_LEN)? as usize;
+            let attributes =
+                SecretAttributes::new(SecretType::Aes, SecretPersistence::Persistent, length)
+                    .with_exportable(false);
+            self.add_entry(VaultEntry::Token(TokenKey {
+                private: key,
+                public: None,
+                attributes,
+                public_key: None,
+                key_id: None,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Describe a key pair of the token
+    pub(crate) fn token_key_pair(
+        &self,
+        public: CK_OBJECT_HANDLE,
+        private: CK_OBJECT_HANDLE,
+        attributes: SecretAttributes,
+    ) -> Result<TokenKey> {
+        let length = match attributes.stype() {
+            SecretType::P256 => P256_PUBLIC_LENGTH,
+            SecretType::Ed25519 => ED25519_PUBLIC_LENGTH,
+            _ => return Err(Pkcs11Error::InvalidKeyType.into()),
+        };
+        let mut point = self.token.attribute(public, CKA_EC_POINT)?;
+        // Points are usually returned as a DER octet string
+        if point.len() == length + 2 {
+            point.drain(..2);
+        }
+        if point.len() != length {
+            return Err(Pkcs11Error::GetAttribute.into());
+        }
+
+        let public_key = PublicKey::new(point);
+        let key_id = self.software.compute_key_id_for_public_key(&public_key)?;
+        Ok(TokenKey {
+            private,
+            public: Some(public),
+            attributes,
+            public_key: Some(public_key),
+            key_id: Some(key_id),
+        })
+    }
+
+    pub(crate) fn get_entry(&self, context: &Secret) -> Result<&VaultEntry> {
+        self.entries
+            .get(&context.index())
+            .ok_or_else(|| Pkcs11Error::EntryNotFound.into())
+    }
+
+    /// Get the entry of a secret which may be used for `usage`. The
+    /// software vault checks the usage of its own secrets
+    pub(crate) fn get_entry_for(
+        &self,
+        context: &Secret,
+        usage: SecretUsage,
+    ) -> Result<&VaultEntry> {
+        let entry = self.get_entry(context)?;
+        match entry {
+            VaultEntry::Token(key) if !key.attributes.usage().permits(usage) => {
+                Err(Pkcs11Error::InvalidSecretUsage.into())
+            }
+            _ => Ok(entry),
+        }
+    }
+
+    pub(crate) fn add_entry(&mut self, entry: VaultEntry) -> Secret {
+        self.next_id += 1;
+        self.entries.insert(self.next_id, entry);
+        Secret::new(self.next_id)
+    }
+
+    /// Track a secret of the software vault
+    pub(crate) fn add_software(&mut self, secret: Secret) -> Secret {
+        self.add_entry(VaultEntry::Software(secret))
+    }
+
+    /// The software vault secret of an entry, for operations only
+    /// available in software
+    pub(crate) fn software_secret(&self, context: &Secret) -> Result<Secret> {
+        match self.get_entry(context)? {
+            VaultEntry::Software(secret) => Ok(secret.clone()),
+            VaultEntry::Token(_) => Err(Pkcs11Error::InvalidKeyType.into()),
+        }
+    }
+}
+
+impl Zeroize for Pkcs11Vault {
+    fn zeroize(&mut self) {
+        // Keys on the token stay usable
+        self.software.zeroize();
+        self.entries
+            .retain(|_, entry| matches!(entry, VaultEntry::Token(_)));
+    }
+}
+
+zdrop_impl!(Pkcs11Vault);
+
+/// A secret of a [`Pkcs11Vault`]
+pub(crate) enum VaultEntry {
+    Software(Secret),
+    Token(TokenKey),
+}
+
+/// A key held by the token
+pub(crate) struct TokenKey {
+    pub(crate) private: CK_OBJECT_HANDLE,
+    pub(crate) public: Option<CK_OBJECT_HANDLE>,
+    pub(crate) attributes: SecretAttributes,
+    pub(crate) public_key: Option<PublicKey>,
+    pub(crate) key_id: Option<KeyId>,
+}
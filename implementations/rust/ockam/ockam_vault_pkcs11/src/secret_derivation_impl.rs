@@ -0,0 +1,37 @@
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use ockam_core::Result;
+use ockam_vault_core::{
+    Secret, SecretAttributes, SecretDerivationVault, SecretPersistence, SecretVault,
+};
+
+impl SecretDerivationVault for Pkcs11Vault {
+    /// Derive a secret from an ephemeral seed. Persistent secrets are
+    /// derived in software and imported into the token, which needs a
+    /// seed that can be exported
+    fn secret_derive(
+        &mut self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> Result<Secret> {
+        let seed = self.software_secret(seed)?;
+        match attributes.persistence() {
+            SecretPersistence::Persistent => {
+                let ephemeral = SecretAttributes::new(
+                    attributes.stype(),
+                    SecretPersistence::Ephemeral,
+                    attributes.length(),
+                );
+                let secret = self.software.secret_derive(&seed, path, ephemeral)?;
+                let key = self.software.secret_export(&secret);
+                self.software.secret_destroy(secret)?;
+                let key = self.token_import(key?.as_ref(), attributes)?;
+                Ok(self.add_entry(VaultEntry::Token(key)))
+            }
+            SecretPersistence::Ephemeral => {
+                let secret = self.software.secret_derive(&seed, path, attributes)?;
+                Ok(self.add_software(secret))
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::cryptoki::*;
+use crate::pkcs11_vault::{Pkcs11Vault, TokenKey, VaultEntry, ED25519_PARAMS, P256_PARAMS};
+use crate::token::{Mechanism, Template};
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{
+    PublicKey, Secret, SecretAttributes, SecretKey, SecretPersistence, SecretType, SecretUsage,
+    SecretVault, AES128_SECRET_LENGTH, AES256_SECRET_LENGTH, ED25519_SECRET_LENGTH,
+    P256_SECRET_LENGTH,
+};
+use rand::{thread_rng, RngCore};
+
+/// A fresh id pairing the public and private objects of a key
+fn new_object_id() -> [u8; 16] {
+    let mut id = [0u8; 16];
+    thread_rng().fill_bytes(&mut id);
+    id
+}
+
+/// Attributes shared by all the keys generated or imported into the token
+fn key_template(class: CK_ULONG, key_type: CK_ULONG, id: &[u8]) -> Template {
+    Template::new()
+        .ulong(CKA_CLASS, class)
+        .ulong(CKA_KEY_TYPE, key_type)
+        .bool(CKA_TOKEN, true)
+        .bytes(CKA_ID, id)
+}
+
+/// The token enforces the usage of private keys
+fn private_template(key_type: CK_ULONG, id: &[u8], usage: SecretUsage) -> Template {
+    key_template(CKO_PRIVATE_KEY, key_type, id)
+        .bool(CKA_PRIVATE, true)
+        .bool(CKA_SENSITIVE, true)
+        .bool(CKA_EXTRACTABLE, false)
+        .bool(CKA_SIGN, usage.permits(SecretUsage::Sign))
+        .bool(
+            CKA_DERIVE,
+            key_type == CKK_EC && usage.permits(SecretUsage::Derive),
+        )
+}
+
+fn public_template(key_type: CK_ULONG, id: &[u8]) -> Template {
+    key_template(CKO_PUBLIC_KEY, key_type, id).bool(CKA_VERIFY, true)
+}
+
+fn aes_template(id: &[u8]) -> Template {
+    key_template(CKO_SECRET_KEY, CKK_AES, id)
+        .bool(CKA_PRIVATE, true)
+        .bool(CKA_SENSITIVE, true)
+        .bool(CKA_EXTRACTABLE, false)
+        .bool(CKA_ENCRYPT, true)
+        .bool(CKA_DECRYPT, true)
+}
+
+/// The key type and curve parameters of a key pair
+fn curve(attributes: SecretAttributes) -> Result<(CK_ULONG, &'static [u8])> {
+    match attributes.stype() {
+        SecretType::P256 if attributes.length() == P256_SECRET_LENGTH => Ok((CKK_EC, P256_PARAMS)),
+        SecretType::Ed25519 if attributes.length() == ED25519_SECRET_LENGTH => {
+            Ok((CKK_EC_EDWARDS, ED25519_PARAMS))
+        }
+        _ => Err(Pkcs11Error::InvalidKeyType.into()),
+    }
+}
+
+fn check_aes_length(attributes: SecretAttributes) -> Result<()> {
+    if attributes.length() == AES256_SECRET_LENGTH || attributes.length() == AES128_SECRET_LENGTH {
+        Ok(())
+    } else {
+        Err(Pkcs11Error::InvalidKeyType.into())
+    }
+}
+
+impl Pkcs11Vault {
+    /// Generate a non-extractable key on the token
+    fn token_generate(&mut self, attributes: SecretAttributes) -> Result<TokenKey> {
+        let attributes = attributes.with_exportable(false);
+        let id = new_object_id();
+        if attributes.stype() == SecretType::Aes {
+            check_aes_length(attributes)?;
+            let template = aes_template(&id).ulong(CKA_VALUE_LEN, attributes.length() as CK_ULONG);
+            let key = self.token.generate_key(Mechanism::AesKeyGen, template)?;
+            return Ok(TokenKey {
+                private: key,
+                public: None,
+                attributes,
+                public_key: None,
+                key_id: None,
+            });
+        }
+
+        let (key_type, params) = curve(attributes)?;
+        let mechanism = if key_type == CKK_EC {
+            Mechanism::EcKeyPairGen
+        } else {
+            Mechanism::EcEdwardsKeyPairGen
+        };
+        // Key pair generation takes the curve from the public template
+        let (public, private) = self.token.generate_key_pair(
+            mechanism,
+            public_template(key_type, &id).bytes(CKA_EC_PARAMS, params),
+            private_template(key_type, &id, attributes.usage()),
+        )?;
+        self.token_key_pair(public, private, attributes)
+    }
+
+    /// Import a key into the token, where it can't be extracted anymore
+    pub(crate) fn token_import(
+        &mut self,
+        secret: &[u8],
+        attributes: SecretAttributes,
+    ) -> Result<TokenKey> {
+        if secret.len() != attributes.length() {
+            return Err(Pkcs11Error::InvalidKeyType.into());
+        }
+        let attributes = attributes.with_exportable(false);
+        let id = new_object_id();
+        if attributes.stype() == SecretType::Aes {
+            check_aes_length(attributes)?;
+            let key = self
+                .token
+                .create_object(aes_template(&id).bytes(CKA_VALUE, secret))?;
+            return Ok(TokenKey {
+                private: key,
+                public: None,
+                attributes,
+                public_key: None,
+                key_id: None,
+            });
+        }
+
+        let (key_type, params) = curve(attributes)?;
+        // The token doesn't compute the public key of imported keys
+        let public_key = {
+            let ephemeral = SecretAttributes::new(
+                attributes.stype(),
+                SecretPersistence::Ephemeral,
+                secret.len(),
+            );
+            let software_secret = self.software.secret_import(secret, ephemeral)?;
+            let public_key = self.software.secret_public_key_get(&software_secret);
+            self.software.secret_destroy(software_secret)?;
+            public_key?
+        };
+        let mut point = vec![0x04, public_key.as_ref().len() as u8];
+        point.extend_from_slice(public_key.as_ref());
+
+        let public = self.token.create_object(
+            public_template(key_type, &id)
+                .bytes(CKA_EC_PARAMS, params)
+                .bytes(CKA_EC_POINT, &point),
+        )?;
+        let private = self.token.create_object(
+            private_template(key_type, &id, attributes.usage())
+                .bytes(CKA_EC_PARAMS, params)
+                .bytes(CKA_VALUE, secret),
+        );
+        let private = match private {
+            Ok(private) => private,
+            Err(err) => {
+                self.token.destroy_object(public)?;
+                return Err(err);
+            }
+        };
+        self.token_key_pair(public, private, attributes)
+    }
+}
+
+impl SecretVault for Pkcs11Vault {
+    /// Generate fresh secret. Persistent secrets are generated on the
+    /// token, which supports P256, Ed25519 and Aes types
+    fn secret_generate(&mut self, attributes: SecretAttributes) -> Result<Secret> {
+        match attributes.persistence() {
+            SecretPersistence::Persistent => {
+                let key = self.token_generate(attributes)?;
+                Ok(self.add_entry(VaultEntry::Token(key)))
+            }
+            SecretPersistence::Ephemeral => {
+                let secret = self.software.secret_generate(attributes)?;
+                Ok(self.add_software(secret))
+            }
+        }
+    }
+
+    /// Import a secret. Persistent secrets are imported into the token,
+    /// which supports P256, Ed25519 and Aes types
+    fn secret_import(&mut self, secret: &[u8], attributes: SecretAttributes) -> Result<Secret> {
+        match attributes.persistence() {
+            SecretPersistence::Persistent => {
+                let key = self.token_import(secret, attributes)?;
+                Ok(self.add_entry(VaultEntry::Token(key)))
+            }
+            SecretPersistence::Ephemeral => {
+                let secret = self.software.secret_import(secret, attributes)?;
+                Ok(self.add_software(secret))
+            }
+        }
+    }
+
+    /// Export an ephemeral secret. Keys held by the token can't be
+    /// exported, whatever their attributes asked for
+    fn secret_export(&mut self, context: &Secret) -> Result<SecretKey> {
+        match self.get_entry(context)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software.secret_export(&secret)
+            }
+            VaultEntry::Token(_) => Err(Pkcs11Error::SecretNotExtractable.into()),
+        }
+    }
+
+    fn secret_attributes_get(&mut self, context: &Secret) -> Result<SecretAttributes> {
+        match self.get_entry(context)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software.secret_attributes_get(&secret)
+            }
+            VaultEntry::Token(key) => Ok(key.attributes),
+        }
+    }
+
+    fn secret_public_key_get(&mut self, context: &Secret) -> Result<PublicKey> {
+        match self.get_entry(context)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software.secret_public_key_get(&secret)
+            }
+            VaultEntry::Token(key) => key
+                .public_key
+                .clone()
+                .ok_or_else(|| Pkcs11Error::InvalidKeyType.into()),
+        }
+    }
+
+    /// Remove secret, deleting it from the token if it is held there
+    fn secret_destroy(&mut self, context: Secret) -> Result<()> {
+        match self.entries.remove(&context.index()) {
+            Some(VaultEntry::Software(secret)) => self.software.secret_destroy(secret),
+            Some(VaultEntry::Token(key)) => {
+                if let Some(public) = key.public {
+                    self.token.destroy_object(public)?;
+                }
+                self.token.destroy_object(key.private)
+            }
+            None => Err(Pkcs11Error::EntryNotFound.into()),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{
+    Buffer, PublicKey, Secret, SecretPersistence, SecretSharingVault, SecretVault, SmallBuffer,
+};
+
+impl SecretSharingVault for Pkcs11Vault {
+    /// Split an ephemeral secret which can be exported. Keys held by the
+    /// token can't be split
+    fn secret_split(
+        &mut self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> Result<SmallBuffer<Buffer<u8>>> {
+        match self.get_entry(context)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software.secret_split(&secret, threshold, guardians)
+            }
+            VaultEntry::Token(_) => Err(Pkcs11Error::SecretNotExtractable.into()),
+        }
+    }
+
+    fn secret_share_reencrypt(
+        &mut self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> Result<Buffer<u8>> {
+        let guardian = self.software_secret(guardian)?;
+        self.software
+            .secret_share_reencrypt(&guardian, share, recipient)
+    }
+
+    /// Recover a secret. Persistent secrets are imported into the
+    /// token, unless they can't be exported: the software vault doesn't
+    /// let their key out
+    fn secret_recover(&mut self, recipient: &Secret, shares: &[Buffer<u8>]) -> Result<Secret> {
+        let recipient = self.software_secret(recipient)?;
+        let secret = self.software.secret_recover(&recipient, shares)?;
+        let attributes = self.software.secret_attributes_get(&secret)?;
+        match attributes.persistence() {
+            SecretPersistence::Persistent => {
+                let key = self.software.secret_export(&secret);
+                self.software.secret_destroy(secret)?;
+                let key = self.token_import(key?.as_ref(), attributes)?;
+                Ok(self.add_entry(VaultEntry::Token(key)))
+            }
+            SecretPersistence::Ephemeral => Ok(self.add_software(secret)),
+        }
+    }
+}
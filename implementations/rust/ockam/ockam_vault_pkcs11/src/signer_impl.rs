@@ -0,0 +1,34 @@
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use crate::token::Mechanism;
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{Hasher, Secret, SecretType, SecretUsage, Signer};
+use std::convert::TryInto;
+
+impl Signer for Pkcs11Vault {
+    /// Sign data with ECDSA (SHA-256) for P256 keys and with Ed25519
+    /// (RFC 8032) for Ed25519 keys held by the token. Ephemeral keys
+    /// are signed with in software.
+    fn sign(&mut self, secret_key: &Secret, data: &[u8]) -> Result<[u8; 64]> {
+        let key = match self.get_entry_for(secret_key, SecretUsage::Sign)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                return self.software.sign(&secret, data);
+            }
+            VaultEntry::Token(key) => key,
+        };
+        let signature = match key.attributes.stype() {
+            // CKM_ECDSA signs a digest
+            SecretType::P256 => {
+                let digest = self.software.sha256(data)?;
+                self.token.sign(Mechanism::Ecdsa, key.private, &digest)?
+            }
+            SecretType::Ed25519 => self.token.sign(Mechanism::EdDsa, key.private, data)?,
+            _ => return Err(Pkcs11Error::InvalidKeyType.into()),
+        };
+        signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| Pkcs11Error::Sign.into())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::pkcs11_vault::{Pkcs11Vault, VaultEntry};
+use crate::token::Mechanism;
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use ockam_vault_core::{Buffer, Secret, SecretType, SecretUsage, SymmetricVault};
+
+impl SymmetricVault for Pkcs11Vault {
+    /// Encrypt with AES-GCM, on the token for keys held there
+    fn aead_aes_gcm_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        match self.get_entry_for(context, SecretUsage::Any)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software
+                    .aead_aes_gcm_encrypt(&secret, plaintext, nonce, aad)
+            }
+            VaultEntry::Token(key) if key.attributes.stype() == SecretType::Aes => self
+                .token
+                .encrypt(Mechanism::AesGcm { nonce, aad }, key.private, plaintext),
+            VaultEntry::Token(_) => Err(Pkcs11Error::InvalidKeyType.into()),
+        }
+    }
+
+    /// Decrypt with AES-GCM, on the token for keys held there
+    fn aead_aes_gcm_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        match self.get_entry_for(context, SecretUsage::Any)? {
+            VaultEntry::Software(secret) => {
+                let secret = secret.clone();
+                self.software
+                    .aead_aes_gcm_decrypt(&secret, cipher_text, nonce, aad)
+            }
+            VaultEntry::Token(key) if key.attributes.stype() == SecretType::Aes => self
+                .token
+                .decrypt(Mechanism::AesGcm { nonce, aad }, key.private, cipher_text),
+            VaultEntry::Token(_) => Err(Pkcs11Error::InvalidKeyType.into()),
+        }
+    }
+
+    /// Encrypt with ChaCha20-Poly1305. Only ephemeral keys are supported
+    fn aead_chacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let secret = self.software_secret(context)?;
+        self.software
+            .aead_chacha20_poly1305_encrypt(&secret, plaintext, nonce, aad)
+    }
+
+    /// Decrypt with ChaCha20-Poly1305. Only ephemeral keys are supported
+    fn aead_chacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let secret = self.software_secret(context)?;
+        self.software
+            .aead_chacha20_poly1305_decrypt(&secret, cipher_text, nonce, aad)
+    }
+
+    /// Encrypt with XChaCha20-Poly1305. Only ephemeral keys are supported
+    fn aead_xchacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let secret = self.software_secret(context)?;
+        self.software
+            .aead_xchacha20_poly1305_encrypt(&secret, plaintext, nonce, aad)
+    }
+
+    /// Decrypt with XChaCha20-Poly1305. Only ephemeral keys are supported
+    fn aead_xchacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        let secret = self.software_secret(context)?;
+        self.software
+            .aead_xchacha20_poly1305_decrypt(&secret, cipher_text, nonce, aad)
+    }
+}
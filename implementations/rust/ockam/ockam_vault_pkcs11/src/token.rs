@@ -0,0 +1,246 @@
+use crate::cryptoki::*;
+use crate::Pkcs11Error;
+use ockam_core::Result;
+use std::ffi::OsStr;
+use std::mem::size_of;
+
+pub(crate) use crate::cryptoki::{Mechanism, Template};
+
+fn check(rv: CK_RV, err: Pkcs11Error) -> Result<()> {
+    if rv == CKR_OK {
+        Ok(())
+    } else {
+        Err(err.into())
+    }
+}
+
+/// A logged in session with a PKCS#11 token
+///
+/// The module is initialized once per process and never finalized,
+/// since other sessions may still use it.
+pub(crate) struct Token {
+    module: Module,
+    session: CK_SESSION_HANDLE,
+}
+
+impl Token {
+    /// Load the PKCS#11 `module` and log into the token labeled `label`
+    pub(crate) fn open(module: &OsStr, label: &str, pin: &str) -> Result<Self> {
+        let module = Module::load(module).ok_or(Pkcs11Error::ModuleLoad)?;
+
+        let rv = module.initialize();
+        if rv != CKR_CRYPTOKI_ALREADY_INITIALIZED {
+            check(rv, Pkcs11Error::Initialize)?;
+        }
+
+        let slot = Self::find_slot(&module, label)?;
+        let session = module
+            .open_session(slot, CKF_SERIAL_SESSION | CKF_RW_SESSION)
+            .map_err(|_| Pkcs11Error::OpenSession)?;
+        let token = Self { module, session };
+
+        let rv = token.module.login(session, CKU_USER, pin.as_bytes());
+        if rv != CKR_USER_ALREADY_LOGGED_IN {
+            check(rv, Pkcs11Error::Login)?;
+        }
+
+        Ok(token)
+    }
+
+    fn find_slot(module: &Module, label: &str) -> Result<CK_SLOT_ID> {
+        let slots = module.slot_list().map_err(|_| Pkcs11Error::TokenNotFound)?;
+
+        for slot in slots {
+            let info = match module.token_info(slot) {
+                Ok(info) => info,
+                Err(_) => continue,
+            };
+            // Labels are padded with blanks
+            if String::from_utf8_lossy(&info.label).trim_end() == label {
+                return Ok(slot);
+            }
+        }
+
+        Err(Pkcs11Error::TokenNotFound.into())
+    }
+
+    pub(crate) fn find_objects(&self, mut template: Template) -> Result<Vec<CK_OBJECT_HANDLE>> {
+        check(
+            self.module.find_objects_init(self.session, &mut template),
+            Pkcs11Error::FindObjects,
+        )?;
+
+        let mut objects = Vec::new();
+        let mut result = Ok(());
+        loop {
+            let mut batch = [0; 16];
+            match self.module.find_objects(self.session, &mut batch) {
+                Ok(0) => break,
+                Ok(count) => objects.extend_from_slice(&batch[..count]),
+                Err(rv) => {
+                    result = check(rv, Pkcs11Error::FindObjects);
+                    break;
+                }
+            }
+        }
+
+        // The search must be finished even if it failed
+        check(
+            self.module.find_objects_final(self.session),
+            Pkcs11Error::FindObjects,
+        )?;
+        result.map(|_| objects)
+    }
+
+    pub(crate) fn attribute(
+        &self,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<Vec<u8>> {
+        self.module
+            .attribute_value(self.session, object, attribute)
+            .ok()
+            .flatten()
+            .ok_or_else(|| Pkcs11Error::GetAttribute.into())
+    }
+
+    pub(crate) fn ulong_attribute(
+        &self,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<CK_ULONG> {
+        let value = self.attribute(object, attribute)?;
+        let mut bytes = [0u8; size_of::<CK_ULONG>()];
+        if value.len() != bytes.len() {
+            return Err(Pkcs11Error::GetAttribute.into());
+        }
+        bytes.copy_from_slice(&value);
+        Ok(CK_ULONG::from_ne_bytes(bytes))
+    }
+
+    pub(crate) fn bool_attribute(
+        &self,
+        object: CK_OBJECT_HANDLE,
+        attribute: CK_ATTRIBUTE_TYPE,
+    ) -> Result<bool> {
+        match self.attribute(object, attribute)?.as_slice() {
+            [value] => Ok(*value != CK_FALSE),
+            _ => Err(Pkcs11Error::GetAttribute.into()),
+        }
+    }
+
+    pub(crate) fn generate_key(
+        &self,
+        mechanism: Mechanism,
+        mut template: Template,
+    ) -> Result<CK_OBJECT_HANDLE> {
+        self.module
+            .generate_key(self.session, &mechanism, &mut template)
+            .map_err(|_| Pkcs11Error::GenerateKey.into())
+    }
+
+    /// Generate a key pair, returning the public and the private key
+    pub(crate) fn generate_key_pair(
+        &self,
+        mechanism: Mechanism,
+        mut public_template: Template,
+        mut private_template: Template,
+    ) -> Result<(CK_OBJECT_HANDLE, CK_OBJECT_HANDLE)> {
+        self.module
+            .generate_key_pair(
+                self.session,
+                &mechanism,
+                &mut public_template,
+                &mut private_template,
+            )
+            .map_err(|_| Pkcs11Error::GenerateKey.into())
+    }
+
+    pub(crate) fn create_object(&self, mut template: Template) -> Result<CK_OBJECT_HANDLE> {
+        self.module
+            .create_object(self.session, &mut template)
+            .map_err(|_| Pkcs11Error::CreateObject.into())
+    }
+
+    pub(crate) fn destroy_object(&self, object: CK_OBJECT_HANDLE) -> Result<()> {
+        check(
+            self.module.destroy_object(self.session, object),
+            Pkcs11Error::DestroyObject,
+        )
+    }
+
+    pub(crate) fn derive_key(
+        &self,
+        mechanism: Mechanism,
+        base_key: CK_OBJECT_HANDLE,
+        mut template: Template,
+    ) -> Result<CK_OBJECT_HANDLE> {
+        self.module
+            .derive_key(self.session, &mechanism, base_key, &mut template)
+            .map_err(|_| Pkcs11Error::DeriveKey.into())
+    }
+
+    pub(crate) fn sign(
+        &self,
+        mechanism: Mechanism,
+        key: CK_OBJECT_HANDLE,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.single_part(SinglePart::Sign, mechanism, key, data, Pkcs11Error::Sign)
+    }
+
+    pub(crate) fn encrypt(
+        &self,
+        mechanism: Mechanism,
+        key: CK_OBJECT_HANDLE,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.single_part(
+            SinglePart::Encrypt,
+            mechanism,
+            key,
+            data,
+            Pkcs11Error::Encrypt,
+        )
+    }
+
+    pub(crate) fn decrypt(
+        &self,
+        mechanism: Mechanism,
+        key: CK_OBJECT_HANDLE,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.single_part(
+            SinglePart::Decrypt,
+            mechanism,
+            key,
+            data,
+            Pkcs11Error::Decrypt,
+        )
+    }
+
+    fn single_part(
+        &self,
+        operation: SinglePart,
+        mechanism: Mechanism,
+        key: CK_OBJECT_HANDLE,
+        data: &[u8],
+        err: Pkcs11Error,
+    ) -> Result<Vec<u8>> {
+        check(
+            self.module.init(operation, self.session, &mechanism, key),
+            err,
+        )?;
+        self.module
+            .run(operation, self.session, data)
+            .map_err(|_| err.into())
+    }
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        // Logging out is left to the module, which does it when the
+        // last session of the application is closed
+        self.module.close_session(self.session);
+    }
+}
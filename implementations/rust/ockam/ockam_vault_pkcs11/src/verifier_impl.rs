@@ -0,0 +1,16 @@
+use crate::pkcs11_vault::Pkcs11Vault;
+use ockam_core::Result;
+use ockam_vault_core::{SecretType, Verifier};
+
+impl Verifier for Pkcs11Vault {
+    /// Verify signature in software, like [`ockam_vault::SoftwareVault`]
+    fn verify(
+        &mut self,
+        signature: &[u8; 64],
+        public_key: &[u8],
+        key_type: SecretType,
+        data: &[u8],
+    ) -> Result<()> {
+        self.software.verify(signature, public_key, key_type, data)
+    }
+}
@@ -0,0 +1,35 @@
+use ockam_core::Error;
+
+/// Represents the failures that can occur when using
+/// a vault over routes
+///
+/// Errors of the remote vault keep their code, but are returned under
+/// the domain of this type since domain names are not sent.
+#[derive(Clone, Copy, Debug)]
+pub enum VaultServiceError {
+    None,
+    /// The request can't be passed to the vault
+    InvalidRequest,
+    /// The vault service answered with an unexpected result
+    InvalidResponse,
+    /// The vault service did not answer in time
+    ResponseTimeout,
+    /// The secret is not known to the client of the vault service
+    UnknownSecret,
+}
+
+impl VaultServiceError {
+    /// Integer code associated with the error domain.
+    pub const DOMAIN_CODE: u32 = 19_000;
+    /// Descriptive name for the error domain.
+    pub const DOMAIN_NAME: &'static str = "OCKAM_VAULT_SERVICE";
+}
+
+impl From<VaultServiceError> for Error {
+    fn from(err: VaultServiceError) -> Self {
+        Self::new(
+            VaultServiceError::DOMAIN_CODE + (err as u32),
+            VaultServiceError::DOMAIN_NAME,
+        )
+    }
+}
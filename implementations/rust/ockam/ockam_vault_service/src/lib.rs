@@ -0,0 +1,263 @@
+//! A vault served over routes
+//!
+//! A [`VaultService`] worker serves the vault of one node to the
+//! workers of other nodes, so that a single hardened node can hold
+//! the keys of several application nodes.  A [`RemoteVault`]
+//! implements the `ockam_vault_core` traits by sending requests to
+//! such a service, and can be used wherever a local vault is
+//! expected.
+
+#![deny(
+    // missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications,
+    warnings
+)]
+
+#[macro_use]
+extern crate tracing;
+
+mod error;
+mod messages;
+mod remote;
+mod service;
+
+pub use error::*;
+pub use messages::*;
+pub use remote::*;
+pub use service::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ockam_core::{AllowAll, LocalOnly, TransportMessage, Typed};
+    use ockam_node::{DeadLetter, NodeBuilder};
+    use ockam_vault::SoftwareVault;
+    use ockam_vault_core::{
+        AsymmetricVault, Hasher, KeyIdVault, SecretAttributes, SecretPersistence, SecretType,
+        SecretVault, Signer, SymmetricVault, Verifier, AES256_SECRET_LENGTH,
+        CURVE25519_SECRET_LENGTH,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn remote_vault() {
+        let (ctx, mut executor) = ockam_node::start_node();
+        executor
+            .execute_test(
+                async move {
+                    let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+                    ctx.start_worker("vault", VaultService::new(vault.clone(), AllowAll))
+                        .await?;
+                    let mut remote = RemoteVault::create(&ctx, "vault").await?;
+
+                    let attributes = SecretAttributes::new(
+                        SecretType::Curve25519,
+                        SecretPersistence::Ephemeral,
+                        CURVE25519_SECRET_LENGTH,
+                    );
+                    let alice = remote.secret_generate(attributes)?;
+                    let bob = remote.secret_generate(attributes)?;
+                    let alice_public = remote.secret_public_key_get(&alice)?;
+                    let bob_public = remote.secret_public_key_get(&bob)?;
+
+                    // Keys are held by the served vault
+                    let key_id = remote.compute_key_id_for_public_key(&alice_public)?;
+                    {
+                        let mut local = vault.lock().unwrap();
+                        let local_alice = local.get_secret_by_key_id(&key_id)?;
+                        assert_eq!(local.secret_public_key_get(&local_alice)?, alice_public);
+                    }
+
+                    let signature = remote.sign(&alice, b"hello")?;
+                    remote.verify(
+                        &signature,
+                        alice_public.as_ref(),
+                        SecretType::Curve25519,
+                        b"hello",
+                    )?;
+                    assert!(remote
+                        .verify(
+                            &signature,
+                            bob_public.as_ref(),
+                            SecretType::Curve25519,
+                            b"hello"
+                        )
+                        .is_err());
+
+                    let shared = remote.ec_diffie_hellman(&alice, bob_public.as_ref())?;
+                    let key_attributes = SecretAttributes::new(
+                        SecretType::Aes,
+                        SecretPersistence::Ephemeral,
+                        AES256_SECRET_LENGTH,
+                    )
+                    .with_exportable(false);
+                    let key = remote
+                        .hkdf_sha256(&shared, b"", None, vec![key_attributes])?
+                        .pop()
+                        .unwrap();
+                    assert_eq!(remote.secret_attributes_get(&key)?, key_attributes);
+
+                    let nonce = [0u8; 12];
+                    let ciphertext = remote.aead_aes_gcm_encrypt(&key, b"hello", &nonce, b"")?;
+                    let plaintext = remote.aead_aes_gcm_decrypt(&key, &ciphertext, &nonce, b"")?;
+                    assert_eq!(plaintext, b"hello");
+                    assert_eq!(remote.sha256(b"")?, vault.lock().unwrap().sha256(b"")?);
+
+                    // Errors of the served vault keep their code
+                    let err = remote.secret_export(&key).unwrap_err();
+                    assert_eq!(err.domain(), VaultServiceError::DOMAIN_NAME);
+                    assert_eq!(err.code() / 1000, 12);
+
+                    // Destroyed secrets are forgotten
+                    remote.secret_destroy(key.clone())?;
+                    let err = remote.secret_attributes_get(&key).unwrap_err();
+                    assert_eq!(
+                        err.code(),
+                        ockam_core::Error::from(VaultServiceError::UnknownSecret).code()
+                    );
+
+                    ctx.stop().await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn async_vaults() {
+        use ockam_vault_core::{AsyncKeyIdVault, AsyncSecretVault, AsyncSigner, AsyncVerifier};
+
+        let (ctx, mut executor) = ockam_node::start_node();
+        executor
+            .execute_test(
+                async move {
+                    let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+                    ctx.start_worker("vault", VaultService::new(vault.clone(), AllowAll))
+                        .await?;
+                    let remote = RemoteVault::create(&ctx, "vault").await?;
+
+                    let attributes = SecretAttributes::new(
+                        SecretType::Curve25519,
+                        SecretPersistence::Ephemeral,
+                        CURVE25519_SECRET_LENGTH,
+                    );
+                    let secret = AsyncSecretVault::secret_generate(&remote, attributes).await?;
+                    let public_key =
+                        AsyncSecretVault::secret_public_key_get(&remote, &secret).await?;
+                    let signature = AsyncSigner::sign(&remote, &secret, b"hello").await?;
+
+                    // The local vault is reached through the Mutex adapter
+                    let local = &*vault;
+                    let key_id =
+                        AsyncKeyIdVault::compute_key_id_for_public_key(local, &public_key).await?;
+                    let local_secret =
+                        AsyncKeyIdVault::get_secret_by_key_id(local, &key_id).await?;
+                    assert_eq!(
+                        AsyncSecretVault::secret_public_key_get(local, &local_secret).await?,
+                        public_key
+                    );
+                    AsyncVerifier::verify(
+                        local,
+                        &signature,
+                        public_key.as_ref(),
+                        SecretType::Curve25519,
+                        b"hello",
+                    )
+                    .await?;
+
+                    ctx.stop().await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn clients_only_reach_their_own_secrets() {
+        let (ctx, mut executor) = ockam_node::start_node();
+        executor
+            .execute_test(
+                async move {
+                    let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+                    ctx.start_worker("vault", VaultService::new(vault, AllowAll))
+                        .await?;
+                    let mut alice = RemoteVault::create(&ctx, "vault").await?;
+                    let mut bob = RemoteVault::create(&ctx, "vault").await?;
+
+                    let attributes = SecretAttributes::new(
+                        SecretType::Curve25519,
+                        SecretPersistence::Ephemeral,
+                        CURVE25519_SECRET_LENGTH,
+                    );
+                    let secret = alice.secret_generate(attributes)?;
+                    let public_key = alice.secret_public_key_get(&secret)?;
+                    let key_id = alice.compute_key_id_for_public_key(&public_key)?;
+                    assert_eq!(alice.get_secret_by_key_id(&key_id)?.index(), secret.index());
+
+                    // The handles of alice are unknown to bob
+                    let unknown = ockam_core::Error::from(VaultServiceError::UnknownSecret).code();
+                    assert_eq!(bob.secret_export(&secret).unwrap_err().code(), unknown);
+                    assert_eq!(bob.sign(&secret, b"hello").unwrap_err().code(), unknown);
+                    assert_eq!(
+                        bob.secret_destroy(secret.clone()).unwrap_err().code(),
+                        unknown
+                    );
+                    assert_eq!(
+                        bob.get_secret_by_key_id(&key_id).unwrap_err().code(),
+                        unknown
+                    );
+
+                    // Alice can still use her secret
+                    alice.sign(&secret, b"hello")?;
+
+                    ctx.stop().await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn access_control() {
+        let (mut ctx, mut executor) = NodeBuilder::new()
+            .dead_letter_address("app")
+            .build()
+            .unwrap();
+        executor
+            .execute_test(
+                async move {
+                    let vault = Arc::new(Mutex::new(SoftwareVault::default()));
+                    ctx.start_worker("vault", VaultService::new(vault, LocalOnly))
+                        .await?;
+
+                    // Requests forwarded by a transport are denied
+                    let request = Typed::new(VaultRequest {
+                        id: 0,
+                        call: VaultCall::Sha256 { data: vec![] },
+                    });
+                    let payload = ctx
+                        .codec::<Typed<VaultRequest>>()
+                        .encode_message(&request)?;
+                    let mut msg = TransportMessage::v2("vault".into(), payload);
+                    msg.return_ = ctx.address().into();
+                    msg.local.transport = Some("tcp_conn".into());
+                    ctx.forward_message(msg).await?;
+                    let letter = ctx.receive::<Typed<DeadLetter>>().await?.take().take();
+                    assert_eq!(letter.address, "vault".into());
+
+                    // Local clients are served
+                    let remote = RemoteVault::create(&ctx, "vault").await?;
+                    ockam_vault_core::AsyncHasher::sha256(&remote, b"").await?;
+
+                    ctx.stop().await
+                },
+                Duration::from_secs(10),
+            )
+            .unwrap();
+    }
+}
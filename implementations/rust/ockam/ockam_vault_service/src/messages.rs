@@ -0,0 +1,147 @@
+use ockam_core::TypedMessage;
+use ockam_vault_core::{SecretAttributes, SecretType};
+use serde::{Deserialize, Serialize};
+
+/// An AEAD algorithm of [`SymmetricVault`](ockam_vault_core::SymmetricVault)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aead {
+    /// AES-GCM
+    AesGcm,
+    /// ChaCha20-Poly1305
+    ChaCha20Poly1305,
+    /// XChaCha20-Poly1305
+    XChaCha20Poly1305,
+}
+
+/// A call of a vault trait function
+///
+/// Secrets are sent as opaque handles, which are only valid for the
+/// client the [`VaultService`](crate::VaultService) returned them to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VaultCall {
+    /// [`SecretVault::secret_generate`](ockam_vault_core::SecretVault::secret_generate)
+    SecretGenerate { attributes: SecretAttributes },
+    /// [`SecretVault::secret_import`](ockam_vault_core::SecretVault::secret_import)
+    SecretImport {
+        secret: Vec<u8>,
+        attributes: SecretAttributes,
+    },
+    /// [`SecretVault::secret_export`](ockam_vault_core::SecretVault::secret_export)
+    SecretExport { secret: usize },
+    /// [`SecretVault::secret_attributes_get`](ockam_vault_core::SecretVault::secret_attributes_get)
+    SecretAttributesGet { secret: usize },
+    /// [`SecretVault::secret_public_key_get`](ockam_vault_core::SecretVault::secret_public_key_get)
+    SecretPublicKeyGet { secret: usize },
+    /// [`SecretVault::secret_destroy`](ockam_vault_core::SecretVault::secret_destroy)
+    SecretDestroy { secret: usize },
+    /// [`Hasher::sha256`](ockam_vault_core::Hasher::sha256)
+    Sha256 { data: Vec<u8> },
+    /// [`Hasher::hkdf_sha256`](ockam_vault_core::Hasher::hkdf_sha256)
+    HkdfSha256 {
+        salt: usize,
+        info: Vec<u8>,
+        ikm: Option<usize>,
+        output_attributes: Vec<SecretAttributes>,
+    },
+    /// [`KeyIdVault::get_secret_by_key_id`](ockam_vault_core::KeyIdVault::get_secret_by_key_id)
+    GetSecretByKeyId { key_id: String },
+    /// [`KeyIdVault::compute_key_id_for_public_key`](ockam_vault_core::KeyIdVault::compute_key_id_for_public_key)
+    ComputeKeyIdForPublicKey { public_key: Vec<u8> },
+    /// [`Signer::sign`](ockam_vault_core::Signer::sign)
+    Sign { secret: usize, data: Vec<u8> },
+    /// [`Verifier::verify`](ockam_vault_core::Verifier::verify)
+    Verify {
+        signature: Vec<u8>,
+        public_key: Vec<u8>,
+        key_type: SecretType,
+        data: Vec<u8>,
+    },
+    /// [`AsymmetricVault::ec_diffie_hellman`](ockam_vault_core::AsymmetricVault::ec_diffie_hellman)
+    EcDiffieHellman {
+        secret: usize,
+        peer_public_key: Vec<u8>,
+    },
+    /// The encrypt function of [`SymmetricVault`](ockam_vault_core::SymmetricVault) for an AEAD
+    AeadEncrypt {
+        aead: Aead,
+        secret: usize,
+        plaintext: Vec<u8>,
+        nonce: Vec<u8>,
+        aad: Vec<u8>,
+    },
+    /// The decrypt function of [`SymmetricVault`](ockam_vault_core::SymmetricVault) for an AEAD
+    AeadDecrypt {
+        aead: Aead,
+        secret: usize,
+        cipher_text: Vec<u8>,
+        nonce: Vec<u8>,
+        aad: Vec<u8>,
+    },
+    /// [`SecretSharingVault::secret_split`](ockam_vault_core::SecretSharingVault::secret_split)
+    SecretSplit {
+        secret: usize,
+        threshold: u8,
+        guardians: Vec<Vec<u8>>,
+    },
+    /// [`SecretSharingVault::secret_share_reencrypt`](ockam_vault_core::SecretSharingVault::secret_share_reencrypt)
+    SecretShareReencrypt {
+        guardian: usize,
+        share: Vec<u8>,
+        recipient: Vec<u8>,
+    },
+    /// [`SecretSharingVault::secret_recover`](ockam_vault_core::SecretSharingVault::secret_recover)
+    SecretRecover {
+        recipient: usize,
+        shares: Vec<Vec<u8>>,
+    },
+    /// [`SecretDerivationVault::secret_derive`](ockam_vault_core::SecretDerivationVault::secret_derive)
+    SecretDerive {
+        seed: usize,
+        path: String,
+        attributes: SecretAttributes,
+    },
+}
+
+/// The value returned by a vault trait function
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VaultResult {
+    Unit,
+    Secret(usize),
+    Secrets(Vec<usize>),
+    SecretAttributes(SecretAttributes),
+    SecretKey(Vec<u8>),
+    PublicKey(Vec<u8>),
+    Hash([u8; 32]),
+    KeyId(String),
+    Signature(Vec<u8>),
+    Buffer(Vec<u8>),
+    Buffers(Vec<Vec<u8>>),
+}
+
+/// A request sent to a [`VaultService`](crate::VaultService)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VaultRequest {
+    /// Identifies the request to the sender
+    pub id: u64,
+    /// The function to call
+    pub call: VaultCall,
+}
+
+impl TypedMessage for VaultRequest {
+    const TYPE_ID: &'static str = "ockam.vault.request";
+    const VERSION: u16 = 1;
+}
+
+/// The answer of a [`VaultService`](crate::VaultService) to a request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VaultResponse {
+    /// The id of the request
+    pub id: u64,
+    /// The returned value, or the code of the error
+    pub result: Result<VaultResult, u32>,
+}
+
+impl TypedMessage for VaultResponse {
+    const TYPE_ID: &'static str = "ockam.vault.response";
+    const VERSION: u16 = 1;
+}
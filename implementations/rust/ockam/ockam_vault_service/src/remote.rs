@@ -0,0 +1,633 @@
+use crate::{Aead, VaultCall, VaultRequest, VaultResponse, VaultResult, VaultServiceError};
+use ockam_core::{async_trait::async_trait, Address, Error, Result, Route, Typed};
+use ockam_node::Context;
+use ockam_vault_core::{
+    AsymmetricVault, AsyncAsymmetricVault, AsyncHasher, AsyncKeyIdVault,
+    AsyncSecretDerivationVault, AsyncSecretSharingVault, AsyncSecretVault, AsyncSigner,
+    AsyncSymmetricVault, AsyncVerifier, Buffer, Hasher, KeyId, KeyIdVault, PublicKey, Secret,
+    SecretAttributes, SecretDerivationVault, SecretKey, SecretSharingVault, SecretType,
+    SecretVault, Signer, SmallBuffer, SymmetricVault, Verifier,
+};
+use std::convert::TryInto;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::{runtime::Handle, sync::Mutex};
+use zeroize::Zeroize;
+
+/// How long to wait for a vault service to answer a request
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A vault served by a [`VaultService`](crate::VaultService)
+/// reachable via a route
+///
+/// Workers should use the asynchronous vault traits.  With the
+/// synchronous traits each call blocks the calling thread until the
+/// vault service answers, which is only supported on the
+/// multi-threaded runtime of a node, or on threads outside of it.
+pub struct RemoteVault {
+    ctx: Mutex<Context>,
+    route: Route,
+    rt: Handle,
+}
+
+impl RemoteVault {
+    /// Create a client for the vault service at `route`
+    ///
+    /// Responses are received at a new random address of the node of
+    /// `ctx`.
+    pub async fn create<R: Into<Route>>(ctx: &Context, route: R) -> Result<Self> {
+        let ctx = ctx.new_context(Address::random(0)).await?;
+        Ok(Self {
+            ctx: Mutex::new(ctx),
+            route: route.into(),
+            rt: Handle::current(),
+        })
+    }
+
+    async fn request(&self, call: VaultCall) -> Result<VaultResult> {
+        static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+        let id = REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+
+        let mut ctx = self.ctx.lock().await;
+        ctx.send_message(self.route.clone(), Typed::new(VaultRequest { id, call }))
+            .await?;
+
+        let response = tokio::time::timeout(
+            RESPONSE_TIMEOUT,
+            ctx.receive_match(|r: &Typed<VaultResponse>| r.id == id),
+        )
+        .await
+        .map_err(|_| VaultServiceError::ResponseTimeout)??
+        .take()
+        .take();
+
+        response
+            .result
+            .map_err(|code| Error::new(code, VaultServiceError::DOMAIN_NAME))
+    }
+
+    /// Wait for a request from synchronous code
+    fn block<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::task::block_in_place(|| self.rt.block_on(f))
+    }
+}
+
+fn invalid_response() -> Error {
+    VaultServiceError::InvalidResponse.into()
+}
+
+#[async_trait]
+impl AsyncSecretVault for RemoteVault {
+    async fn secret_generate(&self, attributes: SecretAttributes) -> Result<Secret> {
+        match self
+            .request(VaultCall::SecretGenerate { attributes })
+            .await?
+        {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_import(&self, secret: &[u8], attributes: SecretAttributes) -> Result<Secret> {
+        let call = VaultCall::SecretImport {
+            secret: secret.to_vec(),
+            attributes,
+        };
+        match self.request(call).await? {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_export(&self, context: &Secret) -> Result<SecretKey> {
+        let call = VaultCall::SecretExport {
+            secret: context.index(),
+        };
+        match self.request(call).await? {
+            VaultResult::SecretKey(key) => Ok(SecretKey::new(key)),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_attributes_get(&self, context: &Secret) -> Result<SecretAttributes> {
+        let call = VaultCall::SecretAttributesGet {
+            secret: context.index(),
+        };
+        match self.request(call).await? {
+            VaultResult::SecretAttributes(attributes) => Ok(attributes),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_public_key_get(&self, context: &Secret) -> Result<PublicKey> {
+        let call = VaultCall::SecretPublicKeyGet {
+            secret: context.index(),
+        };
+        match self.request(call).await? {
+            VaultResult::PublicKey(key) => Ok(PublicKey::new(key)),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_destroy(&self, context: Secret) -> Result<()> {
+        let call = VaultCall::SecretDestroy {
+            secret: context.index(),
+        };
+        match self.request(call).await? {
+            VaultResult::Unit => Ok(()),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncHasher for RemoteVault {
+    async fn sha256(&self, data: &[u8]) -> Result<[u8; 32]> {
+        let call = VaultCall::Sha256 {
+            data: data.to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Hash(hash) => Ok(hash),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn hkdf_sha256(
+        &self,
+        salt: &Secret,
+        info: &[u8],
+        ikm: Option<&Secret>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<Secret>> {
+        let call = VaultCall::HkdfSha256 {
+            salt: salt.index(),
+            info: info.to_vec(),
+            ikm: ikm.map(Secret::index),
+            output_attributes,
+        };
+        match self.request(call).await? {
+            VaultResult::Secrets(indexes) => Ok(indexes.into_iter().map(Secret::new).collect()),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncKeyIdVault for RemoteVault {
+    async fn get_secret_by_key_id(&self, key_id: &str) -> Result<Secret> {
+        let call = VaultCall::GetSecretByKeyId {
+            key_id: key_id.into(),
+        };
+        match self.request(call).await? {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn compute_key_id_for_public_key(&self, public_key: &PublicKey) -> Result<KeyId> {
+        let call = VaultCall::ComputeKeyIdForPublicKey {
+            public_key: public_key.as_ref().to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::KeyId(key_id) => Ok(key_id),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncSigner for RemoteVault {
+    async fn sign(&self, secret_key: &Secret, data: &[u8]) -> Result<[u8; 64]> {
+        let call = VaultCall::Sign {
+            secret: secret_key.index(),
+            data: data.to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Signature(signature) => signature
+                .as_slice()
+                .try_into()
+                .map_err(|_| invalid_response()),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncVerifier for RemoteVault {
+    async fn verify(
+        &self,
+        signature: &[u8; 64],
+        public_key: &[u8],
+        key_type: SecretType,
+        data: &[u8],
+    ) -> Result<()> {
+        let call = VaultCall::Verify {
+            signature: signature.to_vec(),
+            public_key: public_key.to_vec(),
+            key_type,
+            data: data.to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Unit => Ok(()),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncAsymmetricVault for RemoteVault {
+    async fn ec_diffie_hellman(&self, context: &Secret, peer_public_key: &[u8]) -> Result<Secret> {
+        let call = VaultCall::EcDiffieHellman {
+            secret: context.index(),
+            peer_public_key: peer_public_key.to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+impl RemoteVault {
+    async fn aead(&self, call: VaultCall) -> Result<Buffer<u8>> {
+        match self.request(call).await? {
+            VaultResult::Buffer(buffer) => Ok(buffer),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn aead_encrypt(
+        &self,
+        aead: Aead,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead(VaultCall::AeadEncrypt {
+            aead,
+            secret: context.index(),
+            plaintext: plaintext.to_vec(),
+            nonce: nonce.to_vec(),
+            aad: aad.to_vec(),
+        })
+        .await
+    }
+
+    async fn aead_decrypt(
+        &self,
+        aead: Aead,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead(VaultCall::AeadDecrypt {
+            aead,
+            secret: context.index(),
+            cipher_text: cipher_text.to_vec(),
+            nonce: nonce.to_vec(),
+            aad: aad.to_vec(),
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl AsyncSymmetricVault for RemoteVault {
+    async fn aead_aes_gcm_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_encrypt(Aead::AesGcm, context, plaintext, nonce, aad)
+            .await
+    }
+
+    async fn aead_aes_gcm_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_decrypt(Aead::AesGcm, context, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn aead_chacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_encrypt(Aead::ChaCha20Poly1305, context, plaintext, nonce, aad)
+            .await
+    }
+
+    async fn aead_chacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_decrypt(Aead::ChaCha20Poly1305, context, cipher_text, nonce, aad)
+            .await
+    }
+
+    async fn aead_xchacha20_poly1305_encrypt(
+        &self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_encrypt(Aead::XChaCha20Poly1305, context, plaintext, nonce, aad)
+            .await
+    }
+
+    async fn aead_xchacha20_poly1305_decrypt(
+        &self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.aead_decrypt(Aead::XChaCha20Poly1305, context, cipher_text, nonce, aad)
+            .await
+    }
+}
+
+#[async_trait]
+impl AsyncSecretSharingVault for RemoteVault {
+    async fn secret_split(
+        &self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> Result<SmallBuffer<Buffer<u8>>> {
+        let call = VaultCall::SecretSplit {
+            secret: context.index(),
+            threshold,
+            guardians: guardians.iter().map(|g| g.as_ref().to_vec()).collect(),
+        };
+        match self.request(call).await? {
+            VaultResult::Buffers(shares) => Ok(shares),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_share_reencrypt(
+        &self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> Result<Buffer<u8>> {
+        let call = VaultCall::SecretShareReencrypt {
+            guardian: guardian.index(),
+            share: share.to_vec(),
+            recipient: recipient.as_ref().to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Buffer(share) => Ok(share),
+            _ => Err(invalid_response()),
+        }
+    }
+
+    async fn secret_recover(&self, recipient: &Secret, shares: &[Buffer<u8>]) -> Result<Secret> {
+        let call = VaultCall::SecretRecover {
+            recipient: recipient.index(),
+            shares: shares.to_vec(),
+        };
+        match self.request(call).await? {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncSecretDerivationVault for RemoteVault {
+    async fn secret_derive(
+        &self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> Result<Secret> {
+        let call = VaultCall::SecretDerive {
+            seed: seed.index(),
+            path: path.to_string(),
+            attributes,
+        };
+        match self.request(call).await? {
+            VaultResult::Secret(index) => Ok(Secret::new(index)),
+            _ => Err(invalid_response()),
+        }
+    }
+}
+
+impl Zeroize for RemoteVault {
+    fn zeroize(&mut self) {
+        // Key material stays in the remote vault
+    }
+}
+
+impl SecretVault for RemoteVault {
+    fn secret_generate(&mut self, attributes: SecretAttributes) -> Result<Secret> {
+        self.block(AsyncSecretVault::secret_generate(self, attributes))
+    }
+
+    fn secret_import(&mut self, secret: &[u8], attributes: SecretAttributes) -> Result<Secret> {
+        self.block(AsyncSecretVault::secret_import(self, secret, attributes))
+    }
+
+    fn secret_export(&mut self, context: &Secret) -> Result<SecretKey> {
+        self.block(AsyncSecretVault::secret_export(self, context))
+    }
+
+    fn secret_attributes_get(&mut self, context: &Secret) -> Result<SecretAttributes> {
+        self.block(AsyncSecretVault::secret_attributes_get(self, context))
+    }
+
+    fn secret_public_key_get(&mut self, context: &Secret) -> Result<PublicKey> {
+        self.block(AsyncSecretVault::secret_public_key_get(self, context))
+    }
+
+    fn secret_destroy(&mut self, context: Secret) -> Result<()> {
+        self.block(AsyncSecretVault::secret_destroy(self, context))
+    }
+}
+
+impl Hasher for RemoteVault {
+    fn sha256(&self, data: &[u8]) -> Result<[u8; 32]> {
+        self.block(AsyncHasher::sha256(self, data))
+    }
+
+    fn hkdf_sha256(
+        &mut self,
+        salt: &Secret,
+        info: &[u8],
+        ikm: Option<&Secret>,
+        output_attributes: SmallBuffer<SecretAttributes>,
+    ) -> Result<SmallBuffer<Secret>> {
+        self.block(AsyncHasher::hkdf_sha256(
+            self,
+            salt,
+            info,
+            ikm,
+            output_attributes,
+        ))
+    }
+}
+
+impl KeyIdVault for RemoteVault {
+    fn get_secret_by_key_id(&self, key_id: &str) -> Result<Secret> {
+        self.block(AsyncKeyIdVault::get_secret_by_key_id(self, key_id))
+    }
+
+    fn compute_key_id_for_public_key(&self, public_key: &PublicKey) -> Result<KeyId> {
+        self.block(AsyncKeyIdVault::compute_key_id_for_public_key(
+            self, public_key,
+        ))
+    }
+}
+
+impl Signer for RemoteVault {
+    fn sign(&mut self, secret_key: &Secret, data: &[u8]) -> Result<[u8; 64]> {
+        self.block(AsyncSigner::sign(self, secret_key, data))
+    }
+}
+
+impl Verifier for RemoteVault {
+    fn verify(
+        &mut self,
+        signature: &[u8; 64],
+        public_key: &[u8],
+        key_type: SecretType,
+        data: &[u8],
+    ) -> Result<()> {
+        self.block(AsyncVerifier::verify(
+            self, signature, public_key, key_type, data,
+        ))
+    }
+}
+
+impl AsymmetricVault for RemoteVault {
+    fn ec_diffie_hellman(&mut self, context: &Secret, peer_public_key: &[u8]) -> Result<Secret> {
+        self.block(AsyncAsymmetricVault::ec_diffie_hellman(
+            self,
+            context,
+            peer_public_key,
+        ))
+    }
+}
+
+impl SymmetricVault for RemoteVault {
+    fn aead_aes_gcm_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_encrypt(Aead::AesGcm, context, plaintext, nonce, aad))
+    }
+
+    fn aead_aes_gcm_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_decrypt(Aead::AesGcm, context, cipher_text, nonce, aad))
+    }
+
+    fn aead_chacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_encrypt(Aead::ChaCha20Poly1305, context, plaintext, nonce, aad))
+    }
+
+    fn aead_chacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_decrypt(Aead::ChaCha20Poly1305, context, cipher_text, nonce, aad))
+    }
+
+    fn aead_xchacha20_poly1305_encrypt(
+        &mut self,
+        context: &Secret,
+        plaintext: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_encrypt(Aead::XChaCha20Poly1305, context, plaintext, nonce, aad))
+    }
+
+    fn aead_xchacha20_poly1305_decrypt(
+        &mut self,
+        context: &Secret,
+        cipher_text: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+    ) -> Result<Buffer<u8>> {
+        self.block(self.aead_decrypt(Aead::XChaCha20Poly1305, context, cipher_text, nonce, aad))
+    }
+}
+
+impl SecretSharingVault for RemoteVault {
+    fn secret_split(
+        &mut self,
+        context: &Secret,
+        threshold: u8,
+        guardians: &[PublicKey],
+    ) -> Result<SmallBuffer<Buffer<u8>>> {
+        self.block(AsyncSecretSharingVault::secret_split(
+            self, context, threshold, guardians,
+        ))
+    }
+
+    fn secret_share_reencrypt(
+        &mut self,
+        guardian: &Secret,
+        share: &[u8],
+        recipient: &PublicKey,
+    ) -> Result<Buffer<u8>> {
+        self.block(AsyncSecretSharingVault::secret_share_reencrypt(
+            self, guardian, share, recipient,
+        ))
+    }
+
+    fn secret_recover(&mut self, recipient: &Secret, shares: &[Buffer<u8>]) -> Result<Secret> {
+        self.block(AsyncSecretSharingVault::secret_recover(
+            self, recipient, shares,
+        ))
+    }
+}
+
+impl SecretDerivationVault for RemoteVault {
+    fn secret_derive(
+        &mut self,
+        seed: &Secret,
+        path: &str,
+        attributes: SecretAttributes,
+    ) -> Result<Secret> {
+        self.block(AsyncSecretDerivationVault::secret_derive(
+            self, seed, path, attributes,
+        ))
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{Aead, VaultCall, VaultRequest, VaultResponse, VaultResult, VaultServiceError};
+use ockam_core::{
+    async_trait::async_trait, AccessControl, LocalInfo, Result, Route, Routed, Typed, Worker,
+};
+use ockam_node::Context;
+use ockam_vault_core::{
+    AsymmetricVault, Hasher, KeyIdVault, PublicKey, Secret, SecretDerivationVault,
+    SecretSharingVault, SecretVault, Signer, SymmetricVault, Verifier,
+};
+use rand::{thread_rng, Rng};
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::convert::TryInto;
+use std::sync::{Arc, Mutex};
+
+/// Vault with the functionality served by a [`VaultService`]
+pub trait ServiceVault:
+    SecretVault
+    + Hasher
+    + KeyIdVault
+    + Signer
+    + Verifier
+    + SymmetricVault
+    + AsymmetricVault
+    + SecretSharingVault
+    + SecretDerivationVault
+    + Send
+{
+}
+
+impl<D> ServiceVault for D where
+    D: SecretVault
+        + Hasher
+        + KeyIdVault
+        + Signer
+        + Verifier
+        + SymmetricVault
+        + AsymmetricVault
+        + SecretSharingVault
+        + SecretDerivationVault
+        + Send
+{
+}
+
+/// A worker serving a vault to other workers and nodes
+///
+/// Each [`VaultRequest`] is answered with a [`VaultResponse`] sent to
+/// the return route of the request.  The vault may still be used
+/// directly by the node holding it.
+///
+/// Clients are told apart by the return routes of their requests.
+/// Secrets are returned to a client as random handles which no other
+/// client can use, so clients sharing the service can't reach each
+/// other's keys.  Secrets of the vault which were not returned to a
+/// client aren't reachable through the service at all.
+pub struct VaultService {
+    vault: Arc<Mutex<dyn ServiceVault>>,
+    access_control: Arc<dyn AccessControl>,
+    clients: BTreeMap<Route, Handles>,
+}
+
+/// The handles of the secrets returned to one client
+#[derive(Default)]
+struct Handles(BTreeMap<usize, usize>);
+
+impl Handles {
+    fn get(&self, handle: usize) -> Result<Secret> {
+        self.0
+            .get(&handle)
+            .map(|index| Secret::new(*index))
+            .ok_or_else(|| VaultServiceError::UnknownSecret.into())
+    }
+
+    fn find(&self, secret: &Secret) -> Result<usize> {
+        self.0
+            .iter()
+            .find(|(_, index)| **index == secret.index())
+            .map(|(handle, _)| *handle)
+            .ok_or_else(|| VaultServiceError::UnknownSecret.into())
+    }
+
+    fn insert(&mut self, secret: Secret) -> usize {
+        if let Ok(handle) = self.find(&secret) {
+            return handle;
+        }
+        let mut rng = thread_rng();
+        loop {
+            let handle = rng.gen();
+            if let Entry::Vacant(entry) = self.0.entry(handle) {
+                entry.insert(secret.index());
+                return handle;
+            }
+        }
+    }
+}
+
+/// Hands the policy of a [`VaultService`] to its relay
+struct SharedAccessControl(Arc<dyn AccessControl>);
+
+impl AccessControl for SharedAccessControl {
+    fn is_authorized(&self, return_route: &Route, local: &LocalInfo) -> bool {
+        self.0.is_authorized(return_route, local)
+    }
+}
+
+impl VaultService {
+    /// Serve `vault` to the senders of messages accepted by `access_control`
+    pub fn new(vault: Arc<Mutex<dyn ServiceVault>>, access_control: impl AccessControl) -> Self {
+        Self {
+            vault,
+            access_control: Arc::new(access_control),
+            clients: BTreeMap::new(),
+        }
+    }
+
+    fn handle_call(&mut self, client: Route, call: VaultCall) -> Result<VaultResult> {
+        let mut vault = self.vault.lock().unwrap();
+        let handles = self.clients.entry(client).or_default();
+        let result = match call {
+            VaultCall::SecretGenerate { attributes } => {
+                VaultResult::Secret(handles.insert(vault.secret_generate(attributes)?))
+            }
+            VaultCall::SecretImport { secret, attributes } => {
+                VaultResult::Secret(handles.insert(vault.secret_import(&secret, attributes)?))
+            }
+            VaultCall::SecretExport { secret } => {
+                let key = vault.secret_export(&handles.get(secret)?)?;
+                VaultResult::SecretKey(key.as_ref().to_vec())
+            }
+            VaultCall::SecretAttributesGet { secret } => {
+                VaultResult::SecretAttributes(vault.secret_attributes_get(&handles.get(secret)?)?)
+            }
+            VaultCall::SecretPublicKeyGet { secret } => {
+                let public_key = vault.secret_public_key_get(&handles.get(secret)?)?;
+                VaultResult::PublicKey(public_key.as_ref().to_vec())
+            }
+            VaultCall::SecretDestroy { secret } => {
+                vault.secret_destroy(handles.get(secret)?)?;
+                handles.0.remove(&secret);
+                VaultResult::Unit
+            }
+            VaultCall::Sha256 { data } => VaultResult::Hash(vault.sha256(&data)?),
+            VaultCall::HkdfSha256 {
+                salt,
+                info,
+                ikm,
+                output_attributes,
+            } => {
+                let ikm = ikm.map(|ikm| handles.get(ikm)).transpose()?;
+                let secrets = vault.hkdf_sha256(
+                    &handles.get(salt)?,
+                    &info,
+                    ikm.as_ref(),
+                    output_attributes,
+                )?;
+                VaultResult::Secrets(secrets.into_iter().map(|s| handles.insert(s)).collect())
+            }
+            VaultCall::GetSecretByKeyId { key_id } => {
+                VaultResult::Secret(handles.find(&vault.get_secret_by_key_id(&key_id)?)?)
+            }
+            VaultCall::ComputeKeyIdForPublicKey { public_key } => {
+                let public_key = PublicKey::new(public_key);
+                VaultResult::KeyId(vault.compute_key_id_for_public_key(&public_key)?)
+            }
+            VaultCall::Sign { secret, data } => {
+                let signature = vault.sign(&handles.get(secret)?, &data)?;
+                VaultResult::Signature(signature.to_vec())
+            }
+            VaultCall::Verify {
+                signature,
+                public_key,
+                key_type,
+                data,
+            } => {
+                let signature: [u8; 64] = signature
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| VaultServiceError::InvalidRequest)?;
+                vault.verify(&signature, &public_key, key_type, &data)?;
+                VaultResult::Unit
+            }
+            VaultCall::EcDiffieHellman {
+                secret,
+                peer_public_key,
+            } => {
+                let secret = vault.ec_diffie_hellman(&handles.get(secret)?, &peer_public_key)?;
+                VaultResult::Secret(handles.insert(secret))
+            }
+            VaultCall::AeadEncrypt {
+                aead,
+                secret,
+                plaintext,
+                nonce,
+                aad,
+            } => {
+                let secret = handles.get(secret)?;
+                VaultResult::Buffer(match aead {
+                    Aead::AesGcm => {
+                        vault.aead_aes_gcm_encrypt(&secret, &plaintext, &nonce, &aad)?
+                    }
+                    Aead::ChaCha20Poly1305 => {
+                        vault.aead_chacha20_poly1305_encrypt(&secret, &plaintext, &nonce, &aad)?
+                    }
+                    Aead::XChaCha20Poly1305 => {
+                        vault.aead_xchacha20_poly1305_encrypt(&secret, &plaintext, &nonce, &aad)?
+                    }
+                })
+            }
+            VaultCall::AeadDecrypt {
+                aead,
+                secret,
+                cipher_text,
+                nonce,
+                aad,
+            } => {
+                let secret = handles.get(secret)?;
+                VaultResult::Buffer(match aead {
+                    Aead::AesGcm => {
+                        vault.aead_aes_gcm_decrypt(&secret, &cipher_text, &nonce, &aad)?
+                    }
+                    Aead::ChaCha20Poly1305 => {
+                        vault.aead_chacha20_poly1305_decrypt(&secret, &cipher_text, &nonce, &aad)?
+                    }
+                    Aead::XChaCha20Poly1305 => vault.aead_xchacha20_poly1305_decrypt(
+                        &secret,
+                        &cipher_text,
+                        &nonce,
+                        &aad,
+                    )?,
+                })
+            }
+            VaultCall::SecretSplit {
+                secret,
+                threshold,
+                guardians,
+            } => {
+                let guardians: Vec<_> = guardians.into_iter().map(PublicKey::new).collect();
+                VaultResult::Buffers(vault.secret_split(
+                    &handles.get(secret)?,
+                    threshold,
+                    &guardians,
+                )?)
+            }
+            VaultCall::SecretShareReencrypt {
+                guardian,
+                share,
+                recipient,
+            } => VaultResult::Buffer(vault.secret_share_reencrypt(
+                &handles.get(guardian)?,
+                &share,
+                &PublicKey::new(recipient),
+            )?),
+            VaultCall::SecretRecover { recipient, shares } => {
+                let secret = vault.secret_recover(&handles.get(recipient)?, &shares)?;
+                VaultResult::Secret(handles.insert(secret))
+            }
+            VaultCall::SecretDerive {
+                seed,
+                path,
+                attributes,
+            } => {
+                let secret = vault.secret_derive(&handles.get(seed)?, &path, attributes)?;
+                VaultResult::Secret(handles.insert(secret))
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl Worker for VaultService {
+    type Message = Typed<VaultRequest>;
+    type Context = Context;
+
+    fn access_control(&self) -> Box<dyn AccessControl> {
+        Box::new(SharedAccessControl(self.access_control.clone()))
+    }
+
+    async fn handle_message(
+        &mut self,
+        ctx: &mut Context,
+        msg: Routed<Typed<VaultRequest>>,
+    ) -> Result<()> {
+        let reply = msg.reply();
+        let VaultRequest { id, call } = msg.take().take();
+
+        let result = self.handle_call(reply.clone(), call).map_err(|e| {
+            debug!("Vault request {} failed: {}", id, e);
+            e.code()
+        });
+        ctx.send_message(reply, Typed::new(VaultResponse { id, result }))
+            .await
+    }
+}